@@ -1,90 +1,71 @@
+use manteau_indexer_helper::magnet::info_hash;
+use manteau_indexer_prelude::capabilities::Capabilities;
+use manteau_indexer_prelude::http::HttpClient;
 use manteau_indexer_prelude::{
-    Category, Indexer, IndexerBuilder, IndexerEntry, IndexerError, IndexerErrorReason,
-    IndexerResult,
+    Category, DeferredLink, Indexer, IndexerBuilder, IndexerEntry, IndexerError,
+    IndexerErrorReason, IndexerResult, SearchRequest,
 };
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 
 mod date;
 mod search;
 mod torrent;
 
 const BASE_URL: &str = "https://1337x.to";
+const MAX_CONCURRENCY: usize = 4;
 pub const NAME: &str = "1337x";
 
-async fn fetch_page(base_url: &str, path: &str) -> Result<String, IndexerError> {
-    let url = format!("{base_url}{path}");
-    let req = reqwest::get(&url).await.map_err(|cause| {
-        IndexerError::new(
-            NAME,
-            IndexerErrorReason::UnableToQuery {
-                url: url.clone(),
-                cause: cause.to_string(),
-            },
-        )
-    })?;
-    req.text().await.map_err(|err| {
-        IndexerError::new(
-            NAME,
-            IndexerErrorReason::UnableToRead {
-                url,
-                cause: err.to_string(),
-            },
-        )
-    })
-}
-
-async fn resolve_magnet(
-    base_url: &str,
-    mut entry: IndexerEntry,
-) -> Result<IndexerEntry, IndexerError> {
-    let html = fetch_page(base_url, &entry.magnet).await?;
-    entry.magnet = torrent::parse_magnet(html.as_str())?;
-    Ok(entry)
-}
-
-async fn search(base_url: &str, path: &str) -> IndexerResult {
-    let html = match fetch_page(base_url, path).await {
-        Ok(value) => value,
-        Err(error) => return IndexerResult::from(error),
-    };
-
-    let IndexerResult { entries, errors } = search::parse(base_url, html.as_str());
-    let mut results = IndexerResult::from(errors);
-
-    let entries = futures::future::join_all(
-        entries
-            .into_iter()
-            .map(|entry| resolve_magnet(base_url, entry)),
-    )
-    .await;
-
-    for entry in entries {
-        match entry {
-            Ok(found) => results.entries.push(found),
-            Err(error) => results.errors.push(error),
-        };
+/// Name of the category in the urls of the site.
+fn category_path(category: Category) -> &'static str {
+    match category {
+        Category::Audio | Category::Music => "Music",
+        Category::Movie => "Movies",
+        Category::Tv => "TV",
+        Category::Book => "Other",
     }
+}
 
-    results
+/// Lets the torrent page be fetched when the entry is downloaded.
+fn defer_magnet(name: &str, mut entry: IndexerEntry) -> IndexerEntry {
+    entry.deferred = Some(DeferredLink {
+        indexer: name.to_string(),
+        token: std::mem::take(&mut entry.magnet),
+    });
+    entry
 }
 
 #[derive(Debug, serde::Deserialize)]
 pub struct Indexer1337xConfig {
     #[serde(default = "Indexer1337xConfig::default_base_url")]
     pub base_url: String,
+    /// Only fetch the torrent pages when the entries are downloaded.
+    #[serde(default)]
+    pub lazy_magnet: bool,
+    /// Maximum number of torrent pages fetched at the same time.
+    #[serde(default = "Indexer1337xConfig::default_max_concurrency")]
+    pub max_concurrency: usize,
 }
 
 impl Indexer1337xConfig {
     fn default_base_url() -> String {
         BASE_URL.into()
     }
+
+    fn default_max_concurrency() -> usize {
+        MAX_CONCURRENCY
+    }
 }
 
 impl IndexerBuilder for Indexer1337xConfig {
-    fn build(self, name: String) -> Box<dyn Indexer + Send + Sync + 'static> {
+    fn build(self, name: String, client: HttpClient) -> Box<dyn Indexer + Send + Sync + 'static> {
         tracing::info!("building 1337x indexer named {name:?}");
         Box::new(Indexer1337x {
             name,
             base_url: self.base_url,
+            lazy_magnet: self.lazy_magnet,
+            client,
+            detail_permits: Arc::new(Semaphore::new(self.max_concurrency.max(1))),
         })
     }
 }
@@ -93,6 +74,10 @@ impl IndexerBuilder for Indexer1337xConfig {
 pub struct Indexer1337x {
     name: String,
     base_url: String,
+    lazy_magnet: bool,
+    client: HttpClient,
+    // bounds the number of torrent pages fetched at the same time
+    detail_permits: Arc<Semaphore>,
 }
 
 impl Default for Indexer1337x {
@@ -106,37 +91,125 @@ impl Indexer1337x {
         Self {
             name: "1337x".into(),
             base_url: base_url.into(),
+            lazy_magnet: false,
+            client: HttpClient::default(),
+            detail_permits: Arc::new(Semaphore::new(MAX_CONCURRENCY)),
+        }
+    }
+
+    pub fn with_lazy_magnet(mut self, lazy_magnet: bool) -> Self {
+        self.lazy_magnet = lazy_magnet;
+        self
+    }
+
+    async fn fetch_page(&self, path: &str) -> Result<String, IndexerError> {
+        let url = format!("{}{path}", self.base_url);
+        self.client.get_text(NAME, url).await
+    }
+
+    async fn resolve_magnet(&self, mut entry: IndexerEntry) -> Result<IndexerEntry, IndexerError> {
+        let _permit = self.detail_permits.acquire().await;
+        let html = self.fetch_page(&entry.magnet).await?;
+        entry.magnet = torrent::parse_magnet(html.as_str())?;
+        entry.info_hash = info_hash(&entry.magnet);
+        entry.files = torrent::parse_files(html.as_str());
+        Ok(entry)
+    }
+
+    async fn search_page(&self, path: &str) -> IndexerResult {
+        let html = match self.fetch_page(path).await {
+            Ok(value) => value,
+            Err(error) => return IndexerResult::from(error),
+        };
+
+        let IndexerResult { entries, errors } = search::parse(&self.base_url, html.as_str());
+        let mut results = IndexerResult::from(errors);
+
+        if self.lazy_magnet {
+            results.entries = entries
+                .into_iter()
+                .map(|entry| defer_magnet(&self.name, entry))
+                .collect();
+            return results;
+        }
+
+        let entries =
+            futures::future::join_all(entries.into_iter().map(|entry| self.resolve_magnet(entry)))
+                .await;
+
+        for entry in entries {
+            match entry {
+                Ok(found) => results.entries.push(found),
+                Err(error) => results.errors.push(error),
+            };
         }
+
+        results
     }
 }
 
 #[async_trait::async_trait]
 impl Indexer for Indexer1337x {
-    async fn search(&self, query: &str) -> IndexerResult {
+    async fn search(&self, request: &SearchRequest) -> IndexerResult {
+        let query = request.keywords();
+        if query.is_empty() {
+            tracing::debug!("{} skipping search without keywords", self.name);
+            return IndexerResult::default();
+        }
         tracing::debug!("{} searching {query:?}", self.name);
-        let query = urlencoding::encode(query);
-        let path = format!("/search/{query}/1/");
+        let query = urlencoding::encode(&query);
+        let path = match request.category() {
+            Some(category) => format!("/category-search/{query}/{}/1/", category_path(category)),
+            None => format!("/search/{query}/1/"),
+        };
 
-        search(self.base_url.as_str(), path.as_str()).await
+        self.search_page(path.as_str()).await
     }
 
     async fn feed(&self, category: Category) -> IndexerResult {
         tracing::debug!("{} fetching feed for {category:?}", self.name);
-        let path = match category {
-            Category::Audio | Category::Music => "/cat/Music/1/",
-            Category::Movie => "/cat/Movies/1/",
-            Category::Tv => "/cat/TV/1/",
-            Category::Book => "/cat/Other/1/",
-        };
+        let path = format!("/cat/{}/1/", category_path(category));
 
-        search(self.base_url.as_str(), path).await
+        self.search_page(&path).await
+    }
+
+    async fn resolve(&self, token: &str) -> Result<String, IndexerError> {
+        tracing::debug!("{} resolving {token:?}", self.name);
+        // the token is a path on the site, don't let it go anywhere else
+        if !token.starts_with("/torrent/") {
+            return Err(IndexerError::new(
+                NAME,
+                IndexerErrorReason::EntryLinkNotResolvable,
+            ));
+        }
+        let html = self.fetch_page(token).await?;
+        torrent::parse_magnet(html.as_str())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        [
+            Category::Movie,
+            Category::Tv,
+            Category::Music,
+            Category::Book,
+        ]
+        .into_iter()
+        .fold(
+            Capabilities::default().with_keyword_searches(),
+            |caps, category| caps.with_category(category_path(category), category),
+        )
+    }
+
+    fn feed_interval(&self) -> std::time::Duration {
+        // every feed fetches the detail page of each entry
+        std::time::Duration::from_secs(900)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Indexer1337x;
-    use manteau_indexer_prelude::Indexer;
+    use manteau_indexer_prelude::{Category, Indexer, SearchRequest};
 
     #[tokio::test]
     async fn basic_search() {
@@ -166,13 +239,21 @@ mod tests {
             .create_async()
             .await;
 
-        let results = indexer.search("how i met your mother").await;
+        let results = indexer
+            .search(&SearchRequest::new("how i met your mother"))
+            .await;
         assert_eq!(results.entries.len(), 20);
         assert_eq!(results.errors.len(), 0);
         assert_eq!(results.entries[0].name, "How I Met Your Mother - Season 4");
         assert_eq!(results.entries[0].seeders, 26);
         assert_eq!(results.entries[0].leechers, 9);
         assert_eq!(results.entries[0].size.to_string(), "4.1 GB");
+        assert_eq!(results.entries[0].uploader.as_deref(), Some("xbex"));
+        assert_eq!(results.entries[0].files.len(), 24);
+        assert_eq!(
+            results.entries[0].files[0],
+            "How.I.Met.Your.Mother.S04E02.PROPER.HDTV.XviD-SAiNTS.[VTV].avi"
+        );
         assert_eq!(
             results.entries[1].name,
             "How I Met Your Mother S01-S09 COMPLETE DVDrip mixed"
@@ -189,4 +270,51 @@ mod tests {
         search_page.assert_async().await;
         result_page.assert_async().await;
     }
+
+    #[tokio::test]
+    async fn lazy_search() {
+        let mut server = mockito::Server::new_async().await;
+        let indexer = Indexer1337x::new(server.url().as_str()).with_lazy_magnet(true);
+
+        let search_page = server
+            .mock(
+                "GET",
+                "/category-search/how%20i%20met%20your%20mother/TV/1/",
+            )
+            .with_status(200)
+            .with_header("content-type", "text/html")
+            .with_body(include_str!("./search.html"))
+            .create_async()
+            .await;
+
+        let result_page = server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex(r"^/torrent/(\d+)/(.*)/$".to_string()),
+            )
+            .with_status(200)
+            .with_header("content-type", "text/html")
+            .with_body(
+                include_str!("./torrent.html")
+                    .replace("%TORRENT_NAME%", "How I Met Your Mother - Season 4"),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let request = SearchRequest::new("how i met your mother").with_category(Category::Tv);
+        let results = indexer.search(&request).await;
+        assert_eq!(results.entries.len(), 20);
+        assert_eq!(results.errors.len(), 0);
+        let deferred = results.entries[0].deferred.clone().unwrap();
+        assert_eq!(deferred.indexer, "1337x");
+        assert!(results.entries[0].magnet.is_empty());
+
+        let magnet = indexer.resolve(&deferred.token).await.unwrap();
+        assert!(magnet.starts_with("magnet:?"));
+        assert!(indexer.resolve("/../admin").await.is_err());
+
+        search_page.assert_async().await;
+        result_page.assert_async().await;
+    }
 }
@@ -13,6 +13,7 @@ static SEEDS_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td.seeds")
 static LEECHERS_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td.leeches").unwrap());
 static SIZE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td.size").unwrap());
 static DATE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td.coll-date").unwrap());
+static UPLOADER_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td.coll-5 a").unwrap());
 
 fn parse_link_element<'a>(elt: &'a ElementRef) -> Result<ElementRef<'a>, IndexerError> {
     elt.select(&NAME_SELECTOR)
@@ -84,6 +85,12 @@ fn parse_size(elt: &ElementRef) -> Result<bytesize::ByteSize, IndexerError> {
         .map_err(|cause| IndexerError::new(NAME, IndexerErrorReason::EntrySizeInvalid { cause }))
 }
 
+fn parse_uploader(elt: &ElementRef) -> Option<String> {
+    let value = elt.select(&UPLOADER_SELECTOR).next()?;
+    let value = value.text().collect::<String>().trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
 fn parse_list_row(base_url: &str, elt: ElementRef) -> Result<IndexerEntry, IndexerError> {
     let (name, link) = parse_link(&elt)?;
     let seeders = parse_seeders(&elt)?;
@@ -94,12 +101,19 @@ fn parse_list_row(base_url: &str, elt: ElementRef) -> Result<IndexerEntry, Index
     Ok(IndexerEntry {
         name,
         url: format!("{base_url}{link}"),
-        date,
+        date: Some(date),
         size,
         seeders,
         leechers,
         magnet: link.to_string(),
         origin: NAME,
+        uploader: parse_uploader(&elt),
+        info_hash: None,
+        release: Default::default(),
+        deferred: None,
+        duplicates: Vec::new(),
+        files: Vec::new(),
+        suspicious: Vec::new(),
     })
 }
 
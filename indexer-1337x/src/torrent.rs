@@ -5,6 +5,7 @@ use scraper::{Html, Selector};
 static RESULT_LINK: Lazy<Selector> = Lazy::new(|| {
     Selector::parse("main.container div.row div.page-content div.box-info.torrent-detail-page div.no-top-radius div ul li a").unwrap()
 });
+static FILE: Lazy<Selector> = Lazy::new(|| Selector::parse("div.file-content li").unwrap());
 
 pub fn parse_magnet(html: &str) -> Result<String, IndexerError> {
     let html = Html::parse_document(html);
@@ -16,3 +17,21 @@ pub fn parse_magnet(html: &str) -> Result<String, IndexerError> {
         .next()
         .ok_or_else(|| IndexerError::new(super::NAME, IndexerErrorReason::EntryMagnetNotFound))
 }
+
+/// Names of the files listed on the torrent page, without their size.
+pub fn parse_files(html: &str) -> Vec<String> {
+    let html = Html::parse_document(html);
+
+    html.select(&FILE)
+        .map(|item| {
+            let text = item.text().collect::<String>();
+            let text = text.trim();
+            // How.I.Met.Your.Mother.S04E20.HDTV.XviD-2HD.avi (175.2 MB)
+            match text.rsplit_once('(') {
+                Some((name, size)) if size.ends_with("B)") => name.trim().to_string(),
+                _ => text.to_string(),
+            }
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
@@ -1,7 +1,9 @@
+use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode};
+use manteau_indexer_prelude::http::HttpClient;
 use manteau_indexer_prelude::{
     Category, Indexer, IndexerBuilder, IndexerError, IndexerErrorReason, IndexerResult,
+    SearchRequest,
 };
-use reqwest::IntoUrl;
 use url::Url;
 
 mod date;
@@ -10,28 +12,6 @@ mod search;
 const BASE_URL: &str = "https://bitsearch.to";
 pub const NAME: &str = "bitsearch";
 
-async fn fetch_page<U: IntoUrl + ToString>(url: U) -> Result<String, IndexerError> {
-    let url_str = url.to_string();
-    let req = reqwest::get(url).await.map_err(|err| {
-        IndexerError::new(
-            NAME,
-            IndexerErrorReason::UnableToQuery {
-                url: url_str.clone(),
-                cause: err.to_string(),
-            },
-        )
-    })?;
-    req.text().await.map_err(|err| {
-        IndexerError::new(
-            NAME,
-            IndexerErrorReason::UnableToRead {
-                url: url_str,
-                cause: err.to_string(),
-            },
-        )
-    })
-}
-
 #[derive(Debug, serde::Deserialize)]
 pub struct IndexerBitsearchConfig {
     #[serde(default = "IndexerBitsearchConfig::default_base_url")]
@@ -45,11 +25,12 @@ impl IndexerBitsearchConfig {
 }
 
 impl IndexerBuilder for IndexerBitsearchConfig {
-    fn build(self, name: String) -> Box<dyn Indexer + Send + Sync + 'static> {
+    fn build(self, name: String, client: HttpClient) -> Box<dyn Indexer + Send + Sync + 'static> {
         tracing::info!("building bitsearch indexer named {name:?}");
         Box::new(IndexerBitsearch {
             name,
             base_url: self.base_url,
+            client,
         })
     }
 }
@@ -58,6 +39,7 @@ impl IndexerBuilder for IndexerBitsearchConfig {
 pub struct IndexerBitsearch {
     name: String,
     base_url: String,
+    client: HttpClient,
 }
 
 impl Default for IndexerBitsearch {
@@ -71,13 +53,19 @@ impl IndexerBitsearch {
         Self {
             name: "bitsearch".into(),
             base_url: base_url.into(),
+            client: HttpClient::default(),
         }
     }
 }
 
 #[async_trait::async_trait]
 impl Indexer for IndexerBitsearch {
-    async fn search(&self, query: &str) -> IndexerResult {
+    async fn search(&self, request: &SearchRequest) -> IndexerResult {
+        let query = request.keywords();
+        if query.is_empty() {
+            tracing::debug!("{} skipping search without keywords", self.name);
+            return IndexerResult::default();
+        }
         tracing::debug!("{} searching {query:?}", self.name);
         let url = format!("{}/search", self.base_url);
         let url = match Url::parse_with_params(&url, &[("q", query)]) {
@@ -90,7 +78,7 @@ impl Indexer for IndexerBitsearch {
             }
         };
 
-        let html = match fetch_page(url).await {
+        let html = match self.client.get_text(NAME, url).await {
             Ok(value) => value,
             Err(error) => return IndexerResult::from(error),
         };
@@ -108,19 +96,30 @@ impl Indexer for IndexerBitsearch {
         };
         let url = format!("{}{path}", self.base_url);
 
-        let html = match fetch_page(url).await {
+        let html = match self.client.get_text(NAME, url).await {
             Ok(value) => value,
             Err(error) => return IndexerResult::from(error),
         };
 
         search::parse(&self.base_url, html.as_str())
     }
+
+    fn capabilities(&self) -> Capabilities {
+        let mut caps = Capabilities::default()
+            .with_keyword_searches()
+            .with_category("Movies", Category::Movie)
+            .with_category("TV", Category::Tv)
+            .with_category("Music", Category::Music);
+        // nothing to tell the books apart
+        caps.searching.remove(&SearchMode::BookSearch);
+        caps
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::IndexerBitsearch;
-    use manteau_indexer_prelude::Indexer;
+    use manteau_indexer_prelude::{Indexer, SearchRequest};
 
     #[tokio::test]
     async fn basic_search() {
@@ -135,7 +134,9 @@ mod tests {
             .create_async()
             .await;
 
-        let results = indexer.search("how i met your mother").await;
+        let results = indexer
+            .search(&SearchRequest::new("how i met your mother"))
+            .await;
         println!("results: {results:#?}");
         assert_eq!(results.entries.len(), 20);
         assert_eq!(results.errors.len(), 0);
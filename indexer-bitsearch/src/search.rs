@@ -1,4 +1,5 @@
 use chrono::{DateTime, Utc};
+use manteau_indexer_helper::magnet::info_hash;
 use manteau_indexer_helper::numeric::Number;
 use manteau_indexer_prelude::{IndexerEntry, IndexerError, IndexerErrorReason, IndexerResult};
 use once_cell::sync::Lazy;
@@ -126,12 +127,19 @@ fn parse_row_result(base_url: &str, elt: ElementRef) -> Result<IndexerEntry, Ind
     Ok(IndexerEntry {
         name,
         url: format!("{base_url}{path}"),
-        date,
+        date: Some(date),
         size,
         seeders,
         leechers,
+        info_hash: info_hash(&magnet),
+        release: Default::default(),
         magnet,
         origin: super::NAME,
+        uploader: None,
+        deferred: None,
+        duplicates: Vec::new(),
+        files: Vec::new(),
+        suspicious: Vec::new(),
     })
 }
 
@@ -0,0 +1,179 @@
+use chrono::format::ParseErrorKind;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+
+// 3 hours ago, 1 day ago
+static AGO_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
+    regex::Regex::new(r"^(\d+)\s*(second|sec|minute|min|hour|hr|day|week|month|year)s?\s+ago$")
+        .unwrap()
+});
+
+// go reference values with their chrono equivalent, the longest first
+const GO_LAYOUT: [(&str, &str); 24] = [
+    ("January", "%B"),
+    ("Monday", "%A"),
+    ("Z07:00", "%:z"),
+    ("-07:00", "%:z"),
+    ("-0700", "%z"),
+    ("2006", "%Y"),
+    ("Jan", "%b"),
+    ("Mon", "%a"),
+    ("MST", "%Z"),
+    ("_2", "%e"),
+    ("01", "%m"),
+    ("02", "%d"),
+    ("03", "%I"),
+    ("04", "%M"),
+    ("05", "%S"),
+    ("06", "%y"),
+    ("15", "%H"),
+    ("PM", "%p"),
+    ("pm", "%P"),
+    ("1", "%m"),
+    ("2", "%d"),
+    ("3", "%I"),
+    ("4", "%M"),
+    ("5", "%S"),
+];
+
+/// Turns a layout like `2006-01-02 15:04` into a chrono format.
+fn chrono_format(layout: &str) -> String {
+    let mut result = String::new();
+    let mut rest = layout;
+    'outer: while let Some(next) = rest.chars().next() {
+        for (go, chrono) in GO_LAYOUT {
+            if let Some(tail) = rest.strip_prefix(go) {
+                result.push_str(chrono);
+                rest = tail;
+                continue 'outer;
+            }
+        }
+        if next == '%' {
+            result.push('%');
+        }
+        result.push(next);
+        rest = &rest[next.len_utf8()..];
+    }
+    result
+}
+
+/// Parses a date written with a go layout, like the `dateparse` filter asks.
+pub fn parse_layout(input: &str, layout: &str) -> Result<DateTime<Utc>, ParseErrorKind> {
+    let format = chrono_format(layout);
+    let input = input.trim();
+    if let Ok(found) = DateTime::parse_from_str(input, &format) {
+        return Ok(found.with_timezone(&Utc));
+    }
+    if let Ok(found) = NaiveDateTime::parse_from_str(input, &format) {
+        return Ok(Utc.from_utc_datetime(&found));
+    }
+    NaiveDate::parse_from_str(input, &format)
+        .map(|found| Utc.from_utc_datetime(&found.and_hms_opt(0, 0, 0).unwrap()))
+        .map_err(|err| {
+            tracing::debug!("unable to parse date {input:?} with {format:?}: {err:?}");
+            err.kind()
+        })
+}
+
+fn parse_relative(input: &str) -> Option<DateTime<Utc>> {
+    let now = Utc::now();
+    match input {
+        "now" | "just now" | "today" => return Some(now),
+        "yesterday" => return Some(now - Duration::days(1)),
+        _ => {}
+    }
+    let cap = AGO_REGEX.captures(input)?;
+    let count = i32::try_from(cap[1].parse::<u64>().ok()?).ok()?;
+    let unit: i64 = match &cap[2] {
+        "second" | "sec" => 1,
+        "minute" | "min" => 60,
+        "hour" | "hr" => 60 * 60,
+        "day" => 24 * 60 * 60,
+        "week" => 7 * 24 * 60 * 60,
+        "month" => 30 * 24 * 60 * 60,
+        _ => 365 * 24 * 60 * 60,
+    };
+    let seconds = unit.checked_mul(count.into())?;
+    // out of its bounds, building the duration panics
+    if seconds > Duration::max_value().num_seconds() {
+        return None;
+    }
+    now.checked_sub_signed(Duration::seconds(seconds))
+}
+
+/// Seconds since the epoch, a shorter number like `2023` being something else.
+fn parse_timestamp(input: &str) -> Option<DateTime<Utc>> {
+    if !(9..=10).contains(&input.len()) || !input.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Utc.timestamp_opt(input.parse().ok()?, 0).single()
+}
+
+/// Parses the date field once the filters are applied, whatever its format.
+pub fn parse(input: &str) -> Result<DateTime<Utc>, ParseErrorKind> {
+    let input = input.trim();
+    if let Some(found) = parse_timestamp(input) {
+        return Ok(found);
+    }
+    if let Ok(found) = DateTime::parse_from_rfc3339(input) {
+        return Ok(found.with_timezone(&Utc));
+    }
+    if let Ok(found) = DateTime::parse_from_rfc2822(input) {
+        return Ok(found.with_timezone(&Utc));
+    }
+    if let Some(found) = parse_relative(&input.to_lowercase()) {
+        return Ok(found);
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(found) = NaiveDateTime::parse_from_str(input, format) {
+            return Ok(Utc.from_utc_datetime(&found));
+        }
+    }
+    parse_layout(input, "2006-01-02")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Timelike};
+
+    #[test]
+    fn should_convert_go_layouts() {
+        assert_eq!(super::chrono_format("2006-01-02 15:04"), "%Y-%m-%d %H:%M");
+        assert_eq!(super::chrono_format("Jan _2, 2006"), "%b %e, %Y");
+        assert_eq!(super::chrono_format("02/01/06 3:04pm"), "%d/%m/%y %I:%M%P");
+    }
+
+    #[test]
+    fn should_parse_with_layout() {
+        let found = super::parse_layout("2023-03-21 22:15", "2006-01-02 15:04").unwrap();
+        assert_eq!((found.year(), found.month(), found.day()), (2023, 3, 21));
+        assert_eq!((found.hour(), found.minute()), (22, 15));
+        let found = super::parse_layout("21/03/2023", "02/01/2006").unwrap();
+        assert_eq!((found.year(), found.month(), found.day()), (2023, 3, 21));
+        assert!(super::parse_layout("yesterday", "2006-01-02").is_err());
+    }
+
+    #[test]
+    fn should_parse_any_date() {
+        assert_eq!(super::parse("1679436900").unwrap().year(), 2023);
+        assert_eq!(super::parse("2023-03-21T22:15:00Z").unwrap().hour(), 22);
+        assert_eq!(
+            super::parse("Tue, 21 Mar 2023 22:15:00 +0000")
+                .unwrap()
+                .day(),
+            21
+        );
+        assert_eq!(super::parse("2023-03-21").unwrap().month(), 3);
+        let found = super::parse("3 hours ago").unwrap();
+        assert!(chrono::Utc::now() - found >= chrono::Duration::hours(3));
+        assert!(super::parse("whenever").is_err());
+    }
+
+    #[test]
+    fn should_reject_out_of_range_dates() {
+        assert!(super::parse("300000 years ago").is_err());
+        assert!(super::parse("99999999999999999999 seconds ago").is_err());
+        assert!(super::parse("2023").is_err());
+        assert!(super::parse("-1679436900").is_err());
+    }
+}
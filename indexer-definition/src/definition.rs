@@ -0,0 +1,446 @@
+use crate::template;
+use manteau_indexer_prelude::Category;
+use regex::Regex;
+use scraper::Selector;
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+
+/// Reads a map keeping the order of its keys, the fields depending on the previous ones.
+fn deserialize_ordered<'de, D, T>(deserializer: D) -> Result<Vec<(String, T)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    struct OrderedVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for OrderedVisitor<T> {
+        type Value = Vec<(String, T)>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a map")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            let mut result = Vec::new();
+            while let Some(entry) = map.next_entry()? {
+                result.push(entry);
+            }
+            Ok(result)
+        }
+    }
+
+    deserializer.deserialize_map(OrderedVisitor(PhantomData))
+}
+
+fn text(value: serde_yaml::Value) -> String {
+    match value {
+        serde_yaml::Value::String(inner) => inner,
+        serde_yaml::Value::Number(inner) => inner.to_string(),
+        serde_yaml::Value::Bool(inner) => inner.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Values of a `case`, often written as numbers.
+fn deserialize_case<'de, D>(deserializer: D) -> Result<Vec<(String, String)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let cases: Vec<(String, serde_yaml::Value)> = deserialize_ordered(deserializer)?;
+    Ok(cases
+        .into_iter()
+        .map(|(selector, value)| (selector, text(value)))
+        .collect())
+}
+
+/// Texts of the fields, sometimes written as numbers.
+fn deserialize_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value: Option<serde_yaml::Value> = serde::Deserialize::deserialize(deserializer)?;
+    Ok(value.map(text))
+}
+
+/// Arguments of the filters, a single value or a list of them.
+fn deserialize_args<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value: serde_yaml::Value = serde::Deserialize::deserialize(deserializer)?;
+    Ok(match value {
+        serde_yaml::Value::Null => Vec::new(),
+        serde_yaml::Value::Sequence(items) => items.into_iter().map(text).collect(),
+        other => vec![text(other)],
+    })
+}
+
+/// Ids of the site categories, written as numbers or text.
+fn deserialize_id<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value: serde_yaml::Value = serde::Deserialize::deserialize(deserializer)?;
+    match value {
+        serde_yaml::Value::String(inner) => Ok(inner),
+        serde_yaml::Value::Number(inner) => Ok(inner.to_string()),
+        _ => Err(serde::de::Error::custom("expected a category id")),
+    }
+}
+
+/// Newznab category of a Cardigann one, like `Movies/HD`.
+pub fn newznab_category(name: &str) -> Option<Category> {
+    let (main, sub) = name.split_once('/').unwrap_or((name, ""));
+    match main.trim() {
+        "Movies" => Some(Category::Movie),
+        "TV" => Some(Category::Tv),
+        "Audio" if sub.starts_with("Audiobook") => Some(Category::Audio),
+        "Audio" => Some(Category::Music),
+        "Books" => Some(Category::Book),
+        _ => None,
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CategoryMapping {
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+    pub cat: String,
+    #[serde(default)]
+    pub desc: Option<String>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct Caps {
+    #[serde(default)]
+    pub categorymappings: Vec<CategoryMapping>,
+    /// Search modes with their parameters, like `tv-search: [q, season, ep]`.
+    #[serde(default)]
+    pub modes: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Filter {
+    pub name: String,
+    #[serde(default, deserialize_with = "deserialize_args")]
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseKind {
+    #[default]
+    Html,
+    Json,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct Response {
+    #[serde(default, rename = "type")]
+    pub kind: ResponseKind,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct SearchPath {
+    pub path: String,
+    #[serde(default)]
+    pub method: Option<String>,
+    /// Only used when one of these site categories is searched.
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub response: Response,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Rows {
+    /// CSS selector of the rows, or path to the array in a json response.
+    pub selector: String,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct Field {
+    /// CSS selector in the row, or path to the value in a json row.
+    #[serde(default)]
+    pub selector: Option<String>,
+    #[serde(default)]
+    pub attribute: Option<String>,
+    /// Template building the value from the previous fields.
+    #[serde(default, deserialize_with = "deserialize_text")]
+    pub text: Option<String>,
+    /// Value of the first matching selector.
+    #[serde(default, deserialize_with = "deserialize_case")]
+    pub case: Vec<(String, String)>,
+    #[serde(default)]
+    pub optional: bool,
+    #[serde(default, deserialize_with = "deserialize_text")]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Search {
+    #[serde(default)]
+    pub paths: Vec<SearchPath>,
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Query parameters, `$raw` ones not being supported.
+    #[serde(default)]
+    pub inputs: BTreeMap<String, String>,
+    #[serde(default)]
+    pub keywordsfilters: Vec<Filter>,
+    pub rows: Rows,
+    #[serde(deserialize_with = "deserialize_ordered")]
+    pub fields: Vec<(String, Field)>,
+}
+
+impl Search {
+    /// The paths to query, older definitions only having a single one.
+    pub fn paths(&self) -> Vec<SearchPath> {
+        let mut paths = self.paths.clone();
+        if let Some(ref path) = self.path {
+            paths.push(SearchPath {
+                path: path.clone(),
+                method: None,
+                categories: Vec::new(),
+                response: Response::default(),
+            });
+        }
+        paths
+    }
+}
+
+/// Where to find the link on the detail page, when the rows don't have it.
+#[derive(Debug, serde::Deserialize)]
+pub struct DownloadSelector {
+    pub selector: String,
+    #[serde(default)]
+    pub attribute: Option<String>,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct Download {
+    #[serde(default)]
+    pub selectors: Vec<DownloadSelector>,
+}
+
+/// Regexes of the filters and templates, compiled once when the definition is loaded.
+#[derive(Debug, Default)]
+pub struct Regexes(HashMap<String, Regex>);
+
+impl Regexes {
+    pub fn compile<'a, I>(patterns: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut regexes = HashMap::new();
+        for pattern in patterns {
+            if !regexes.contains_key(pattern) {
+                let regex = Regex::new(pattern)
+                    .map_err(|err| format!("invalid regex {pattern:?}: {err}"))?;
+                regexes.insert(pattern.to_string(), regex);
+            }
+        }
+        Ok(Self(regexes))
+    }
+
+    pub fn get(&self, pattern: &str) -> Option<&Regex> {
+        self.0.get(pattern)
+    }
+}
+
+/// Site described in the Cardigann format used by Jackett.
+#[derive(Debug, serde::Deserialize)]
+pub struct Definition {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub links: Vec<String>,
+    #[serde(default)]
+    pub caps: Caps,
+    pub search: Search,
+    #[serde(default)]
+    pub download: Download,
+    // css selectors, parsed once
+    #[serde(skip)]
+    selectors: HashMap<String, Selector>,
+    // regexes of the filters and templates, compiled once
+    #[serde(skip)]
+    regexes: Regexes,
+}
+
+impl Definition {
+    pub fn from_yaml(input: &str) -> Result<Self, String> {
+        let mut definition: Self = serde_yaml::from_str(input).map_err(|err| err.to_string())?;
+        definition.compile()?;
+        Ok(definition)
+    }
+
+    fn filters(&self) -> impl Iterator<Item = &Filter> {
+        self.search
+            .keywordsfilters
+            .iter()
+            .chain(
+                self.search
+                    .fields
+                    .iter()
+                    .flat_map(|(_, field)| field.filters.iter()),
+            )
+            .chain(
+                self.download
+                    .selectors
+                    .iter()
+                    .flat_map(|selector| selector.filters.iter()),
+            )
+    }
+
+    /// Templates rendered when searching.
+    fn templates(&self) -> impl Iterator<Item = &String> {
+        self.search
+            .paths
+            .iter()
+            .map(|path| &path.path)
+            .chain(self.search.path.iter())
+            .chain(self.search.inputs.values())
+            .chain(
+                self.search
+                    .fields
+                    .iter()
+                    .flat_map(|(_, field)| field.text.iter().chain(field.default.iter())),
+            )
+    }
+
+    /// Parses the selectors and compiles the regexes, so that a broken definition fails to load.
+    fn compile(&mut self) -> Result<(), String> {
+        let mut regexes = self
+            .filters()
+            .filter(|filter| matches!(filter.name.as_str(), "regexp" | "re_replace"))
+            .filter_map(|filter| filter.args.first().cloned())
+            .collect::<Vec<_>>();
+        regexes.extend(
+            self.templates()
+                .flat_map(|template| template::patterns(template)),
+        );
+        self.regexes = Regexes::compile(regexes.iter().map(String::as_str))?;
+        let json = self
+            .search
+            .paths()
+            .iter()
+            .all(|path| path.response.kind == ResponseKind::Json);
+        let mut patterns = vec![];
+        if !json {
+            patterns.push(self.search.rows.selector.clone());
+            for (_, field) in self.search.fields.iter() {
+                patterns.extend(field.selector.clone());
+                patterns.extend(field.case.iter().map(|(selector, _)| selector.clone()));
+            }
+        }
+        patterns.extend(
+            self.download
+                .selectors
+                .iter()
+                .map(|selector| selector.selector.clone()),
+        );
+        for pattern in patterns {
+            if pattern == "*" || pattern.is_empty() || self.selectors.contains_key(&pattern) {
+                continue;
+            }
+            let selector = Selector::parse(&pattern)
+                .map_err(|err| format!("invalid selector {pattern:?}: {err:?}"))?;
+            self.selectors.insert(pattern, selector);
+        }
+        Ok(())
+    }
+
+    pub fn selector(&self, pattern: &str) -> Option<&Selector> {
+        self.selectors.get(pattern)
+    }
+
+    pub fn regexes(&self) -> &Regexes {
+        &self.regexes
+    }
+
+    /// Site categories mapped to the given newznab one.
+    pub fn site_categories(&self, category: Category) -> Vec<String> {
+        self.caps
+            .categorymappings
+            .iter()
+            .filter(|mapping| {
+                newznab_category(&mapping.cat).is_some_and(|found| found.kind() == category.kind())
+            })
+            .map(|mapping| mapping.id.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{newznab_category, Definition};
+    use manteau_indexer_prelude::Category;
+
+    #[test]
+    fn should_load_definition() {
+        let definition = Definition::from_yaml(include_str!("./definition.yml")).unwrap();
+        assert_eq!(definition.id, "example");
+        assert!(definition.regexes().get(r"\s+").is_some());
+        assert_eq!(definition.site_categories(Category::Tv), vec!["5", "6"]);
+        let fields = definition
+            .search
+            .fields
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec!["title", "details", "magnet", "size", "seeders", "leechers", "date", "category"]
+        );
+    }
+
+    #[test]
+    fn should_reject_broken_definition() {
+        let error = Definition::from_yaml(
+            r#"
+id: broken
+search:
+  path: /search
+  rows:
+    selector: "table >> tr"
+  fields:
+    title:
+      selector: a
+"#,
+        )
+        .unwrap_err();
+        assert!(error.contains("invalid selector"));
+
+        let error = Definition::from_yaml(
+            r#"
+id: broken
+search:
+  path: "/search/{{ re_replace .Keywords \"(\" \"+\" }}"
+  rows:
+    selector: tr
+  fields:
+    title:
+      selector: a
+"#,
+        )
+        .unwrap_err();
+        assert!(error.contains("invalid regex"));
+    }
+
+    #[test]
+    fn should_map_categories() {
+        assert_eq!(newznab_category("Movies/HD"), Some(Category::Movie));
+        assert_eq!(newznab_category("Audio/Audiobook"), Some(Category::Audio));
+        assert_eq!(newznab_category("Audio/MP3"), Some(Category::Music));
+        assert_eq!(newznab_category("PC/Games"), None);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::definition::{Filter, Regexes};
+use regex::Regex;
+
+fn arg(filter: &Filter, index: usize) -> &str {
+    filter
+        .args
+        .get(index)
+        .map(String::as_str)
+        .unwrap_or_default()
+}
+
+fn regex<'a>(filter: &Filter, regexes: &'a Regexes) -> Option<&'a Regex> {
+    regexes.get(arg(filter, 0))
+}
+
+fn split(value: &str, filter: &Filter) -> String {
+    let parts = value.split(arg(filter, 0)).collect::<Vec<_>>();
+    let index = arg(filter, 1).parse::<isize>().unwrap_or_default();
+    // negative indexes start from the end
+    let index = match index < 0 {
+        true => parts.len() as isize + index,
+        false => index,
+    };
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| parts.get(index))
+        .map(|part| part.to_string())
+        .unwrap_or_default()
+}
+
+fn querystring(value: &str, filter: &Filter) -> String {
+    let query = value.split_once('?').map_or(value, |(_, query)| query);
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == arg(filter, 0))
+        .map(|(_, found)| found.into_owned())
+        .unwrap_or_default()
+}
+
+/// Applies a filter of the definition, the unknown ones leaving the value unchanged.
+pub fn apply(value: String, filter: &Filter, regexes: &Regexes) -> String {
+    match filter.name.as_str() {
+        "replace" => value.replace(arg(filter, 0), arg(filter, 1)),
+        "re_replace" => match regex(filter, regexes) {
+            Some(regex) => regex.replace_all(&value, arg(filter, 1)).into_owned(),
+            None => value,
+        },
+        "regexp" => regex(filter, regexes)
+            .and_then(|regex| {
+                let cap = regex.captures(&value)?;
+                cap.get(1)
+                    .or_else(|| cap.get(0))
+                    .map(|m| m.as_str().to_string())
+            })
+            .unwrap_or_default(),
+        "append" => value + arg(filter, 0),
+        "prepend" => format!("{}{value}", arg(filter, 0)),
+        "trim" => match arg(filter, 0) {
+            "" => value.trim().to_string(),
+            cutset => value.trim_matches(|c| cutset.contains(c)).to_string(),
+        },
+        "tolower" => value.to_lowercase(),
+        "toupper" => value.to_uppercase(),
+        "split" => split(&value, filter),
+        "querystring" => querystring(&value, filter),
+        "urldecode" => urlencoding::decode(&value)
+            .map(|decoded| decoded.into_owned())
+            .unwrap_or(value),
+        "urlencode" => urlencoding::encode(&value).into_owned(),
+        "dateparse" => match crate::date::parse_layout(&value, arg(filter, 0)) {
+            Ok(date) => date.to_rfc3339(),
+            Err(_) => value,
+        },
+        "timeago" | "fuzzytime" => match crate::date::parse(&value) {
+            Ok(date) => date.to_rfc3339(),
+            Err(_) => value,
+        },
+        other => {
+            tracing::debug!("unsupported filter {other:?}");
+            value
+        }
+    }
+}
+
+pub fn apply_all(value: String, filters: &[Filter], regexes: &Regexes) -> String {
+    filters
+        .iter()
+        .fold(value, |value, filter| apply(value, filter, regexes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply_all;
+    use crate::definition::{Filter, Regexes};
+
+    fn filter(name: &str, args: &[&str]) -> Filter {
+        Filter {
+            name: name.into(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn should_apply_filters() {
+        let regexes = Regexes::compile([r"Size: (.+)$"]).unwrap();
+        let filters = vec![
+            filter("regexp", &[r"Size: (.+)$"]),
+            filter("replace", &["GiB", "GB"]),
+            filter("trim", &[]),
+        ];
+        assert_eq!(
+            apply_all("Uploaded, Size: 1.2 GiB ".into(), &filters, &regexes),
+            "1.2 GB"
+        );
+
+        let filters = vec![filter("split", &["/", "-1"]), filter("toupper", &[])];
+        assert_eq!(
+            apply_all("/torrent/123/abc".into(), &filters, &regexes),
+            "ABC"
+        );
+
+        let filters = vec![filter("querystring", &["id"]), filter("prepend", &["#"])];
+        assert_eq!(
+            apply_all("/download.php?id=42&x=1".into(), &filters, &regexes),
+            "#42"
+        );
+
+        let filters = vec![filter("dateparse", &["02/01/2006"])];
+        assert_eq!(
+            apply_all("21/03/2023".into(), &filters, &regexes),
+            "2023-03-21T00:00:00+00:00"
+        );
+
+        let filters = vec![filter("unknown", &[])];
+        assert_eq!(apply_all("same".into(), &filters, &regexes), "same");
+    }
+}
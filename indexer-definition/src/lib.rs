@@ -0,0 +1,404 @@
+use chrono::Datelike;
+use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode, SearchParam};
+use manteau_indexer_prelude::http::HttpClient;
+use manteau_indexer_prelude::{
+    leak_origin, Category, DeferredLink, Indexer, IndexerBuilder, IndexerError, IndexerErrorReason,
+    IndexerResult, SearchRequest,
+};
+use std::sync::Arc;
+
+mod date;
+mod definition;
+mod filter;
+mod search;
+mod template;
+
+pub use definition::Definition;
+
+use definition::{newznab_category, SearchPath};
+use template::{render, Context};
+
+pub const NAME: &str = "definition";
+
+/// Reads the definition file given in the configuration.
+fn load<'de, D>(deserializer: D) -> Result<Definition, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let path: String = serde::Deserialize::deserialize(deserializer)?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|err| serde::de::Error::custom(format!("unable to read {path:?}: {err}")))?;
+    Definition::from_yaml(&content)
+        .map_err(|err| serde::de::Error::custom(format!("invalid definition {path:?}: {err}")))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct IndexerDefinitionConfig {
+    /// Path to the yaml file describing the site.
+    #[serde(rename = "path", deserialize_with = "load")]
+    pub definition: Definition,
+    /// Overrides the first link of the definition.
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+impl IndexerBuilder for IndexerDefinitionConfig {
+    fn build(self, name: String, client: HttpClient) -> Box<dyn Indexer + Send + Sync + 'static> {
+        tracing::info!(
+            "building indexer named {name:?} from the definition {:?}",
+            self.definition.id
+        );
+        let base_url = self
+            .base_url
+            .or_else(|| self.definition.links.first().cloned())
+            .unwrap_or_default();
+        let mut indexer = IndexerDefinition::new(self.definition, base_url);
+        indexer.name = name;
+        indexer.client = client;
+        Box::new(indexer)
+    }
+}
+
+/// Indexer searching a site the way its Cardigann definition describes.
+#[derive(Debug)]
+pub struct IndexerDefinition {
+    name: String,
+    // the id of the definition, kept for the whole life of the application
+    origin: &'static str,
+    base_url: String,
+    definition: Arc<Definition>,
+    client: HttpClient,
+}
+
+impl IndexerDefinition {
+    pub fn new<S: Into<String>>(definition: Definition, base_url: S) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        Self {
+            name: definition.id.clone(),
+            origin: leak_origin(&definition.id),
+            base_url,
+            definition: Arc::new(definition),
+            client: HttpClient::default(),
+        }
+    }
+
+    fn context(&self, request: &SearchRequest, categories: Vec<String>) -> Context {
+        let keywords = filter::apply_all(
+            request.keywords(),
+            &self.definition.search.keywordsfilters,
+            self.definition.regexes(),
+        );
+        let imdb_id = request.imdb_id.clone().unwrap_or_default();
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        Context::default()
+            .with_value(".Keywords", keywords.as_str())
+            .with_value(".Query.Keywords", keywords)
+            .with_value(".Query.Q", request.query.as_str())
+            .with_value(".Query.Series", request.query.as_str())
+            .with_value(
+                ".Query.Season",
+                request
+                    .season
+                    .map(|value| value.to_string())
+                    .unwrap_or_default(),
+            )
+            .with_value(".Query.Ep", optional(&request.episode))
+            .with_value(".Query.IMDBIDShort", imdb_id.trim_start_matches("tt"))
+            .with_value(".Query.IMDBID", imdb_id)
+            .with_value(
+                ".Query.TVDBID",
+                request
+                    .tvdb_id
+                    .map(|value| value.to_string())
+                    .unwrap_or_default(),
+            )
+            .with_value(".Query.Artist", optional(&request.artist))
+            .with_value(".Query.Album", optional(&request.album))
+            .with_value(".Query.Author", optional(&request.author))
+            .with_value(".Config.sitelink", self.base_url.as_str())
+            .with_value(".Today.Year", chrono::Utc::now().year().to_string())
+            .with_list(".Categories", categories)
+    }
+
+    fn url(&self, path: &SearchPath, context: &Context) -> Result<url::Url, IndexerError> {
+        let base = url::Url::parse(&self.base_url).map_err(|cause| {
+            IndexerError::new(self.origin, IndexerErrorReason::UnableToBuildUrl { cause })
+        })?;
+        let mut url = base
+            .join(&render(&path.path, context, self.definition.regexes()))
+            .map_err(|cause| {
+                IndexerError::new(self.origin, IndexerErrorReason::UnableToBuildUrl { cause })
+            })?;
+        let inputs = self
+            .definition
+            .search
+            .inputs
+            .iter()
+            .filter(|(key, _)| {
+                let raw = key.as_str() == "$raw";
+                if raw {
+                    tracing::debug!("{} ignoring the raw inputs", self.name);
+                }
+                !raw
+            })
+            .map(|(key, value)| (key, render(value, context, self.definition.regexes())))
+            .collect::<Vec<_>>();
+        if !inputs.is_empty() {
+            url.query_pairs_mut().extend_pairs(inputs);
+        }
+        Ok(url)
+    }
+
+    async fn search_path(&self, path: SearchPath, context: &Context) -> IndexerResult {
+        if let Some(method) = path
+            .method
+            .as_deref()
+            .filter(|method| !method.eq_ignore_ascii_case("get"))
+        {
+            tracing::warn!("{} unable to search with the {method:?} method", self.name);
+            return IndexerResult::default();
+        }
+        let url = match self.url(&path, context) {
+            Ok(value) => value,
+            Err(error) => return IndexerResult::from(error),
+        };
+        let body = match self.client.get_text(self.origin, url).await {
+            Ok(value) => value,
+            Err(error) => return IndexerResult::from(error),
+        };
+        let mut result = search::parse(
+            &self.definition,
+            path.response.kind,
+            &self.base_url,
+            self.origin,
+            &body,
+            context,
+        );
+        // the link is found on the details page once the entry is downloaded
+        for entry in result
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.magnet.is_empty())
+        {
+            entry.deferred = Some(DeferredLink {
+                indexer: self.name.clone(),
+                token: entry.url.clone(),
+            });
+        }
+        result
+    }
+}
+
+#[async_trait::async_trait]
+impl Indexer for IndexerDefinition {
+    async fn search(&self, request: &SearchRequest) -> IndexerResult {
+        tracing::debug!("{} searching {:?}", self.name, request.query);
+        let mut categories: Vec<String> = Vec::new();
+        for category in request.categories.iter() {
+            for site in self.definition.site_categories(*category) {
+                if !categories.contains(&site) {
+                    categories.push(site);
+                }
+            }
+        }
+        let paths = self
+            .definition
+            .search
+            .paths()
+            .into_iter()
+            .filter(|path| {
+                path.categories.is_empty()
+                    || categories.is_empty()
+                    || path
+                        .categories
+                        .iter()
+                        .any(|found| categories.contains(found))
+            })
+            .collect::<Vec<_>>();
+        let context = self.context(request, categories);
+        futures::future::join_all(
+            paths
+                .into_iter()
+                .map(|path| self.search_path(path, &context)),
+        )
+        .await
+        .into_iter()
+        .fold(IndexerResult::default(), IndexerResult::merge)
+    }
+
+    async fn feed(&self, category: Category) -> IndexerResult {
+        tracing::debug!("{} fetching feed for {category:?}", self.name);
+        self.search(&SearchRequest::default().with_category(category))
+            .await
+    }
+
+    async fn resolve(&self, token: &str) -> Result<String, IndexerError> {
+        tracing::debug!("{} resolving {token:?}", self.name);
+        let not_resolvable =
+            || IndexerError::new(self.origin, IndexerErrorReason::EntryLinkNotResolvable);
+        // the token is a page of the site, don't let it go anywhere else
+        if !token.starts_with(&self.base_url) {
+            return Err(not_resolvable());
+        }
+        let html = self.client.get_text(self.origin, token).await?;
+        let html = scraper::Html::parse_document(&html);
+        self.definition
+            .download
+            .selectors
+            .iter()
+            .find_map(|selector| {
+                let found =
+                    search::element(&self.definition, html.root_element(), &selector.selector)?;
+                let value = search::element_value(found, selector.attribute.as_deref())?;
+                let value = filter::apply_all(value, &selector.filters, self.definition.regexes());
+                (!value.is_empty()).then(|| search::absolute(token, &value))
+            })
+            .ok_or_else(not_resolvable)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let caps = &self.definition.caps;
+        let mut result = Capabilities::default();
+        for (name, params) in caps.modes.iter() {
+            let Some(mode) = SearchMode::ALL.into_iter().find(|mode| mode.name() == name) else {
+                continue;
+            };
+            let params = SearchParam::ALL
+                .into_iter()
+                .filter(|param| params.iter().any(|found| found == param.name()))
+                .collect::<Vec<_>>();
+            result = result.with_search(mode, &params);
+        }
+        if result.searching.is_empty() {
+            result = result.with_keyword_searches();
+        }
+        caps.categorymappings
+            .iter()
+            .fold(result, |result, mapping| {
+                match newznab_category(&mapping.cat) {
+                    Some(category) => result.with_category(
+                        mapping.desc.clone().unwrap_or_else(|| mapping.cat.clone()),
+                        category,
+                    ),
+                    None => result,
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Definition, IndexerDefinition, IndexerDefinitionConfig};
+    use manteau_indexer_prelude::capabilities::{SearchMode, SearchParam};
+    use manteau_indexer_prelude::{Category, Indexer, IndexerBuilder, SearchRequest};
+
+    fn indexer(base_url: &str) -> IndexerDefinition {
+        let definition = Definition::from_yaml(include_str!("./definition.yml")).unwrap();
+        IndexerDefinition::new(definition, base_url)
+    }
+
+    #[tokio::test]
+    async fn should_search_with_definition() {
+        let mut server = mockito::Server::new_async().await;
+        let indexer = indexer(server.url().as_str());
+
+        let search_page = server
+            .mock("GET", "/search/the+last+of+us+s01e01/?cat=5%2C6")
+            .with_status(200)
+            .with_header("content-type", "text/html")
+            .with_body(include_str!("./search.html"))
+            .create_async()
+            .await;
+
+        let request = SearchRequest {
+            query: "The Last of Us".into(),
+            season: Some(1),
+            episode: Some("1".into()),
+            ..Default::default()
+        }
+        .with_category(Category::Tv);
+        let result = indexer.search(&request).await;
+        assert_eq!(result.entries.len(), 2);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.entries[0].origin, "example");
+        assert_eq!(
+            result.entries[0].url,
+            format!("{}/torrent/1/the-last-of-us", server.url())
+        );
+        assert!(result.entries[1].magnet.starts_with("magnet:?"));
+        assert_eq!(result.entries[1].size.as_u64(), 1_024_500_000);
+
+        search_page.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_resolve_from_details_page() {
+        let mut server = mockito::Server::new_async().await;
+        let yaml = include_str!("./definition.yml")
+            .replace("a[href^=\"magnet:\"]", "a.missing\n      optional: true")
+            .replace("\nsearch:", "\ndownload:\n  selectors:\n    - selector: a.download\n      attribute: href\nsearch:");
+        let definition = Definition::from_yaml(&yaml).unwrap();
+        let indexer = IndexerDefinition::new(definition, server.url());
+
+        let search_page = server
+            .mock("GET", "/search/latest/?cat=1")
+            .with_status(200)
+            .with_body(include_str!("./search.html"))
+            .create_async()
+            .await;
+        let details_page = server
+            .mock("GET", "/torrent/1/the-last-of-us")
+            .with_status(200)
+            .with_body(r#"<html><body><a class="download" href="/get/1.torrent">Download</a></body></html>"#)
+            .create_async()
+            .await;
+
+        let result = indexer.feed(Category::Movie).await;
+        assert_eq!(result.entries.len(), 2);
+        let deferred = result.entries[0].deferred.clone().unwrap();
+        assert_eq!(deferred.indexer, "example");
+        assert!(result.entries[0].magnet.is_empty());
+
+        let link = indexer.resolve(&deferred.token).await.unwrap();
+        assert_eq!(link, format!("{}/get/1.torrent", server.url()));
+        assert!(indexer.resolve("https://elsewhere.com/").await.is_err());
+
+        search_page.assert_async().await;
+        details_page.assert_async().await;
+    }
+
+    #[test]
+    fn should_declare_capabilities() {
+        let caps = indexer("https://example.com").capabilities();
+        assert_eq!(
+            caps.searching[&SearchMode::TvSearch]
+                .iter()
+                .map(SearchParam::name)
+                .collect::<Vec<_>>(),
+            vec!["q", "season", "ep", "imdbid"]
+        );
+        assert!(!caps.searching.contains_key(&SearchMode::BookSearch));
+        assert_eq!(
+            caps.newznab_categories(),
+            vec![Category::Movie, Category::Music, Category::Tv]
+        );
+    }
+
+    #[test]
+    fn should_build_from_config() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/definition.yml");
+        let config: IndexerDefinitionConfig =
+            serde_json::from_value(serde_json::json!({ "path": path })).unwrap();
+        let built = config.build("mine".into(), Default::default());
+        assert!(format!("{built:?}").contains("https://example.com/"));
+
+        let error = serde_json::from_value::<IndexerDefinitionConfig>(
+            serde_json::json!({ "path": "/nowhere.yml" }),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unable to read"));
+    }
+}
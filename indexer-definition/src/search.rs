@@ -0,0 +1,366 @@
+use crate::definition::{Definition, Field, ResponseKind};
+use crate::filter::apply_all;
+use crate::template::{render, Context};
+use bytesize::ByteSize;
+use manteau_indexer_helper::magnet::info_hash;
+use manteau_indexer_helper::numeric::{Number, ParseNumberError};
+use manteau_indexer_prelude::{IndexerEntry, IndexerError, IndexerErrorReason, IndexerResult};
+use scraper::{ElementRef, Html};
+use std::collections::HashMap;
+
+/// Where the values of an entry are read from.
+enum Row<'a> {
+    Html(ElementRef<'a>),
+    Json(&'a serde_json::Value),
+}
+
+/// Value at a path like `data.torrents` of a json document, the empty path being the root.
+fn json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.')
+        .filter(|key| !key.is_empty())
+        .try_fold(value, |current, key| match current {
+            serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            other => other.get(key),
+        })
+}
+
+fn json_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(inner) => Some(inner.clone()),
+        serde_json::Value::Number(inner) => Some(inner.to_string()),
+        serde_json::Value::Bool(inner) => Some(inner.to_string()),
+        _ => None,
+    }
+}
+
+/// Resolves a link of the site against its base url.
+pub fn absolute(base_url: &str, link: &str) -> String {
+    url::Url::parse(base_url)
+        .and_then(|base| base.join(link))
+        .map(String::from)
+        .unwrap_or_else(|_| link.to_string())
+}
+
+/// The element matching the selector in the row, `*` being the row itself.
+pub fn element<'a>(
+    definition: &Definition,
+    row: ElementRef<'a>,
+    pattern: &str,
+) -> Option<ElementRef<'a>> {
+    match pattern {
+        "" | "*" => Some(row),
+        pattern => row.select(definition.selector(pattern)?).next(),
+    }
+}
+
+pub fn element_value(element: ElementRef, attribute: Option<&str>) -> Option<String> {
+    match attribute {
+        Some(name) => element.value().attr(name).map(str::to_string),
+        None => Some(element.text().collect::<String>().trim().to_string()),
+    }
+}
+
+fn extract(definition: &Definition, row: &Row, field: &Field) -> Option<String> {
+    match row {
+        Row::Html(element_ref) => {
+            if !field.case.is_empty() {
+                return field
+                    .case
+                    .iter()
+                    .find(|(pattern, _)| element(definition, *element_ref, pattern).is_some())
+                    .map(|(_, value)| value.clone());
+            }
+            let found = element(
+                definition,
+                *element_ref,
+                field.selector.as_deref().unwrap_or_default(),
+            )?;
+            element_value(found, field.attribute.as_deref())
+        }
+        Row::Json(value) => json_text(json_path(value, field.selector.as_deref()?)?),
+    }
+}
+
+/// Reason given when a required field can't be found.
+fn missing(name: &str) -> IndexerErrorReason {
+    match name {
+        "details" | "download" | "magnet" => IndexerErrorReason::EntryLinkNotFound,
+        "size" => IndexerErrorReason::EntrySizeNotFound,
+        "seeders" => IndexerErrorReason::EntrySeedersNotFound,
+        "leechers" => IndexerErrorReason::EntryLeechersNotFound,
+        "date" => IndexerErrorReason::EntryDateNotFound,
+        _ => IndexerErrorReason::EntryNameNotFound,
+    }
+}
+
+/// Values of the fields of a row, each of them usable by the next ones as `.Result.<name>`.
+fn fields(
+    definition: &Definition,
+    origin: &'static str,
+    row: &Row,
+    context: &Context,
+) -> Result<HashMap<String, String>, IndexerError> {
+    let mut context = context.clone();
+    let mut values = HashMap::new();
+    for (name, field) in definition.search.fields.iter() {
+        let value = match field.text {
+            Some(ref text) => Some(render(text, &context, definition.regexes())),
+            None => extract(definition, row, field),
+        }
+        .map(|value| apply_all(value, &field.filters, definition.regexes()))
+        .filter(|value| !value.is_empty())
+        .or_else(|| {
+            field
+                .default
+                .as_ref()
+                .map(|value| render(value, &context, definition.regexes()))
+        });
+        match value {
+            Some(value) => {
+                context.set(format!(".Result.{name}"), value.as_str());
+                values.insert(name.clone(), value);
+            }
+            None if field.optional => context.set(format!(".Result.{name}"), ""),
+            None => return Err(IndexerError::new(origin, missing(name))),
+        }
+    }
+    Ok(values)
+}
+
+/// Counts written like `1,200` or `1.2k`.
+fn number(value: &str) -> Result<usize, ParseNumberError> {
+    value
+        .trim()
+        .replace(',', "")
+        .parse::<Number>()
+        .map(|num| num.as_value())
+}
+
+fn to_entry(
+    base_url: &str,
+    origin: &'static str,
+    deferrable: bool,
+    mut values: HashMap<String, String>,
+) -> Result<IndexerEntry, IndexerError> {
+    let error = |reason| IndexerError::new(origin, reason);
+    let name = values
+        .remove("title")
+        .ok_or_else(|| error(IndexerErrorReason::EntryNameNotFound))?;
+    let download = values.remove("download");
+    let link = values
+        .remove("magnet")
+        .or_else(|| download.as_deref().map(|link| absolute(base_url, link)));
+    let url = values
+        .remove("details")
+        .map(|link| absolute(base_url, &link))
+        .or_else(|| link.clone())
+        .ok_or_else(|| error(IndexerErrorReason::EntryLinkNotFound))?;
+    // the download selectors find the link on the details page
+    let magnet = match link {
+        Some(link) => link,
+        None if deferrable => String::new(),
+        None => return Err(error(IndexerErrorReason::EntryLinkNotFound)),
+    };
+    let size = match values.remove("size") {
+        Some(value) => value
+            .replace(',', "")
+            .parse::<ByteSize>()
+            .map_err(|cause| error(IndexerErrorReason::EntrySizeInvalid { cause }))?,
+        None => ByteSize::default(),
+    };
+    let seeders = match values.remove("seeders") {
+        Some(value) => number(&value)
+            .map_err(|cause| error(IndexerErrorReason::EntrySeedersInvalid { cause }))?,
+        None => 0,
+    };
+    let leechers = match values.remove("leechers") {
+        Some(value) => number(&value)
+            .map_err(|cause| error(IndexerErrorReason::EntryLeechersInvalid { cause }))?,
+        None => 0,
+    };
+    let date = match values.remove("date") {
+        Some(value) => Some(
+            crate::date::parse(&value)
+                .map_err(|cause| error(IndexerErrorReason::EntryDateInvalid { cause }))?,
+        ),
+        None => None,
+    };
+    let info_hash = values
+        .remove("infohash")
+        .filter(|hash| hash.len() == 40)
+        .map(|hash| hash.to_uppercase())
+        .or_else(|| info_hash(&magnet));
+
+    Ok(IndexerEntry {
+        name,
+        url,
+        date,
+        size,
+        seeders,
+        leechers,
+        magnet,
+        origin,
+        uploader: values.remove("uploader"),
+        info_hash,
+        release: Default::default(),
+        deferred: None,
+        duplicates: Vec::new(),
+        files: Vec::new(),
+        suspicious: Vec::new(),
+    })
+}
+
+fn parse_rows(
+    definition: &Definition,
+    base_url: &str,
+    origin: &'static str,
+    rows: Vec<Row>,
+    context: &Context,
+) -> IndexerResult {
+    let deferrable = !definition.download.selectors.is_empty();
+    let mut results = IndexerResult::default();
+    for row in rows {
+        match fields(definition, origin, &row, context)
+            .and_then(|values| to_entry(base_url, origin, deferrable, values))
+        {
+            Ok(found) => results.entries.push(found),
+            Err(error) => results.errors.push(error),
+        }
+    }
+    results
+}
+
+/// Reads the entries of a search page, as described by the definition.
+pub fn parse(
+    definition: &Definition,
+    kind: ResponseKind,
+    base_url: &str,
+    origin: &'static str,
+    body: &str,
+    context: &Context,
+) -> IndexerResult {
+    match kind {
+        ResponseKind::Html => {
+            let html = Html::parse_document(body);
+            let rows = match definition.selector(&definition.search.rows.selector) {
+                Some(selector) => html.select(selector).map(Row::Html).collect(),
+                None => Vec::new(),
+            };
+            parse_rows(definition, base_url, origin, rows, context)
+        }
+        ResponseKind::Json => {
+            let document = match serde_json::from_str::<serde_json::Value>(body) {
+                Ok(value) => value,
+                Err(cause) => {
+                    return IndexerResult::from(IndexerError::new(
+                        origin,
+                        IndexerErrorReason::UnableToRead {
+                            url: base_url.to_string(),
+                            cause: cause.to_string(),
+                        },
+                    ))
+                }
+            };
+            let rows = match json_path(&document, &definition.search.rows.selector) {
+                Some(serde_json::Value::Array(items)) => items.iter().map(Row::Json).collect(),
+                _ => Vec::new(),
+            };
+            parse_rows(definition, base_url, origin, rows, context)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Definition, ResponseKind};
+    use crate::template::Context;
+    use manteau_indexer_prelude::IndexerErrorReason;
+
+    #[test]
+    fn should_parse_html_rows() {
+        let definition = Definition::from_yaml(include_str!("./definition.yml")).unwrap();
+        let result = super::parse(
+            &definition,
+            ResponseKind::Html,
+            "https://example.com/",
+            "example",
+            include_str!("./search.html"),
+            &Context::default(),
+        );
+        assert_eq!(result.entries.len(), 2);
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(
+            result.errors[0].reason,
+            IndexerErrorReason::EntryNameNotFound
+        ));
+        let first = &result.entries[0];
+        assert_eq!(first.name, "The Last of Us S01E01 1080p WEB h264");
+        assert_eq!(first.url, "https://example.com/torrent/1/the-last-of-us");
+        assert_eq!(first.size.to_string(), "2.1 GB");
+        assert_eq!(first.seeders, 1200);
+        assert_eq!(first.leechers, 42);
+        assert_eq!(
+            first.date.unwrap().to_rfc3339(),
+            "2023-01-16T03:05:00+00:00"
+        );
+        assert_eq!(first.info_hash.as_deref(), Some(&*"A".repeat(40)));
+    }
+
+    #[test]
+    fn should_parse_json_rows() {
+        let definition = Definition::from_yaml(
+            r#"
+id: json
+search:
+  paths:
+    - path: api/search
+      response:
+        type: json
+  rows:
+    selector: data.torrents
+  fields:
+    id:
+      selector: id
+    title:
+      selector: name
+    details:
+      text: "/torrent/{{ .Result.id }}"
+    infohash:
+      selector: hash
+    magnet:
+      text: "magnet:?xt=urn:btih:{{ .Result.infohash }}"
+    size:
+      selector: size
+    seeders:
+      selector: stats.seeders
+    leechers:
+      selector: stats.leechers
+      optional: true
+"#,
+        )
+        .unwrap();
+        let body = format!(
+            r#"{{"data":{{"torrents":[{{"id":7,"name":"Dune 2021","hash":"{}","size":1024,"stats":{{"seeders":3}}}}]}}}}"#,
+            "b".repeat(40)
+        );
+        let result = super::parse(
+            &definition,
+            ResponseKind::Json,
+            "https://example.com/",
+            "json",
+            &body,
+            &Context::default(),
+        );
+        assert!(result.errors.is_empty());
+        let entry = &result.entries[0];
+        assert_eq!(entry.name, "Dune 2021");
+        assert_eq!(entry.url, "https://example.com/torrent/7");
+        assert_eq!(
+            entry.magnet,
+            format!("magnet:?xt=urn:btih:{}", "b".repeat(40))
+        );
+        assert_eq!(entry.info_hash, Some("B".repeat(40)));
+        assert_eq!(entry.size.as_u64(), 1024);
+        assert_eq!((entry.seeders, entry.leechers), (3, 0));
+    }
+}
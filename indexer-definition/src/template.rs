@@ -0,0 +1,323 @@
+//! Subset of the Go templates used by the definitions, like `{{ .Keywords }}` or
+//! `{{ if .Query.Season }}S{{ .Query.Season }}{{ end }}`.
+
+use crate::definition::Regexes;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Values given to the templates, like `.Keywords` or `.Result.title`.
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    values: HashMap<String, String>,
+    lists: HashMap<String, Vec<String>>,
+}
+
+impl Context {
+    pub fn with_value<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    pub fn with_list<K: Into<String>>(mut self, key: K, values: Vec<String>) -> Self {
+        self.lists.insert(key.into(), values);
+        self
+    }
+
+    pub fn set<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    fn value(&self, key: &str) -> String {
+        match self.values.get(key) {
+            Some(found) => found.clone(),
+            None => self.list(key).join(","),
+        }
+    }
+
+    fn list(&self, key: &str) -> Vec<String> {
+        self.lists.get(key).cloned().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Argument {
+    Variable(String),
+    Literal(String),
+    Word(String),
+}
+
+/// Splits an action, the quoted values staying together.
+fn arguments(action: &str) -> Vec<Argument> {
+    let mut result = Vec::new();
+    let mut chars = action.trim().chars().peekable();
+    while let Some(&next) = chars.peek() {
+        if next.is_whitespace() {
+            chars.next();
+        } else if next == '"' || next == '`' {
+            chars.next();
+            let mut value = String::new();
+            while let Some(current) = chars.next() {
+                match current {
+                    // other escapes are kept, like in `"\s+"`
+                    '\\' if next == '"' => match chars.next() {
+                        Some(escaped @ ('"' | '\\')) => value.push(escaped),
+                        Some(other) => {
+                            value.push('\\');
+                            value.push(other);
+                        }
+                        None => value.push('\\'),
+                    },
+                    current if current == next => break,
+                    current => value.push(current),
+                }
+            }
+            result.push(Argument::Literal(value));
+        } else {
+            let mut value = String::new();
+            while let Some(&current) = chars.peek() {
+                if current.is_whitespace() {
+                    break;
+                }
+                value.push(current);
+                chars.next();
+            }
+            result.push(match value.starts_with('.') {
+                true => Argument::Variable(value),
+                false => Argument::Word(value),
+            });
+        }
+    }
+    result
+}
+
+fn value(context: &Context, argument: &Argument) -> String {
+    match argument {
+        Argument::Variable(name) => context.value(name),
+        Argument::Literal(value) | Argument::Word(value) => value.clone(),
+    }
+}
+
+fn boolean(value: bool) -> String {
+    match value {
+        true => "true".into(),
+        false => String::new(),
+    }
+}
+
+/// Patterns given as text to `re_replace`, compiled once with the definition.
+pub fn patterns(template: &str) -> Vec<String> {
+    template
+        .split("{{")
+        .skip(1)
+        .filter_map(|tail| tail.split_once("}}").map(|(action, _)| action))
+        .filter_map(|action| {
+            let action = action.trim_matches('-').trim();
+            let action = action.strip_prefix("else ").unwrap_or(action);
+            let action = action.strip_prefix("if ").unwrap_or(action);
+            match arguments(action).as_slice() {
+                [Argument::Word(function), _, Argument::Literal(pattern), ..]
+                    if function == "re_replace" =>
+                {
+                    Some(pattern.clone())
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Result of an action, an empty text being false.
+fn evaluate(context: &Context, regexes: &Regexes, action: &str) -> String {
+    let args = arguments(action);
+    let Some((first, rest)) = args.split_first() else {
+        return String::new();
+    };
+    let Argument::Word(function) = first else {
+        return value(context, first);
+    };
+    let values = rest
+        .iter()
+        .map(|argument| value(context, argument))
+        .collect::<Vec<_>>();
+    match (function.as_str(), values.as_slice()) {
+        ("join", [_, separator]) => match rest.first() {
+            Some(Argument::Variable(name)) => context.list(name).join(separator),
+            _ => String::new(),
+        },
+        ("re_replace", [input, pattern, replacement]) => match regexes.get(pattern) {
+            Some(regex) => regex.replace_all(input, replacement.as_str()).into_owned(),
+            // only the patterns given as text are known beforehand
+            None => match Regex::new(pattern) {
+                Ok(regex) => regex.replace_all(input, replacement.as_str()).into_owned(),
+                Err(error) => {
+                    tracing::debug!("invalid regex in template: {error}");
+                    input.clone()
+                }
+            },
+        },
+        ("eq", [first, others @ ..]) => boolean(others.iter().any(|other| other == first)),
+        ("ne", [first, second]) => boolean(first != second),
+        ("not", [inner]) => boolean(inner.is_empty()),
+        ("and", values) => values
+            .iter()
+            .find(|value| value.is_empty())
+            .or(values.last())
+            .cloned()
+            .unwrap_or_default(),
+        ("or", values) => values
+            .iter()
+            .find(|value| !value.is_empty())
+            .cloned()
+            .unwrap_or_default(),
+        _ => {
+            tracing::debug!("unsupported template action {action:?}");
+            String::new()
+        }
+    }
+}
+
+/// State of an `if` block.
+struct Branch {
+    // the block is in a part being written
+    parent: bool,
+    // one of the conditions was true already
+    taken: bool,
+    current: bool,
+}
+
+pub fn render(template: &str, context: &Context, regexes: &Regexes) -> String {
+    let mut output = String::new();
+    let mut branches: Vec<Branch> = Vec::new();
+    let mut rest = template;
+    let mut trim_next = false;
+    loop {
+        let writing = branches.last().is_none_or(|branch| branch.current);
+        let (text, action) = match rest.split_once("{{") {
+            Some((text, tail)) => match tail.split_once("}}") {
+                Some((action, tail)) => {
+                    rest = tail;
+                    (text, Some(action))
+                }
+                None => (rest, None),
+            },
+            None => (rest, None),
+        };
+        let text = match trim_next {
+            true => text.trim_start(),
+            false => text,
+        };
+        if writing {
+            output.push_str(text);
+        }
+        let Some(action) = action else {
+            break;
+        };
+        if let Some(trimmed) = action.strip_prefix('-') {
+            if writing {
+                output.truncate(output.trim_end().len());
+            }
+            trim_next = trimmed.ends_with('-');
+        } else {
+            trim_next = action.ends_with('-');
+        }
+        let action = action.trim_start_matches('-').trim_end_matches('-').trim();
+
+        if let Some(condition) = action.strip_prefix("if ") {
+            let current = writing && !evaluate(context, regexes, condition).is_empty();
+            branches.push(Branch {
+                parent: writing,
+                taken: current,
+                current,
+            });
+        } else if action == "else" || action.starts_with("else if ") {
+            if let Some(branch) = branches.last_mut() {
+                let current = branch.parent
+                    && !branch.taken
+                    && match action.strip_prefix("else if ") {
+                        Some(condition) => !evaluate(context, regexes, condition).is_empty(),
+                        None => true,
+                    };
+                branch.taken |= current;
+                branch.current = current;
+            }
+        } else if action == "end" {
+            branches.pop();
+        } else if writing {
+            output.push_str(&evaluate(context, regexes, action));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{patterns, Context};
+    use crate::definition::Regexes;
+
+    fn render(template: &str, context: &Context) -> String {
+        let patterns = patterns(template);
+        let regexes = Regexes::compile(patterns.iter().map(String::as_str)).unwrap();
+        super::render(template, context, &regexes)
+    }
+
+    fn context() -> Context {
+        Context::default()
+            .with_value(".Keywords", "the last of us")
+            .with_value(".Query.Season", "1")
+            .with_value(".Query.Ep", "")
+            .with_value(".Config.sitelink", "https://example.com/")
+            .with_list(".Categories", vec!["5".into(), "6".into()])
+    }
+
+    #[test]
+    fn should_render_values() {
+        let context = context();
+        assert_eq!(
+            render("{{ .Config.sitelink }}search/{{ .Keywords }}/", &context),
+            "https://example.com/search/the last of us/"
+        );
+        assert_eq!(render("{{ .Categories }}", &context), "5,6");
+        assert_eq!(render(r#"{{ join .Categories ";" }}"#, &context), "5;6");
+        assert_eq!(
+            render(r#"{{ re_replace .Keywords "\\s+" "." }}"#, &context),
+            "the.last.of.us"
+        );
+        assert_eq!(render("{{ .Unknown }}", &context), "");
+    }
+
+    #[test]
+    fn should_find_regex_patterns() {
+        assert_eq!(
+            patterns(
+                r#"{{ if .Keywords }}{{ re_replace .Keywords "\s+" "." }}{{ else }}all{{ end }}"#
+            ),
+            vec![r"\s+"]
+        );
+        assert!(patterns(r#"{{ re_replace .Keywords .Config.pattern "." }}"#).is_empty());
+    }
+
+    #[test]
+    fn should_render_conditions() {
+        let context = context();
+        let template = "{{ if .Query.Ep }}episode{{ else if .Query.Season }}season {{ .Query.Season }}{{ else }}all{{ end }}";
+        assert_eq!(render(template, &context), "season 1");
+        assert_eq!(
+            render(
+                r#"{{ if and .Keywords .Query.Ep }}a{{ end }}{{ if or .Query.Ep .Keywords }}b{{ end }}"#,
+                &context
+            ),
+            "b"
+        );
+        assert_eq!(
+            render(
+                r#"{{ if eq .Query.Season "1" }}first{{ if .Query.Ep }}-{{ .Query.Ep }}{{ end }}{{ end }}"#,
+                &context
+            ),
+            "first"
+        );
+        assert_eq!(
+            render("a  {{- if .Keywords -}}  b  {{- end -}}  c", &context),
+            "abc"
+        );
+    }
+}
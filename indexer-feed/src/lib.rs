@@ -0,0 +1,245 @@
+use manteau_indexer_helper::text::{tokenize, Token};
+use manteau_indexer_prelude::capabilities::Capabilities;
+use manteau_indexer_prelude::http::HttpClient;
+use manteau_indexer_prelude::{
+    leak_origin, Category, Indexer, IndexerBuilder, IndexerResult, SearchRequest,
+};
+use std::sync::Arc;
+
+mod parser;
+
+pub use parser::Patterns;
+
+pub const NAME: &str = "feed";
+
+/// Every word of the query is in the name, a season matching each of its episodes.
+fn matches(name: &str, query: &[Token]) -> bool {
+    let tokens = tokenize(name);
+    query.iter().all(|wanted| {
+        tokens.iter().any(|found| match (wanted, found) {
+            (
+                Token::Episode {
+                    season,
+                    episode: None,
+                },
+                Token::Episode { season: other, .. },
+            ) => season == other,
+            _ => wanted == found,
+        })
+    })
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct FeedSource {
+    pub category: Category,
+    pub url: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct IndexerFeedConfig {
+    pub feeds: Vec<FeedSource>,
+    #[serde(default)]
+    pub patterns: Patterns,
+}
+
+impl IndexerBuilder for IndexerFeedConfig {
+    fn build(self, name: String, client: HttpClient) -> Box<dyn Indexer + Send + Sync + 'static> {
+        tracing::info!("building feed indexer named {name:?}");
+        let mut indexer = IndexerFeed::new(self.feeds).with_patterns(self.patterns);
+        indexer.origin = leak_origin(&name);
+        indexer.name = name;
+        indexer.client = client;
+        Box::new(indexer)
+    }
+}
+
+/// Indexer reading the RSS or Atom feeds published by a site, searching in their entries.
+#[derive(Debug)]
+pub struct IndexerFeed {
+    name: String,
+    origin: &'static str,
+    feeds: Vec<FeedSource>,
+    patterns: Arc<Patterns>,
+    client: HttpClient,
+}
+
+impl IndexerFeed {
+    pub fn new(feeds: Vec<FeedSource>) -> Self {
+        Self {
+            name: NAME.into(),
+            origin: NAME,
+            feeds,
+            patterns: Arc::new(Patterns::default()),
+            client: HttpClient::default(),
+        }
+    }
+
+    pub fn with_patterns(mut self, patterns: Patterns) -> Self {
+        self.patterns = Arc::new(patterns);
+        self
+    }
+
+    async fn fetch_feed(&self, url: &str) -> IndexerResult {
+        match self.client.get_text(self.origin, url).await {
+            Ok(body) => parser::parse(self.origin, url, &body, &self.patterns),
+            Err(error) => IndexerResult::from(error),
+        }
+    }
+
+    /// Entries of the feeds in any of the categories, or of all of them.
+    async fn fetch(&self, categories: &[Category]) -> IndexerResult {
+        let mut urls: Vec<&str> = Vec::new();
+        for feed in self.feeds.iter() {
+            let wanted = categories.is_empty()
+                || categories
+                    .iter()
+                    .any(|category| category.kind() == feed.category.kind());
+            if wanted && !urls.contains(&feed.url.as_str()) {
+                urls.push(feed.url.as_str());
+            }
+        }
+        futures::future::join_all(urls.into_iter().map(|url| self.fetch_feed(url)))
+            .await
+            .into_iter()
+            .fold(IndexerResult::default(), IndexerResult::merge)
+    }
+}
+
+#[async_trait::async_trait]
+impl Indexer for IndexerFeed {
+    async fn search(&self, request: &SearchRequest) -> IndexerResult {
+        let query = request.keywords();
+        if query.is_empty() {
+            tracing::debug!("{} skipping search without keywords", self.name);
+            return IndexerResult::default();
+        }
+        tracing::debug!("{} searching {query:?}", self.name);
+        let query = tokenize(&query);
+        let mut result = self.fetch(&request.categories).await;
+        // the feeds can't be searched, only their latest entries are matched
+        result.entries.retain(|entry| matches(&entry.name, &query));
+        result
+    }
+
+    async fn feed(&self, category: Category) -> IndexerResult {
+        tracing::debug!("{} fetching feed for {category:?}", self.name);
+        self.fetch(&[category]).await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.feeds.iter().fold(
+            Capabilities::default().with_keyword_searches(),
+            |caps, feed| {
+                let found = caps
+                    .categories
+                    .iter()
+                    .any(|mapping| mapping.category == feed.category);
+                match found {
+                    true => caps,
+                    false => caps.with_category(feed.category.name(), feed.category),
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FeedSource, IndexerFeed, IndexerFeedConfig};
+    use manteau_indexer_helper::text::tokenize;
+    use manteau_indexer_prelude::{Category, Indexer, IndexerBuilder, SearchRequest};
+
+    #[test]
+    fn should_match_names() {
+        let name = "Dark.S01E02.1080p.WEB";
+        assert!(super::matches(name, &tokenize("dark")));
+        assert!(super::matches(name, &tokenize("Dark S01E02")));
+        assert!(super::matches(name, &tokenize("dark S01")));
+        assert!(!super::matches(name, &tokenize("dark S01E03")));
+        assert!(!super::matches(name, &tokenize("darkness")));
+        assert!(super::matches(name, &[]));
+    }
+
+    #[tokio::test]
+    async fn should_search_in_feeds() {
+        let mut server = mockito::Server::new_async().await;
+        let indexer = IndexerFeed::new(vec![
+            FeedSource {
+                category: Category::Tv,
+                url: format!("{}/anime.rss", server.url()),
+            },
+            FeedSource {
+                category: Category::Movie,
+                url: format!("{}/movies.atom", server.url()),
+            },
+        ]);
+
+        let rss = server
+            .mock("GET", "/anime.rss")
+            .with_status(200)
+            .with_header("content-type", "application/rss+xml")
+            .with_body(include_str!("./feed.rss"))
+            .expect(2)
+            .create_async()
+            .await;
+        let atom = server
+            .mock("GET", "/movies.atom")
+            .with_status(200)
+            .with_header("content-type", "application/atom+xml")
+            .with_body(include_str!("./feed.atom"))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let result = indexer.search(&SearchRequest::new("frieren")).await;
+        assert_eq!(result.entries.len(), 1);
+        assert_eq!(result.entries[0].name, "[Group] Frieren - 01 [1080p]");
+
+        // not the whole feed, for the ids it can't search
+        let request = SearchRequest {
+            imdb_id: Some("tt0133093".into()),
+            ..Default::default()
+        };
+        assert!(indexer.search(&request).await.entries.is_empty());
+
+        let result = indexer.feed(Category::Tv).await;
+        assert_eq!(result.entries.len(), 2);
+        assert_eq!(result.errors.len(), 1);
+
+        rss.assert_async().await;
+        atom.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_build_from_config() {
+        let mut server = mockito::Server::new_async().await;
+        let config: IndexerFeedConfig = serde_json::from_value(serde_json::json!({
+            "feeds": [{ "category": 2000, "url": format!("{}/movies.atom", server.url()) }],
+            "patterns": { "size": r"Size: (\S+ \S+)" },
+        }))
+        .unwrap();
+        let feed = server
+            .mock("GET", "/movies.atom")
+            .with_status(200)
+            .with_body(include_str!("./feed.atom"))
+            .create_async()
+            .await;
+
+        let indexer = config.build("group".into(), Default::default());
+        let result = indexer.feed(Category::Movie).await;
+        assert_eq!(result.entries[0].origin, "group");
+        assert_eq!(
+            indexer.capabilities().newznab_categories(),
+            vec![Category::Movie]
+        );
+
+        let error = serde_json::from_value::<IndexerFeedConfig>(serde_json::json!({
+            "feeds": [],
+            "patterns": { "size": "(" },
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("regex"));
+
+        feed.assert_async().await;
+    }
+}
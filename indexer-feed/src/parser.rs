@@ -0,0 +1,340 @@
+use bytesize::ByteSize;
+use chrono::{DateTime, Utc};
+use manteau_indexer_helper::magnet::info_hash;
+use manteau_indexer_helper::numeric::{Number, ParseNumberError};
+use manteau_indexer_prelude::{IndexerEntry, IndexerError, IndexerErrorReason, IndexerResult};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Regexes reading the values in the description of the items, the first group being the value.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct Patterns {
+    #[serde(default, deserialize_with = "deserialize_pattern")]
+    pub size: Option<Regex>,
+    #[serde(default, deserialize_with = "deserialize_pattern")]
+    pub seeders: Option<Regex>,
+    #[serde(default, deserialize_with = "deserialize_pattern")]
+    pub leechers: Option<Regex>,
+}
+
+fn deserialize_pattern<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value: Option<String> = serde::Deserialize::deserialize(deserializer)?;
+    value
+        .map(|pattern| Regex::new(&pattern).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn capture(pattern: Option<&Regex>, value: &str) -> Option<String> {
+    let found = pattern?.captures(value)?;
+    found
+        .get(1)
+        .or_else(|| found.get(0))
+        .map(|m| m.as_str().trim().to_string())
+}
+
+/// What the entries of both formats have in common.
+#[derive(Debug, Default)]
+struct FeedItem {
+    title: Option<String>,
+    /// Page describing the release.
+    page: Option<String>,
+    /// Magnet or torrent url.
+    download: Option<String>,
+    /// Length of the enclosure, in bytes.
+    length: Option<String>,
+    description: String,
+    date: Option<String>,
+    /// Values of the namespaced elements like `nyaa:seeders`, by lowercase name.
+    extensions: HashMap<String, String>,
+}
+
+impl FeedItem {
+    fn extension(&self, names: &[&str]) -> Option<String> {
+        names
+            .iter()
+            .find_map(|name| self.extensions.get(*name))
+            .cloned()
+    }
+}
+
+fn downloadable(link: &str) -> bool {
+    link.starts_with("magnet:") || link.ends_with(".torrent")
+}
+
+fn extensions<'a, I>(values: I) -> HashMap<String, String>
+where
+    I: Iterator<Item = (&'a String, Option<&'a str>)>,
+{
+    values
+        .filter_map(|(name, value)| Some((name.to_lowercase(), value?.trim().to_string())))
+        .collect()
+}
+
+fn from_rss(item: &rss::Item) -> FeedItem {
+    let enclosure = item.enclosure();
+    let link = item.link().map(str::to_string);
+    let download = enclosure
+        .map(|enclosure| enclosure.url().to_string())
+        .or_else(|| link.clone().filter(|link| downloadable(link)));
+    let page = link
+        .filter(|link| Some(link) != download.as_ref())
+        .or_else(|| item.comments().map(str::to_string))
+        .or_else(|| {
+            item.guid()
+                .filter(|guid| guid.is_permalink() && guid.value().starts_with("http"))
+                .map(|guid| guid.value().to_string())
+        });
+    FeedItem {
+        title: item.title().map(str::to_string),
+        page,
+        download,
+        length: enclosure.map(|enclosure| enclosure.length().to_string()),
+        description: item.description().unwrap_or_default().to_string(),
+        date: item.pub_date().map(str::to_string),
+        extensions: extensions(
+            item.extensions()
+                .values()
+                .flatten()
+                .filter_map(|(name, values)| Some((name, values.first()?.value()))),
+        ),
+    }
+}
+
+fn from_atom(entry: &atom_syndication::Entry) -> FeedItem {
+    let enclosure = entry.links().iter().find(|link| {
+        link.rel() == "enclosure"
+            || link.mime_type() == Some("application/x-bittorrent")
+            || link.href().starts_with("magnet:")
+    });
+    let page = entry
+        .links()
+        .iter()
+        .find(|link| link.rel() == "alternate" && Some(*link) != enclosure)
+        .map(|link| link.href().to_string());
+    let description = entry
+        .summary()
+        .map(|summary| summary.as_str())
+        .or_else(|| entry.content().and_then(|content| content.value()))
+        .unwrap_or_default();
+    FeedItem {
+        title: Some(entry.title().as_str().to_string()),
+        page,
+        download: enclosure.map(|link| link.href().to_string()),
+        length: enclosure.and_then(|link| link.length()).map(str::to_string),
+        description: description.to_string(),
+        date: Some(entry.published().unwrap_or(entry.updated()).to_rfc2822()),
+        extensions: extensions(
+            entry
+                .extensions()
+                .values()
+                .flatten()
+                .filter_map(|(name, values)| Some((name, values.first()?.value()))),
+        ),
+    }
+}
+
+fn parse_count(value: Option<String>) -> Result<usize, ParseNumberError> {
+    match value {
+        Some(value) => value
+            .replace(',', "")
+            .parse::<Number>()
+            .map(|num| num.as_value()),
+        None => Ok(0),
+    }
+}
+
+fn to_entry(
+    origin: &'static str,
+    patterns: &Patterns,
+    item: FeedItem,
+) -> Result<IndexerEntry, IndexerError> {
+    let error = |reason| IndexerError::new(origin, reason);
+    let name = item
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .ok_or_else(|| error(IndexerErrorReason::EntryNameNotFound))?
+        .to_string();
+    let magnet = item
+        .extension(&["magneturi"])
+        .or_else(|| item.download.clone())
+        .ok_or_else(|| error(IndexerErrorReason::EntryLinkNotFound))?;
+    let size = item
+        .extension(&["contentlength", "size"])
+        .or_else(|| item.length.clone().filter(|length| length != "0"))
+        .or_else(|| capture(patterns.size.as_ref(), &item.description))
+        .map(|value| value.replace(',', "").parse::<ByteSize>())
+        .transpose()
+        .map_err(|cause| error(IndexerErrorReason::EntrySizeInvalid { cause }))?
+        .unwrap_or_default();
+    let seeders = parse_count(
+        item.extension(&["seeders", "seeds"])
+            .or_else(|| capture(patterns.seeders.as_ref(), &item.description)),
+    )
+    .map_err(|cause| error(IndexerErrorReason::EntrySeedersInvalid { cause }))?;
+    let leechers = match item.extension(&["leechers"]) {
+        Some(value) => parse_count(Some(value)),
+        // the peers count the seeders too
+        None => match item.extension(&["peers"]) {
+            Some(value) => parse_count(Some(value)).map(|peers| peers.saturating_sub(seeders)),
+            None => parse_count(capture(patterns.leechers.as_ref(), &item.description)),
+        },
+    }
+    .map_err(|cause| error(IndexerErrorReason::EntryLeechersInvalid { cause }))?;
+    let date = match item.date {
+        Some(ref value) => Some(
+            DateTime::parse_from_rfc2822(value.trim())
+                .map(|date| date.with_timezone(&Utc))
+                .map_err(|err| error(IndexerErrorReason::EntryDateInvalid { cause: err.kind() }))?,
+        ),
+        None => None,
+    };
+    let info_hash = item
+        .extension(&["infohash"])
+        .filter(|hash| hash.len() == 40)
+        .map(|hash| hash.to_uppercase())
+        .or_else(|| info_hash(&magnet));
+
+    Ok(IndexerEntry {
+        name,
+        url: item.page.unwrap_or_else(|| magnet.clone()),
+        date,
+        size,
+        seeders,
+        leechers,
+        magnet,
+        origin,
+        uploader: None,
+        info_hash,
+        release: Default::default(),
+        deferred: None,
+        duplicates: Vec::new(),
+        files: Vec::new(),
+        suspicious: Vec::new(),
+    })
+}
+
+/// Reads the entries of a RSS 2.0 or Atom feed.
+pub fn parse(origin: &'static str, url: &str, body: &str, patterns: &Patterns) -> IndexerResult {
+    let items = match rss::Channel::read_from(body.as_bytes()) {
+        Ok(channel) => channel.items().iter().map(from_rss).collect::<Vec<_>>(),
+        Err(rss_error) => match atom_syndication::Feed::read_from(body.as_bytes()) {
+            Ok(feed) => feed.entries().iter().map(from_atom).collect(),
+            Err(atom_error) => {
+                return IndexerResult::from(IndexerError::new(
+                    origin,
+                    IndexerErrorReason::UnableToRead {
+                        url: url.to_string(),
+                        cause: format!("neither rss ({rss_error}) nor atom ({atom_error})"),
+                    },
+                ))
+            }
+        },
+    };
+    let mut results = IndexerResult::default();
+    for item in items {
+        match to_entry(origin, patterns, item) {
+            Ok(found) => results.entries.push(found),
+            Err(error) => results.errors.push(error),
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Patterns;
+    use manteau_indexer_prelude::IndexerErrorReason;
+
+    fn patterns() -> Patterns {
+        serde_json::from_value(serde_json::json!({
+            "size": r"Size: ([0-9.]+ [KMGT]i?B)",
+            "seeders": r"Seeders: (\d+)",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn should_parse_rss() {
+        let result = super::parse(
+            "feed",
+            "http://feed",
+            include_str!("./feed.rss"),
+            &patterns(),
+        );
+        assert_eq!(result.entries.len(), 2);
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(
+            result.errors[0].reason,
+            IndexerErrorReason::EntryLinkNotFound
+        ));
+
+        let first = &result.entries[0];
+        assert_eq!(first.name, "[Group] Frieren - 01 [1080p]");
+        assert_eq!(first.url, "https://tracker.example/view/1");
+        assert_eq!(first.magnet, "https://tracker.example/download/1.torrent");
+        assert_eq!(first.size.as_u64(), 1_503_238_553);
+        assert_eq!((first.seeders, first.leechers), (250, 12));
+        assert_eq!(first.info_hash, Some("A".repeat(40)));
+        assert_eq!(
+            first.date.unwrap().to_rfc3339(),
+            "2023-09-29T15:00:00+00:00"
+        );
+
+        // read from the description
+        let second = &result.entries[1];
+        assert!(second.magnet.starts_with("magnet:?"));
+        assert_eq!(second.url, second.magnet);
+        assert_eq!(second.size.as_u64(), 700_000_000);
+        assert_eq!((second.seeders, second.leechers), (14, 0));
+        assert_eq!(second.info_hash, Some("B".repeat(40)));
+    }
+
+    #[test]
+    fn should_parse_atom() {
+        let result = super::parse(
+            "feed",
+            "http://feed",
+            include_str!("./feed.atom"),
+            &Patterns::default(),
+        );
+        assert!(result.errors.is_empty());
+        assert_eq!(result.entries.len(), 1);
+        let entry = &result.entries[0];
+        assert_eq!(entry.name, "Dune.2021.1080p.WEB-DL");
+        assert_eq!(entry.url, "https://group.example/releases/dune");
+        assert_eq!(entry.magnet, "https://group.example/torrents/dune.torrent");
+        assert_eq!(entry.size.as_u64(), 2_000_000_000);
+        assert_eq!(
+            entry.date.unwrap().to_rfc3339(),
+            "2021-10-22T08:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn should_count_leechers_out_of_peers() {
+        let body = format!(
+            r#"<rss version="2.0" xmlns:torrent="http://xmlns.ezrss.it/0.1/"><channel>
+<title>feed</title><link>http://feed</link><description>feed</description>
+<item><title>Dune.2021.1080p</title><link>magnet:?xt=urn:btih:{}</link>
+<torrent:seeds>30</torrent:seeds><torrent:peers>42</torrent:peers></item>
+</channel></rss>"#,
+            "C".repeat(40)
+        );
+        let result = super::parse("feed", "http://feed", &body, &Patterns::default());
+        let entry = &result.entries[0];
+        assert_eq!((entry.seeders, entry.leechers), (30, 12));
+        // without a pubDate
+        assert!(entry.date.is_none());
+    }
+
+    #[test]
+    fn should_reject_other_documents() {
+        let result = super::parse("feed", "http://feed", "<html></html>", &Patterns::default());
+        assert!(result.is_failure());
+    }
+}
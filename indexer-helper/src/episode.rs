@@ -0,0 +1,295 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+// S01E02, S01E02E03, S01E02-E03, S01E02-03, S01.E02
+static EPISODE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\bs(\d{1,3})[ ._-]?e(\d{1,4})(?:(?:[ ._]?-[ ._]?e?|e)(\d{1,4}))?\b").unwrap()
+});
+// 1x02, 1x02-03
+static CROSS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b(\d{1,2})x(\d{2,3})(?:-(\d{2,3}))?\b").unwrap());
+// S01-S03, S01-03
+static SEASONS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\bs(\d{1,2})[ ._]?-[ ._]?s?(\d{1,2})\b").unwrap());
+// S01, Season 1, Season.01
+static SEASON: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b(?:s|season[ ._-]?)(\d{1,2})\b").unwrap());
+
+/// Season and episodes a release is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Episodes {
+    pub season: u16,
+    /// Last season of a pack covering several seasons, same as `season` otherwise.
+    pub last_season: u16,
+    /// First and last episodes, `None` for a whole season pack.
+    pub episodes: Option<(u16, u16)>,
+}
+
+impl Episodes {
+    fn single(season: u16, first: u16, last: Option<u16>) -> Self {
+        let last = last.filter(|last| *last > first).unwrap_or(first);
+        Self {
+            season,
+            last_season: season,
+            episodes: Some((first, last)),
+        }
+    }
+
+    /// Checks that the release has the given episode, or the given season when no
+    /// episode is asked.
+    pub fn contains(&self, season: u16, episode: Option<u16>) -> bool {
+        if season < self.season || season > self.last_season {
+            return false;
+        }
+        match (episode, self.episodes) {
+            (Some(episode), Some((first, last))) => first <= episode && episode <= last,
+            _ => true,
+        }
+    }
+}
+
+fn number(value: Option<regex::Match>) -> Option<u16> {
+    value.and_then(|found| found.as_str().parse().ok())
+}
+
+fn start(found: &regex::Captures) -> usize {
+    found.get(0).map(|whole| whole.start()).unwrap_or_default()
+}
+
+/// Finds the season and episodes in a release name, with the position they start at.
+pub(crate) fn find(name: &str) -> Option<(usize, Episodes)> {
+    // underscores are word characters for the regexes
+    let name = name.replace('_', " ");
+    if let Some(found) = EPISODE.captures(&name) {
+        let episodes = Episodes::single(
+            number(found.get(1))?,
+            number(found.get(2))?,
+            number(found.get(3)),
+        );
+        return Some((start(&found), episodes));
+    }
+    if let Some(found) = CROSS.captures(&name) {
+        let episodes = Episodes::single(
+            number(found.get(1))?,
+            number(found.get(2))?,
+            number(found.get(3)),
+        );
+        return Some((start(&found), episodes));
+    }
+    if let Some(found) = SEASONS.captures(&name) {
+        let season = number(found.get(1))?;
+        let episodes = Episodes {
+            season,
+            last_season: number(found.get(2))?.max(season),
+            episodes: None,
+        };
+        return Some((start(&found), episodes));
+    }
+    let found = SEASON.captures(&name)?;
+    let season = number(found.get(1))?;
+    let episodes = Episodes {
+        season,
+        last_season: season,
+        episodes: None,
+    };
+    Some((start(&found), episodes))
+}
+
+/// Finds the season and episodes in a release name.
+pub fn parse(name: &str) -> Option<Episodes> {
+    find(name).map(|(_, episodes)| episodes)
+}
+
+/// What a tv search is looking for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Season(u16),
+    Episode {
+        season: u16,
+        episode: u16,
+    },
+    /// Episode of a daily show, identified by its air date.
+    Daily {
+        year: u16,
+        month: u8,
+        day: u8,
+    },
+    /// Episode numbered from the start of the show, like for anime.
+    Absolute(u32),
+}
+
+impl Target {
+    /// Reads the `season` and `ep` parameters of a tv search, as sent by Sonarr.
+    pub fn from_params(season: Option<&str>, episode: Option<&str>) -> Option<Self> {
+        let season = season.map(|value| value.trim().parse::<u16>().ok());
+        match (season, episode.map(str::trim)) {
+            // season=2023&ep=10/12
+            (Some(Some(year)), Some(date)) if date.contains('/') => {
+                let (month, day) = date.split_once('/')?;
+                Some(Self::Daily {
+                    year,
+                    month: month.parse().ok()?,
+                    day: day.parse().ok()?,
+                })
+            }
+            (Some(Some(season)), Some(episode)) => Some(Self::Episode {
+                season,
+                episode: episode.parse().ok()?,
+            }),
+            (Some(Some(season)), None) => Some(Self::Season(season)),
+            (None, Some(episode)) => Some(Self::Absolute(episode.parse().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// Ways of writing the target after the title, by decreasing popularity, the last ones
+    /// also matching the season packs.
+    pub fn notations(&self) -> Vec<String> {
+        match *self {
+            Self::Season(season) => vec![format!(" S{season:02}"), format!(" Season {season}")],
+            Self::Episode { season, episode } => vec![
+                format!(" S{season:02}E{episode:02}"),
+                format!(" {season}x{episode:02}"),
+                format!(" Season {season}"),
+            ],
+            Self::Daily { year, month, day } => vec![
+                format!(" {year}.{month:02}.{day:02}"),
+                format!(" {year} {month:02} {day:02}"),
+            ],
+            Self::Absolute(number) => vec![format!(" - {number:02}"), format!(" {number:02}")],
+        }
+    }
+
+    /// Matcher of the releases about the target, to be built once for all of them.
+    pub fn matcher(&self) -> TargetMatcher {
+        let pattern = match *self {
+            Self::Season(_) | Self::Episode { .. } => None,
+            Self::Daily { year, month, day } => {
+                Some(format!(r"\b{year}[ ._-]0?{month}[ ._-]0?{day}\b"))
+            }
+            // Show - 105, Show - 105v2, Show E105, Show #105
+            Self::Absolute(number) => {
+                Some(format!(r"(?i)(?:\s-\s*|\bep?\s?|#)0*{number}(?:v\d)?\b"))
+            }
+        };
+        TargetMatcher {
+            target: *self,
+            pattern: pattern.and_then(|pattern| Regex::new(&pattern).ok()),
+        }
+    }
+
+    /// Checks that the release, given its name and parsed episodes, is about the target.
+    pub fn matches(&self, name: &str, episodes: Option<Episodes>) -> bool {
+        self.matcher().matches(name, episodes)
+    }
+}
+
+/// Target with the pattern of its name already compiled, for daily and absolute episodes.
+#[derive(Clone, Debug)]
+pub struct TargetMatcher {
+    target: Target,
+    pattern: Option<Regex>,
+}
+
+impl TargetMatcher {
+    /// Checks that the release, given its name and parsed episodes, is about the target.
+    pub fn matches(&self, name: &str, episodes: Option<Episodes>) -> bool {
+        match self.target {
+            Target::Season(season) => episodes.is_some_and(|found| found.contains(season, None)),
+            Target::Episode { season, episode } => {
+                episodes.is_some_and(|found| found.contains(season, Some(episode)))
+            }
+            Target::Daily { .. } | Target::Absolute(_) => self
+                .pattern
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_match(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Episodes, Target};
+
+    fn episodes(season: u16, last_season: u16, episodes: Option<(u16, u16)>) -> Option<Episodes> {
+        Some(Episodes {
+            season,
+            last_season,
+            episodes,
+        })
+    }
+
+    #[test]
+    fn should_parse_episodes() {
+        assert_eq!(parse("Dark.S01E02.1080p"), episodes(1, 1, Some((2, 2))));
+        assert_eq!(parse("Dark.S01E02.2017"), episodes(1, 1, Some((2, 2))));
+        assert_eq!(parse("Dark S01E01-E03 720p"), episodes(1, 1, Some((1, 3))));
+        assert_eq!(parse("Dark.S01E01E02.720p"), episodes(1, 1, Some((1, 2))));
+        assert_eq!(parse("Dark.S01E01-03.720p"), episodes(1, 1, Some((1, 3))));
+        assert_eq!(parse("Dark 1x02 HDTV"), episodes(1, 1, Some((2, 2))));
+        assert_eq!(parse("Dark.S02.COMPLETE.720p"), episodes(2, 2, None));
+        assert_eq!(parse("Dark Season 3 Complete"), episodes(3, 3, None));
+        assert_eq!(parse("Dark S01-S03 1080p"), episodes(1, 3, None));
+        assert_eq!(parse("Dark_S01E02_720p"), episodes(1, 1, Some((2, 2))));
+        assert_eq!(parse("Dark.2017.1080p.x264"), None);
+    }
+
+    #[test]
+    fn should_check_episode() {
+        let pack = parse("Dark.S01.COMPLETE").unwrap();
+        assert!(pack.contains(1, Some(2)));
+        assert!(!pack.contains(2, Some(2)));
+        let range = parse("Dark.S01E01-E03").unwrap();
+        assert!(range.contains(1, Some(3)));
+        assert!(!range.contains(1, Some(20)));
+        assert!(range.contains(1, None));
+        let seasons = parse("Dark S01-S03").unwrap();
+        assert!(seasons.contains(2, Some(5)));
+    }
+
+    #[test]
+    fn should_read_search_params() {
+        assert_eq!(
+            Target::from_params(Some("1"), Some("2")),
+            Some(Target::Episode {
+                season: 1,
+                episode: 2
+            })
+        );
+        assert_eq!(
+            Target::from_params(Some("1"), None),
+            Some(Target::Season(1))
+        );
+        assert_eq!(
+            Target::from_params(Some("2023"), Some("10/12")),
+            Some(Target::Daily {
+                year: 2023,
+                month: 10,
+                day: 12
+            })
+        );
+        assert_eq!(
+            Target::from_params(None, Some("105")),
+            Some(Target::Absolute(105))
+        );
+        assert_eq!(Target::from_params(Some("foo"), Some("2")), None);
+    }
+
+    #[test]
+    fn should_match_daily_and_absolute() {
+        let daily = Target::from_params(Some("2023"), Some("10/12")).unwrap();
+        assert!(daily.matches("The.Daily.Show.2023.10.12.720p.WEB", None));
+        assert!(daily.matches("The Daily Show 2023 10 12 1080p", None));
+        assert!(!daily.matches("The.Daily.Show.2023.10.13.720p.WEB", None));
+        assert_eq!(
+            daily.notations(),
+            vec![" 2023.10.12".to_string(), " 2023 10 12".to_string()]
+        );
+
+        let absolute = Target::Absolute(105).matcher();
+        assert!(absolute.matches("[SubsPlease] One Piece - 105 (1080p) [ABCD].mkv", None));
+        assert!(absolute.matches("One.Piece.E105.1080p", None));
+        assert!(!absolute.matches("[SubsPlease] One Piece - 1050 (1080p)", None));
+        assert!(!absolute.matches("One Piece 1080p", None));
+    }
+}
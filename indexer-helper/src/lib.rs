@@ -1,2 +1,10 @@
+#[cfg(feature = "episode")]
+pub mod episode;
+#[cfg(feature = "magnet")]
+pub mod magnet;
 #[cfg(feature = "numeric")]
 pub mod numeric;
+#[cfg(feature = "release")]
+pub mod release;
+#[cfg(feature = "text")]
+pub mod text;
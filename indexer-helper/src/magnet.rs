@@ -0,0 +1,74 @@
+use url::Url;
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+fn base32_to_hex(input: &str) -> Option<String> {
+    let mut bits: u64 = 0;
+    let mut count = 0;
+    let mut output = String::with_capacity(40);
+    for c in input.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|item| *item == c.to_ascii_uppercase())?;
+        bits = (bits << 5) | value as u64;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            output.push_str(&format!("{:02X}", (bits >> count) & 0xff));
+        }
+    }
+    Some(output)
+}
+
+fn normalize(hash: &str) -> Option<String> {
+    match hash.len() {
+        40 if hash.chars().all(|c| c.is_ascii_hexdigit()) => Some(hash.to_ascii_uppercase()),
+        32 => base32_to_hex(hash),
+        _ => None,
+    }
+}
+
+/// Extracts the info hash of a magnet link, as an uppercase hexadecimal string.
+pub fn info_hash(magnet: &str) -> Option<String> {
+    let url = Url::parse(magnet).ok()?;
+    if url.scheme() != "magnet" {
+        return None;
+    }
+    url.query_pairs()
+        .filter(|(key, _)| key == "xt")
+        .find_map(|(_, value)| value.strip_prefix("urn:btih:").and_then(normalize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::info_hash;
+
+    #[test]
+    fn should_extract_hex_info_hash() {
+        assert_eq!(
+            info_hash("magnet:?xt=urn:btih:19370e3fd96fb1ada86ed5892be5b791a2a32254&dn=foo")
+                .unwrap(),
+            "19370E3FD96FB1ADA86ED5892BE5B791A2A32254"
+        );
+        assert_eq!(
+            info_hash("magnet:?dn=foo&xt=urn%3Abtih%3A19370E3FD96FB1ADA86ED5892BE5B791A2A32254")
+                .unwrap(),
+            "19370E3FD96FB1ADA86ED5892BE5B791A2A32254"
+        );
+    }
+
+    #[test]
+    fn should_extract_base32_info_hash() {
+        assert_eq!(
+            info_hash("magnet:?xt=urn:btih:DE3Q4P6ZN6Y23KDO2WESXZNXSGRKGISU").unwrap(),
+            "19370E3FD96FB1ADA86ED5892BE5B791A2A32254"
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_magnets() {
+        assert!(info_hash("https://example.com").is_none());
+        assert!(info_hash("magnet:?dn=foo").is_none());
+        assert!(info_hash("magnet:?xt=urn:btih:1234").is_none());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::episode::{self, Episodes};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static YEAR: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:19|20)\d{2}\b").unwrap());
+static EXTENSION: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\.(?:mkv|mp4|avi|m4v|ts)$").unwrap());
+// SUNSCREEN in x264-SUNSCREEN[TGx]
+static GROUP: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"-([A-Za-z0-9]+)(?:\s*\[[^\]]*\])?\s*$").unwrap());
+
+/// Regex with the value it stands for.
+type Patterns = Lazy<Vec<(Regex, &'static str)>>;
+
+fn patterns(items: &[(&str, &'static str)]) -> Vec<(Regex, &'static str)> {
+    items
+        .iter()
+        .map(|(pattern, value)| {
+            let regex = Regex::new(&format!(r"(?i)\b(?:{pattern})\b")).unwrap();
+            (regex, *value)
+        })
+        .collect()
+}
+
+static RESOLUTIONS: Patterns = Lazy::new(|| {
+    patterns(&[
+        ("2160p|4k|uhd", "2160p"),
+        ("1080p|1080i", "1080p"),
+        ("720p", "720p"),
+        ("576p", "576p"),
+        ("480p", "480p"),
+    ])
+});
+static SOURCES: Patterns = Lazy::new(|| {
+    patterns(&[
+        ("remux", "Remux"),
+        ("web-?dl", "WEB-DL"),
+        ("web-?rip", "WEBRip"),
+        ("blu-?ray|bdrip|brrip|bdremux", "BluRay"),
+        ("hdtv", "HDTV"),
+        ("dvdrip|dvd", "DVD"),
+        ("hdcam|cam|telesync|ts", "CAM"),
+        ("web", "WEB"),
+    ])
+});
+static CODECS: Patterns = Lazy::new(|| {
+    patterns(&[
+        ("x265|h\\.?265|hevc", "x265"),
+        ("x264|h\\.?264|avc", "x264"),
+        ("xvid", "XviD"),
+        ("av1", "AV1"),
+    ])
+});
+static AUDIOS: Patterns = Lazy::new(|| {
+    patterns(&[
+        ("atmos", "Atmos"),
+        ("truehd", "TrueHD"),
+        ("dts-?hd(?:[ .-]?ma)?", "DTS-HD"),
+        ("dts", "DTS"),
+        // with the channels, like in DDP5.1 or AAC2.0
+        ("(?:ddp|dd\\+|e-?ac-?3)(?:\\d\\.\\d)?", "EAC3"),
+        ("(?:dd|ac-?3)(?:\\d\\.\\d)?", "AC3"),
+        ("aac(?:\\d\\.\\d)?", "AAC"),
+        ("flac", "FLAC"),
+        ("mp3", "MP3"),
+    ])
+});
+static HDRS: Patterns = Lazy::new(|| {
+    patterns(&[
+        ("dv|dovi|dolby[ .]?vision", "DV"),
+        ("hdr10\\+|hdr10plus", "HDR10+"),
+        ("hdr10", "HDR10"),
+        ("hdr", "HDR"),
+    ])
+});
+static LANGUAGES: Patterns = Lazy::new(|| {
+    patterns(&[
+        ("multi", "Multi"),
+        ("french|truefrench|vff|vfq|vostfr", "French"),
+        ("german", "German"),
+        ("spanish|castellano", "Spanish"),
+        ("italian|ita", "Italian"),
+        ("russian|rus", "Russian"),
+        ("japanese|jap", "Japanese"),
+        ("english|eng", "English"),
+    ])
+});
+
+/// First value whose pattern matches, with the position it matched at.
+fn find(patterns: &Patterns, name: &str) -> Option<(usize, &'static str)> {
+    patterns
+        .iter()
+        .filter_map(|(regex, value)| regex.find(name).map(|found| (found.start(), *value)))
+        .min_by_key(|(start, _)| *start)
+}
+
+/// What can be told about a release from its name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Release {
+    pub title: String,
+    pub year: Option<u16>,
+    pub episodes: Option<Episodes>,
+    pub resolution: Option<&'static str>,
+    pub source: Option<&'static str>,
+    pub codec: Option<&'static str>,
+    pub audio: Option<&'static str>,
+    pub hdr: Option<&'static str>,
+    pub language: Option<&'static str>,
+    pub group: Option<String>,
+}
+
+/// Parses a release name like `The.Movie.2023.1080p.WEB-DL.DDP5.1.x264-GROUP`.
+pub fn parse(name: &str) -> Release {
+    let name = EXTENSION.replace(name.trim(), "");
+    // underscores are word characters for the regexes
+    let cleaned = name.replace('_', " ");
+
+    // a year at the very beginning is part of the title, like one followed by another
+    let year = YEAR
+        .find_iter(&cleaned)
+        .filter(|found| found.start() > 0)
+        .last()
+        .map(|found| (found.start(), found.as_str().parse::<u16>().ok()));
+    let episodes = episode::find(&cleaned);
+    let resolution = find(&RESOLUTIONS, &cleaned);
+    let source = find(&SOURCES, &cleaned);
+    let codec = find(&CODECS, &cleaned);
+    let audio = find(&AUDIOS, &cleaned);
+    let hdr = find(&HDRS, &cleaned);
+    let language = find(&LANGUAGES, &cleaned);
+    let group = GROUP.captures(&cleaned).and_then(|found| found.get(1));
+
+    let title_end = [
+        year.map(|(start, _)| start),
+        episodes.map(|(start, _)| start),
+        resolution.map(|(start, _)| start),
+        source.map(|(start, _)| start),
+        codec.map(|(start, _)| start),
+        hdr.map(|(start, _)| start),
+        language.map(|(start, _)| start),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|start| *start > 0)
+    .min()
+    .unwrap_or(cleaned.len());
+    let title = cleaned[..title_end]
+        .replace('.', " ")
+        .trim_end_matches(|c: char| c.is_whitespace() || "-([".contains(c))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Release {
+        title,
+        year: year.and_then(|(_, year)| year),
+        episodes: episodes.map(|(_, episodes)| episodes),
+        resolution: resolution.map(|(_, value)| value),
+        source: source.map(|(_, value)| value),
+        codec: codec.map(|(_, value)| value),
+        audio: audio.map(|(_, value)| value),
+        hdr: hdr.map(|(_, value)| value),
+        language: language.map(|(_, value)| value),
+        group: group.map(|found| found.as_str().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::episode::Episodes;
+
+    #[test]
+    fn should_parse_movie() {
+        let release =
+            parse("John.Wick.Chapter.4.2023.2160p.WEB-DL.DDP5.1.Atmos.DV.HDR.H.265-SUNSCREEN[TGx]");
+        assert_eq!(release.title, "John Wick Chapter 4");
+        assert_eq!(release.year, Some(2023));
+        assert_eq!(release.episodes, None);
+        assert_eq!(release.resolution, Some("2160p"));
+        assert_eq!(release.source, Some("WEB-DL"));
+        assert_eq!(release.codec, Some("x265"));
+        assert_eq!(release.audio, Some("EAC3"));
+        assert_eq!(release.hdr, Some("DV"));
+        assert_eq!(release.language, None);
+        assert_eq!(release.group.as_deref(), Some("SUNSCREEN"));
+    }
+
+    #[test]
+    fn should_parse_episode() {
+        let release = parse("The Office (US) S02E03 FRENCH 720p HDTV x264-GROUP.mkv");
+        assert_eq!(release.title, "The Office (US)");
+        assert_eq!(
+            release.episodes,
+            Some(Episodes {
+                season: 2,
+                last_season: 2,
+                episodes: Some((3, 3)),
+            })
+        );
+        assert_eq!(release.resolution, Some("720p"));
+        assert_eq!(release.source, Some("HDTV"));
+        assert_eq!(release.codec, Some("x264"));
+        assert_eq!(release.language, Some("French"));
+        assert_eq!(release.group.as_deref(), Some("GROUP"));
+    }
+
+    #[test]
+    fn should_keep_year_starting_the_title() {
+        let release = parse("2012.2009.1080p.BluRay.x264");
+        assert_eq!(release.title, "2012");
+        assert_eq!(release.year, Some(2009));
+        assert_eq!(release.source, Some("BluRay"));
+        assert_eq!(release.group, None);
+    }
+
+    #[test]
+    fn should_take_the_last_year() {
+        let release = parse("Blade.Runner.2049.2017.1080p.BluRay.x264");
+        assert_eq!(release.title, "Blade Runner 2049");
+        assert_eq!(release.year, Some(2017));
+    }
+}
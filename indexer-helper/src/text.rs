@@ -0,0 +1,130 @@
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Lowercases the value, removes the accents and replaces punctuation, dots and
+/// underscores with single spaces.
+pub fn normalize(value: &str) -> String {
+    let cleaned = value
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>();
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Checks that the words of the phrase are next to each other in the value, both
+/// being normalized first.
+pub fn contains_words(value: &str, phrase: &str) -> bool {
+    let phrase = normalize(phrase);
+    !phrase.is_empty() && format!(" {} ", normalize(value)).contains(&format!(" {phrase} "))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    Word(String),
+    Year(u16),
+    /// `S01E02`, or `S01` for a whole season.
+    Episode {
+        season: u16,
+        episode: Option<u16>,
+    },
+}
+
+impl Token {
+    pub fn is_word(&self) -> bool {
+        matches!(self, Self::Word(_))
+    }
+}
+
+fn parse_year(word: &str) -> Option<u16> {
+    if word.len() != 4 {
+        return None;
+    }
+    word.parse::<u16>()
+        .ok()
+        .filter(|year| (1900..2100).contains(year))
+}
+
+fn parse_episode(word: &str) -> Option<Token> {
+    let rest = word.strip_prefix('s')?;
+    let (season, episode) = match rest.split_once('e') {
+        Some((season, episode)) => (season, Some(episode)),
+        None => (rest, None),
+    };
+    if season.is_empty() || season.len() > 3 || !season.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let episode = match episode {
+        Some(value) if !value.is_empty() && value.bytes().all(|c| c.is_ascii_digit()) => {
+            Some(value.parse().ok()?)
+        }
+        Some(_) => return None,
+        None => None,
+    };
+    Some(Token::Episode {
+        season: season.parse().ok()?,
+        episode,
+    })
+}
+
+/// Splits a query or a release name into normalized tokens.
+pub fn tokenize(value: &str) -> Vec<Token> {
+    normalize(value)
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            parse_year(word)
+                .map(Token::Year)
+                .or_else(|| parse_episode(word))
+                .unwrap_or_else(|| Token::Word(word.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contains_words, normalize, tokenize, Token};
+
+    #[test]
+    fn should_normalize() {
+        assert_eq!(normalize("Amélie.2001_FRENCH"), "amelie 2001 french");
+        assert_eq!(normalize("  Mr. Robot -  S01E02 "), "mr robot s01e02");
+    }
+
+    #[test]
+    fn should_contain_words() {
+        assert!(contains_words("Dune.2021.2160p.WEB-DL", "web dl"));
+        assert!(contains_words("Dune.2021.CAM", "cam"));
+        assert!(!contains_words("Dune.Camera.2021", "cam"));
+        assert!(!contains_words("Dune.2021", "-"));
+    }
+
+    #[test]
+    fn should_tokenize() {
+        assert_eq!(
+            tokenize("Dark.S01E02.2017.1080p"),
+            vec![
+                Token::Word("dark".into()),
+                Token::Episode {
+                    season: 1,
+                    episode: Some(2)
+                },
+                Token::Year(2017),
+                Token::Word("1080p".into()),
+            ]
+        );
+        assert_eq!(
+            tokenize("Dark Season S02 sample"),
+            vec![
+                Token::Word("dark".into()),
+                Token::Word("season".into()),
+                Token::Episode {
+                    season: 2,
+                    episode: None
+                },
+                Token::Word("sample".into()),
+            ]
+        );
+    }
+}
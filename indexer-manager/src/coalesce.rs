@@ -0,0 +1,115 @@
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+/// Single flight execution of futures.
+///
+/// When several callers ask for the same key at the same time, only the first one
+/// starts the future, the others wait for it and get a clone of its output.
+pub(crate) struct Coalescer<K, V> {
+    pending: Mutex<HashMap<K, Shared<BoxFuture<'static, V>>>>,
+}
+
+impl<K, V> Default for Coalescer<K, V> {
+    fn default() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K, V> std::fmt::Debug for Coalescer<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pending = self.pending.lock().map(|inner| inner.len()).unwrap_or(0);
+        f.debug_struct("Coalescer")
+            .field("pending", &pending)
+            .finish()
+    }
+}
+
+impl<K, V> Coalescer<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: Clone + Send + Sync + 'static,
+{
+    pub async fn run<F>(&self, key: K, future: F) -> V
+    where
+        F: Future<Output = V> + Send + 'static,
+    {
+        let shared = {
+            let mut pending = self.pending.lock().expect("coalescer lock poisoned");
+            if let Some(found) = pending.get(&key) {
+                tracing::debug!("joining pending request");
+                found.clone()
+            } else {
+                let shared = future.boxed().shared();
+                pending.insert(key.clone(), shared.clone());
+                shared
+            }
+        };
+
+        let result = shared.clone().await;
+
+        let mut pending = self.pending.lock().expect("coalescer lock poisoned");
+        // a new request could have been started for the same key in the meantime
+        if pending.get(&key).is_some_and(|found| found.ptr_eq(&shared)) {
+            pending.remove(&key);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Coalescer;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    async fn counted(counter: Arc<AtomicUsize>) -> usize {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        counter.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    #[tokio::test]
+    async fn should_share_concurrent_calls() {
+        let coalescer = Coalescer::<&'static str, usize>::default();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let (first, second, third) = tokio::join!(
+            coalescer.run("foo", counted(counter.clone())),
+            coalescer.run("foo", counted(counter.clone())),
+            coalescer.run("foo", counted(counter.clone())),
+        );
+
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert_eq!((first, second, third), (1, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn should_not_share_different_keys() {
+        let coalescer = Coalescer::<&'static str, usize>::default();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        tokio::join!(
+            coalescer.run("foo", counted(counter.clone())),
+            coalescer.run("bar", counted(counter.clone())),
+        );
+
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn should_not_share_sequential_calls() {
+        let coalescer = Coalescer::<&'static str, usize>::default();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        coalescer.run("foo", counted(counter.clone())).await;
+        coalescer.run("foo", counted(counter.clone())).await;
+
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,126 @@
+use manteau_indexer_helper::magnet::info_hash;
+use manteau_indexer_prelude::{EntrySource, IndexerEntry};
+use std::collections::HashMap;
+
+/// Completes the entry with what the duplicate knows better.
+fn merge(entry: &mut IndexerEntry, other: IndexerEntry) {
+    entry.seeders = entry.seeders.max(other.seeders);
+    entry.leechers = entry.leechers.max(other.leechers);
+    if entry.size.as_u64() == 0 {
+        entry.size = other.size;
+    }
+    // the first indexer to list it is the closest to the real upload date
+    entry.date = match (entry.date, other.date) {
+        (Some(date), Some(other)) => Some(date.min(other)),
+        (date, other) => date.or(other),
+    };
+    if other.name.len() > entry.name.len() {
+        entry.name = other.name;
+    }
+    if entry.magnet.is_empty() && !other.magnet.is_empty() {
+        entry.magnet = other.magnet;
+        entry.deferred = None;
+    }
+    if entry.uploader.is_none() {
+        entry.uploader = other.uploader;
+    }
+    if entry.files.is_empty() {
+        entry.files = other.files;
+    }
+    entry.duplicates.push(EntrySource {
+        origin: other.origin,
+        url: other.url,
+    });
+    entry.duplicates.extend(other.duplicates);
+}
+
+/// Merges the entries sharing the same info hash, keeping the position of the first one.
+pub(crate) fn deduplicate(entries: Vec<IndexerEntry>) -> Vec<IndexerEntry> {
+    let mut result: Vec<IndexerEntry> = Vec::with_capacity(entries.len());
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for mut entry in entries {
+        if entry.info_hash.is_none() {
+            entry.info_hash = info_hash(&entry.magnet);
+        }
+        let Some(hash) = entry.info_hash.clone() else {
+            result.push(entry);
+            continue;
+        };
+        match positions.get(&hash) {
+            Some(&index) => merge(&mut result[index], entry),
+            None => {
+                positions.insert(hash, result.len());
+                result.push(entry);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::deduplicate;
+    use crate::testing::{entry, EntryBuilder};
+    use chrono::Duration;
+    use manteau_indexer_prelude::bytesize::ByteSize;
+    use manteau_indexer_prelude::EntrySource;
+
+    const HASH: &str = "19370E3FD96FB1ADA86ED5892BE5B791A2A32254";
+
+    /// The same movie, found by the given indexer.
+    fn found_by(origin: &'static str) -> EntryBuilder {
+        entry("John.Wick.Chapter.4")
+            .origin(origin)
+            .url(format!("https://{origin}/john-wick"))
+            .size(ByteSize::gb(2))
+            .leechers(10)
+    }
+
+    #[test]
+    fn should_merge_entries_with_same_info_hash() {
+        let mut older = found_by("thepiratebay")
+            .magnet(format!("magnet:?xt=urn:btih:{HASH}"))
+            .seeders(50)
+            .age(Duration::days(2))
+            .build();
+        older.name = "John.Wick.Chapter.4.2023.1080p.WEB".into();
+        let entries = vec![
+            found_by("1337x")
+                .magnet(format!("magnet:?xt=urn:btih:{}", HASH.to_lowercase()))
+                .seeders(20)
+                .build(),
+            found_by("bitsearch")
+                .magnet("magnet:?xt=urn:btih:other")
+                .seeders(5)
+                .build(),
+            older.clone(),
+        ];
+
+        let result = deduplicate(entries);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].origin, "1337x");
+        assert_eq!(result[0].info_hash.as_deref(), Some(HASH));
+        assert_eq!(result[0].seeders, 50);
+        assert_eq!(result[0].date, older.date);
+        assert_eq!(result[0].name, "John.Wick.Chapter.4.2023.1080p.WEB");
+        assert_eq!(
+            result[0].duplicates,
+            vec![EntrySource {
+                origin: "thepiratebay",
+                url: "https://thepiratebay/john-wick".into(),
+            }]
+        );
+        assert_eq!(result[1].origin, "bitsearch");
+        assert!(result[1].duplicates.is_empty());
+    }
+
+    #[test]
+    fn should_keep_entries_without_info_hash() {
+        let entries = vec![
+            found_by("1337x").seeders(20).build(),
+            found_by("1337x").seeders(20).build(),
+        ];
+        assert_eq!(deduplicate(entries).len(), 2);
+    }
+}
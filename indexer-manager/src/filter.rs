@@ -0,0 +1,262 @@
+use chrono::{DateTime, Duration, Utc};
+use manteau_indexer_helper::text::contains_words;
+use manteau_indexer_prelude::bytesize::ByteSize;
+use manteau_indexer_prelude::{Category, IndexerEntry};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+fn deserialize_regexes<'de, D>(deserializer: D) -> Result<Vec<Regex>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let patterns: Vec<String> = serde::Deserialize::deserialize(deserializer)?;
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Conditions on the entries, the ones not matching being dropped.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct FilterConfig {
+    /// Words or phrases the names shouldn't contain.
+    #[serde(default)]
+    pub block: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_regexes")]
+    pub block_regex: Vec<Regex>,
+    /// When not empty, names should contain one of these words or phrases, or match one of
+    /// the regexes.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_regexes")]
+    pub allow_regex: Vec<Regex>,
+    #[serde(default)]
+    pub min_size: Option<ByteSize>,
+    #[serde(default)]
+    pub max_size: Option<ByteSize>,
+    #[serde(default)]
+    pub min_seeders: Option<usize>,
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+}
+
+impl FilterConfig {
+    fn allowed(&self, name: &str) -> bool {
+        (self.allow.is_empty() && self.allow_regex.is_empty())
+            || self.allow.iter().any(|word| contains_words(name, word))
+            || self.allow_regex.iter().any(|regex| regex.is_match(name))
+    }
+
+    /// Why the entry should be dropped, if it should.
+    fn reject(&self, entry: &IndexerEntry, now: DateTime<Utc>) -> Option<&'static str> {
+        if self
+            .block
+            .iter()
+            .any(|word| contains_words(&entry.name, word))
+            || self
+                .block_regex
+                .iter()
+                .any(|regex| regex.is_match(&entry.name))
+        {
+            return Some("blocked");
+        }
+        if !self.allowed(&entry.name) {
+            return Some("not allowed");
+        }
+        if self.min_size.is_some_and(|min| entry.size < min)
+            || self.max_size.is_some_and(|max| entry.size > max)
+        {
+            return Some("size");
+        }
+        if self.min_seeders.is_some_and(|min| entry.seeders < min) {
+            return Some("seeders");
+        }
+        // the entries without a date can't be told too old
+        if self
+            .max_age_days
+            .zip(entry.date)
+            .is_some_and(|(days, date)| now - date > Duration::days(days.into()))
+        {
+            return Some("age");
+        }
+        None
+    }
+
+    /// Removes the rejected entries, logging how many were removed and why.
+    pub(crate) fn apply(&self, scope: &str, entries: &mut Vec<IndexerEntry>) {
+        let now = Utc::now();
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        entries.retain(|entry| match self.reject(entry, now) {
+            Some(reason) => {
+                *counts.entry(reason).or_default() += 1;
+                false
+            }
+            None => true,
+        });
+        if !counts.is_empty() {
+            tracing::debug!("{scope} filters removed {counts:?}");
+        }
+    }
+}
+
+/// Filters applied to all the merged entries, and the ones for a given category.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct FiltersConfig {
+    #[serde(flatten)]
+    pub global: FilterConfig,
+    /// Keyed by category, like `2000` for movies.
+    #[serde(default)]
+    pub categories: BTreeMap<String, FilterConfig>,
+}
+
+impl FiltersConfig {
+    pub(crate) fn build(self) -> Filters {
+        let categories = self
+            .categories
+            .into_iter()
+            .filter_map(|(key, config)| match Category::from_str(&key) {
+                Ok(category) => Some((category.kind(), config)),
+                Err(error) => {
+                    tracing::warn!("ignoring filters: {error}");
+                    None
+                }
+            })
+            .collect();
+        Filters {
+            global: self.global,
+            categories,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Filters {
+    global: FilterConfig,
+    categories: HashMap<u32, FilterConfig>,
+}
+
+impl Filters {
+    pub fn apply(&self, category: Option<Category>, entries: &mut Vec<IndexerEntry>) {
+        self.global.apply("global", entries);
+        let found = category.and_then(|category| {
+            let filter = self.categories.get(&category.kind())?;
+            Some((category, filter))
+        });
+        if let Some((category, filter)) = found {
+            filter.apply(category.kind_str(), entries);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FiltersConfig;
+    use crate::testing::entry;
+    use chrono::Duration;
+    use manteau_indexer_prelude::bytesize::ByteSize;
+    use manteau_indexer_prelude::{Category, IndexerEntry};
+
+    fn names(entries: &[IndexerEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn should_filter_entries() {
+        let config: FiltersConfig = toml::from_str(
+            r#"
+block = ["cam", "hd ts"]
+block_regex = ["(?i)\\.exe$"]
+min_seeders = 1
+max_age_days = 365
+
+[categories.2000]
+allow = ["1080p", "2160p"]
+min_size = "1 GB"
+max_size = "50 GB"
+"#,
+        )
+        .unwrap();
+        let filters = config.build();
+        let mut entries = vec![
+            entry("Dune.2021.2160p.WEB")
+                .size(ByteSize::gb(20))
+                .seeders(10)
+                .age(Duration::days(2))
+                .build(),
+            entry("Dune.2021.CAM")
+                .size(ByteSize::gb(2))
+                .seeders(10)
+                .age(Duration::days(2))
+                .build(),
+            entry("Dune.2021.HD.TS.1080p")
+                .size(ByteSize::gb(2))
+                .seeders(10)
+                .age(Duration::days(2))
+                .build(),
+            entry("Dune.2021.1080p.exe")
+                .size(ByteSize::gb(2))
+                .seeders(10)
+                .age(Duration::days(2))
+                .build(),
+            entry("Dune.2021.1080p.WEB")
+                .size(ByteSize::gb(2))
+                .seeders(0)
+                .age(Duration::days(2))
+                .build(),
+            entry("Dune.2021.1080p.WEB")
+                .size(ByteSize::gb(2))
+                .seeders(10)
+                .age(Duration::days(400))
+                .build(),
+            entry("Dune.2021.720p.WEB")
+                .size(ByteSize::gb(2))
+                .seeders(10)
+                .age(Duration::days(2))
+                .build(),
+            entry("Dune.2021.1080p.WEB")
+                .size(ByteSize::mb(200))
+                .seeders(10)
+                .age(Duration::days(2))
+                .build(),
+            entry("Dune.2021.1080p.Camera")
+                .size(ByteSize::gb(2))
+                .seeders(10)
+                .age(Duration::days(2))
+                .build(),
+            // without a date, not told too old
+            entry("Dune.2021.2160p.BluRay")
+                .size(ByteSize::gb(20))
+                .seeders(10)
+                .build(),
+        ];
+
+        let mut tv = entries.clone();
+        filters.apply(Some(Category::Tv), &mut tv);
+        assert_eq!(
+            names(&tv),
+            vec![
+                "Dune.2021.2160p.WEB",
+                "Dune.2021.720p.WEB",
+                "Dune.2021.1080p.WEB",
+                "Dune.2021.1080p.Camera",
+                "Dune.2021.2160p.BluRay"
+            ]
+        );
+
+        filters.apply(Some(Category::Movie), &mut entries);
+        assert_eq!(
+            names(&entries),
+            vec![
+                "Dune.2021.2160p.WEB",
+                "Dune.2021.1080p.Camera",
+                "Dune.2021.2160p.BluRay"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_regex() {
+        assert!(toml::from_str::<FiltersConfig>(r#"block_regex = ["("]"#).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::coalesce::Coalescer;
+use crate::filter::FilterConfig;
+use crate::status::{IndexerStatus, StatusTracker};
+use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode, SearchParam};
+use manteau_indexer_prelude::ratelimit::RateLimiter;
+use manteau_indexer_prelude::{Category, Indexer, IndexerError, IndexerResult, SearchRequest};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct WarmFeed {
+    expires_at: Instant,
+    result: IndexerResult,
+}
+
+/// Wraps an indexer so that identical concurrent requests only hit the upstream once.
+#[derive(Debug)]
+pub(crate) struct IndexerHandle {
+    pub name: String,
+    pub priority: f64,
+    pub filter: Option<FilterConfig>,
+    indexer: Arc<dyn Indexer + Send + Sync + 'static>,
+    status: Arc<StatusTracker>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    searches: Coalescer<SearchRequest, IndexerResult>,
+    // feeds are keyed by category kind, audio and music lead to the same pages
+    feeds: Coalescer<u32, IndexerResult>,
+    resolves: Coalescer<String, Result<String, IndexerError>>,
+    // feeds kept warm by the scheduler
+    warm: Mutex<HashMap<u32, WarmFeed>>,
+}
+
+impl IndexerHandle {
+    pub fn new(name: String, indexer: Box<dyn Indexer + Send + Sync + 'static>) -> Self {
+        Self {
+            status: Arc::new(StatusTracker::new(name.clone())),
+            name,
+            priority: 1.0,
+            filter: None,
+            indexer: Arc::from(indexer),
+            rate_limiter: None,
+            searches: Coalescer::default(),
+            feeds: Coalescer::default(),
+            resolves: Coalescer::default(),
+            warm: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limiter used by the indexer client, only kept to report its usage.
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: f64) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Option<FilterConfig>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.indexer.capabilities()
+    }
+
+    /// Whether the indexer supports the parameters of the request as they are.
+    pub fn supports(&self, request: &SearchRequest) -> bool {
+        self.capabilities()
+            .supports(request.mode(), &request.params())
+    }
+
+    /// Whether the indexer supports the parameters of the request, or has keywords to search.
+    pub fn can_search(&self, request: &SearchRequest) -> bool {
+        self.supports(request)
+            || (self
+                .capabilities()
+                .supports(SearchMode::Search, &[SearchParam::Query])
+                && !request.keywords().is_empty())
+    }
+
+    pub fn feed_interval(&self) -> Duration {
+        self.indexer.feed_interval()
+    }
+
+    pub fn status(&self) -> IndexerStatus {
+        IndexerStatus {
+            rate_limit: self.rate_limiter.as_ref().map(|limiter| limiter.usage()),
+            ..self.status.snapshot()
+        }
+    }
+
+    /// The indexers able to page are asked for the entries up to the end of the requested
+    /// page, the others for their first page, the merged results being paginated afterwards.
+    pub async fn search(&self, request: &SearchRequest) -> IndexerResult {
+        let indexer = self.indexer.clone();
+        let status = self.status.clone();
+        let request = match self.capabilities().paging {
+            true => request.up_to_page(),
+            false => request.without_page(),
+        };
+        self.searches
+            .run(request.clone(), async move {
+                let result = indexer.search(&request).await;
+                status.record(&result);
+                result
+            })
+            .await
+    }
+
+    pub async fn resolve(&self, token: &str) -> Result<String, IndexerError> {
+        let indexer = self.indexer.clone();
+        let token = token.to_string();
+        self.resolves
+            .run(token.clone(), async move { indexer.resolve(&token).await })
+            .await
+    }
+
+    async fn fetch_feed(&self, category: Category) -> IndexerResult {
+        let indexer = self.indexer.clone();
+        let status = self.status.clone();
+        self.feeds
+            .run(category.kind(), async move {
+                let result = indexer.feed(category).await;
+                status.record(&result);
+                result
+            })
+            .await
+    }
+
+    fn warm_feed(&self, category: Category) -> Option<IndexerResult> {
+        let warm = self.warm.lock().expect("warm feeds lock poisoned");
+        warm.get(&category.kind())
+            .filter(|found| found.expires_at > Instant::now())
+            .map(|found| found.result.clone())
+    }
+
+    pub async fn feed(&self, category: Category) -> IndexerResult {
+        if let Some(found) = self.warm_feed(category) {
+            tracing::debug!("{} serving warm feed for {category:?}", self.name);
+            return found;
+        }
+        self.fetch_feed(category).await
+    }
+
+    /// Fetches the feed and keeps it warm for the given duration, unless it failed.
+    pub async fn prefetch(&self, category: Category, valid_for: Duration) -> IndexerResult {
+        let result = self.fetch_feed(category).await;
+        if !result.is_failure() {
+            self.warm.lock().expect("warm feeds lock poisoned").insert(
+                category.kind(),
+                WarmFeed {
+                    expires_at: Instant::now() + valid_for,
+                    result: result.clone(),
+                },
+            );
+        }
+        result
+    }
+}
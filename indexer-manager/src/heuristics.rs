@@ -0,0 +1,285 @@
+use bytesize::ByteSize;
+use chrono::{DateTime, Duration, Utc};
+use manteau_indexer_prelude::IndexerEntry;
+use std::collections::BTreeMap;
+
+// without `com`, that many names end with the domain of the site they come from
+const EXECUTABLES: [&str; 9] = [
+    "exe", "scr", "bat", "cmd", "msi", "lnk", "vbs", "ps1", "jar",
+];
+const ARCHIVES: [&str; 4] = ["zip", "rar", "7z", "iso"];
+const VIDEOS: [&str; 7] = ["mkv", "mp4", "avi", "m4v", "ts", "wmv", "mov"];
+
+fn extension(name: &str) -> Option<String> {
+    let (_, extension) = name.rsplit_once('.')?;
+    Some(extension.trim().to_lowercase())
+}
+
+fn has_extension(name: &str, extensions: &[&str]) -> bool {
+    extension(name).is_some_and(|found| extensions.contains(&found.as_str()))
+}
+
+/// Smallest plausible size for a movie, or a season pack, in this resolution.
+fn min_size(resolution: &str) -> ByteSize {
+    match resolution {
+        "2160p" => ByteSize::gb(1),
+        "1080p" => ByteSize::mb(300),
+        "720p" => ByteSize::mb(150),
+        _ => ByteSize::mb(50),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuspiciousAction {
+    /// Leave them as they are.
+    Keep,
+    /// Tag them as suspicious for the clients.
+    #[default]
+    Mark,
+    Drop,
+}
+
+/// Detection of the fakes, like a `movie.2160p.exe` seeded by thousands the minute it's out.
+#[derive(Debug, serde::Deserialize)]
+pub struct HeuristicsConfig {
+    #[serde(default)]
+    pub action: SuspiciousAction,
+    /// Accounts known for uploading fakes, the case being ignored.
+    #[serde(default)]
+    pub bad_uploaders: Vec<String>,
+    /// In minutes, age under which an upload is considered new.
+    #[serde(default = "HeuristicsConfig::default_new_upload")]
+    pub new_upload: u32,
+    /// More seeders than that on a new upload are not believable.
+    #[serde(default = "HeuristicsConfig::default_max_new_seeders")]
+    pub max_new_seeders: usize,
+}
+
+impl Default for HeuristicsConfig {
+    fn default() -> Self {
+        Self {
+            action: SuspiciousAction::default(),
+            bad_uploaders: Vec::new(),
+            new_upload: Self::default_new_upload(),
+            max_new_seeders: Self::default_max_new_seeders(),
+        }
+    }
+}
+
+impl HeuristicsConfig {
+    fn default_new_upload() -> u32 {
+        60
+    }
+
+    fn default_max_new_seeders() -> usize {
+        1000
+    }
+
+    pub(crate) fn build(self) -> Heuristics {
+        Heuristics {
+            action: self.action,
+            bad_uploaders: self
+                .bad_uploaders
+                .iter()
+                .map(|name| name.to_lowercase())
+                .collect(),
+            new_upload: Duration::minutes(self.new_upload.into()),
+            max_new_seeders: self.max_new_seeders,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Heuristics {
+    action: SuspiciousAction,
+    bad_uploaders: Vec<String>,
+    new_upload: Duration,
+    max_new_seeders: usize,
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        HeuristicsConfig::default().build()
+    }
+}
+
+impl Heuristics {
+    /// Claims to be a video but is too small for its resolution.
+    fn implausible_size(entry: &IndexerEntry) -> bool {
+        let Some(resolution) = entry.release.resolution else {
+            return false;
+        };
+        let mut min = min_size(resolution);
+        // a single episode is way shorter than a movie
+        if entry
+            .release
+            .episodes
+            .is_some_and(|found| found.episodes.is_some_and(|(first, last)| first == last))
+        {
+            min = ByteSize::b(min.as_u64() / 5);
+        }
+        entry.size.as_u64() > 0 && entry.size < min
+    }
+
+    /// Claims to be a video but only comes with archives.
+    fn archive_only(entry: &IndexerEntry) -> bool {
+        entry.release.resolution.is_some()
+            && (has_extension(&entry.name, &ARCHIVES)
+                || (entry
+                    .files
+                    .iter()
+                    .any(|name| has_extension(name, &ARCHIVES))
+                    && !entry.files.iter().any(|name| has_extension(name, &VIDEOS))))
+    }
+
+    fn reasons(&self, entry: &IndexerEntry, now: DateTime<Utc>) -> Vec<&'static str> {
+        let mut reasons = Vec::new();
+        if has_extension(&entry.name, &EXECUTABLES)
+            || entry
+                .files
+                .iter()
+                .any(|name| has_extension(name, &EXECUTABLES))
+        {
+            reasons.push("executable");
+        }
+        if Self::archive_only(entry) {
+            reasons.push("archive");
+        }
+        if Self::implausible_size(entry) {
+            reasons.push("size");
+        }
+        if entry
+            .uploader
+            .as_ref()
+            .is_some_and(|name| self.bad_uploaders.contains(&name.to_lowercase()))
+        {
+            reasons.push("uploader");
+        }
+        // the entries without a date can't be told new
+        let new = entry.date.is_some_and(|date| now - date < self.new_upload);
+        if new && entry.seeders > self.max_new_seeders {
+            reasons.push("seeders");
+        }
+        reasons
+    }
+
+    /// Marks the suspicious entries with the reasons, or drops them.
+    pub fn apply(&self, entries: &mut Vec<IndexerEntry>) {
+        if self.action == SuspiciousAction::Keep {
+            return;
+        }
+        let now = Utc::now();
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for entry in entries.iter_mut() {
+            entry.suspicious = self.reasons(entry, now);
+            for reason in entry.suspicious.iter() {
+                *counts.entry(reason).or_default() += 1;
+            }
+        }
+        if counts.is_empty() {
+            return;
+        }
+        tracing::debug!("found suspicious entries: {counts:?}");
+        if self.action == SuspiciousAction::Drop {
+            entries.retain(|entry| entry.suspicious.is_empty());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeuristicsConfig, SuspiciousAction};
+    use crate::testing::{entry, EntryBuilder};
+    use chrono::Duration;
+    use manteau_indexer_prelude::bytesize::ByteSize;
+
+    /// Uploaded two days ago, well seeded.
+    fn seeded(name: &str, size: ByteSize) -> EntryBuilder {
+        entry(name)
+            .size(size)
+            .seeders(100)
+            .age(Duration::days(2))
+            .parsed()
+    }
+
+    #[test]
+    fn should_mark_suspicious_entries() {
+        let heuristics = HeuristicsConfig {
+            bad_uploaders: vec!["FakeUploader".into()],
+            ..Default::default()
+        }
+        .build();
+        let new = seeded("Dune.2021.1080p.WEB", ByteSize::gb(2))
+            .seeders(5000)
+            .age(Duration::minutes(5))
+            .build();
+        let uploaded = seeded("Dune.2021.1080p.WEB", ByteSize::gb(2))
+            .uploader("fakeuploader")
+            .build();
+        let mut entries = vec![
+            seeded("Dune.2021.2160p.WEB", ByteSize::gb(20))
+                .files(&["Dune.2021.2160p.WEB.mkv"])
+                .build(),
+            seeded("Dune.2021.2160p.exe", ByteSize::gb(2)).build(),
+            seeded("Dune.2021.2160p", ByteSize::gb(2))
+                .files(&["Dune.mkv", "Codec.EXE"])
+                .build(),
+            seeded("Dune.2021.2160p", ByteSize::gb(2))
+                .files(&["Dune.zip", "Readme.txt"])
+                .build(),
+            seeded("Dune.2021.2160p", ByteSize::gb(2))
+                .files(&["Dune.rar", "Dune.mkv"])
+                .build(),
+            seeded("Dune.2021.2160p.WEB", ByteSize::mb(40)).build(),
+            seeded("Dark.S01E02.720p.WEB", ByteSize::mb(40)).build(),
+            seeded("Dune 2021 1080p WEB - ettv.com", ByteSize::gb(2)).build(),
+            new,
+            uploaded,
+            // without a date, not told new
+            seeded("Dune.2021.1080p.WEB", ByteSize::gb(2))
+                .seeders(5000)
+                .undated()
+                .build(),
+        ];
+
+        heuristics.apply(&mut entries);
+
+        let reasons = entries
+            .iter()
+            .map(|entry| entry.suspicious.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![
+                vec![],
+                vec!["executable"],
+                vec!["executable"],
+                vec!["archive"],
+                vec![],
+                vec!["size"],
+                vec![],
+                vec![],
+                vec!["seeders"],
+                vec!["uploader"],
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn should_drop_suspicious_entries() {
+        let heuristics = HeuristicsConfig {
+            action: SuspiciousAction::Drop,
+            ..Default::default()
+        }
+        .build();
+        let mut entries = vec![
+            seeded("Dune.2021.2160p.WEB", ByteSize::gb(20)).build(),
+            seeded("Dune.2021.2160p.exe", ByteSize::gb(2)).build(),
+        ];
+        heuristics.apply(&mut entries);
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].suspicious.is_empty());
+    }
+}
@@ -1,57 +1,157 @@
-use manteau_indexer_prelude::{Category, Indexer, IndexerBuilder, IndexerResult};
-use std::collections::HashMap;
+use filter::Filters;
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
+use handle::IndexerHandle;
+use heuristics::Heuristics;
+use manteau_indexer_helper::release;
+use manteau_indexer_prelude::capabilities::Capabilities;
+use manteau_indexer_prelude::http::HttpClient;
+use manteau_indexer_prelude::ratelimit::RateLimitConfig;
+use manteau_indexer_prelude::{
+    Category, Indexer, IndexerEntry, IndexerError, IndexerResult, SearchRequest,
+};
+use ranking::Ranking;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
+mod coalesce;
+mod dedup;
+mod filter;
+mod handle;
+mod heuristics;
+mod query;
+mod ranking;
+mod registry;
+mod relevance;
+mod status;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub use filter::{FilterConfig, FiltersConfig};
+pub use heuristics::{HeuristicsConfig, SuspiciousAction};
+pub use manteau_indexer_helper::episode::Target;
+pub use query::{Comparison, Predicate, SearchQuery};
+pub use ranking::{RankingConfig, RankingStrategy};
+pub use registry::{BoxedIndexer, BuildError, IndexerRegistry};
+pub use status::IndexerStatus;
+
+/// Settings common to every indexer, next to the ones specific to its type.
 #[derive(Debug, serde::Deserialize)]
-#[serde(tag = "type")]
-pub enum IndexerConfig {
-    #[serde(rename = "1337x")]
-    I1337x(manteau_indexer_1337x::Indexer1337xConfig),
-    #[serde(rename = "bitsearch")]
-    Bitsearch(manteau_indexer_bitsearch::IndexerBitsearchConfig),
-    #[serde(rename = "thepiratebay")]
-    ThePirateBay(manteau_indexer_thepiratebay::IndexerThePirateBayConfig),
+pub struct IndexerSettings {
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Weight applied to the score of the entries when ranking them.
+    #[serde(default = "IndexerSettings::default_priority")]
+    pub priority: f64,
+    /// Applied to the entries of this indexer only.
+    #[serde(default)]
+    pub filters: Option<FilterConfig>,
+    /// Name of the type in the registry, like `1337x`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Everything else, read by the indexer type.
+    #[serde(flatten)]
+    pub options: serde_json::Map<String, serde_json::Value>,
 }
 
-impl IndexerBuilder for IndexerConfig {
-    fn build(self, name: String) -> Box<dyn Indexer + Send + Sync + 'static> {
-        tracing::info!("building indexer {name:?}");
-        match self {
-            Self::Bitsearch(inner) => inner.build(name),
-            Self::I1337x(inner) => inner.build(name),
-            Self::ThePirateBay(inner) => inner.build(name),
-        }
+impl IndexerSettings {
+    fn default_priority() -> f64 {
+        1.0
+    }
+
+    fn build(
+        self,
+        name: String,
+        registry: &IndexerRegistry,
+        client: &HttpClient,
+    ) -> Result<IndexerHandle, BuildError> {
+        let rate_limiter = self.rate_limit.map(|config| Arc::new(config.build()));
+        let client = match rate_limiter {
+            Some(ref limiter) => client.with_rate_limiter(limiter.clone()),
+            None => client.clone(),
+        };
+        let indexer = registry.build(
+            &self.kind,
+            name.clone(),
+            serde_json::Value::Object(self.options),
+            client,
+        )?;
+        Ok(IndexerHandle::new(name, indexer)
+            .with_rate_limiter(rate_limiter)
+            .with_priority(self.priority)
+            .with_filter(self.filters))
     }
 }
 
 #[derive(Debug, serde::Deserialize)]
-pub struct IndexerManagerConfig(HashMap<String, IndexerConfig>);
+pub struct IndexerManagerConfig(BTreeMap<String, IndexerSettings>);
 
 impl IndexerManagerConfig {
-    pub fn build(self) -> IndexerManager {
+    /// Builds the indexers with the types of this repository.
+    pub fn build(self, client: HttpClient) -> Result<IndexerManager, BuildError> {
+        self.build_with(&IndexerRegistry::default(), client)
+    }
+
+    /// The client is shared by all the indexers so that they share its limits.
+    pub fn build_with(
+        self,
+        registry: &IndexerRegistry,
+        client: HttpClient,
+    ) -> Result<IndexerManager, BuildError> {
         tracing::info!("building indexer manager");
-        IndexerManager {
-            indexers: self
-                .0
-                .into_iter()
-                .map(|(name, config)| config.build(name))
-                .collect(),
-        }
+        let indexers = self
+            .0
+            .into_iter()
+            .map(|(name, settings)| Ok(Arc::new(settings.build(name, registry, &client)?)))
+            .collect::<Result<Vec<_>, BuildError>>()?;
+        Ok(IndexerManager {
+            indexers,
+            ranking: Ranking::default(),
+            filters: Filters::default(),
+            heuristics: Heuristics::default(),
+        })
     }
 }
 
+/// Entries found by a single indexer, sent as soon as it answered.
 #[derive(Debug)]
+pub struct SearchBatch {
+    pub indexer: String,
+    pub result: IndexerResult,
+}
+
+#[derive(Clone, Debug)]
 pub struct IndexerManager {
-    indexers: Vec<Box<dyn Indexer + Send + Sync + 'static>>,
+    indexers: Vec<Arc<IndexerHandle>>,
+    ranking: Ranking,
+    filters: Filters,
+    heuristics: Heuristics,
 }
 
 impl Default for IndexerManager {
+    /// Every known indexer type, with its default configuration.
     fn default() -> Self {
+        let registry = IndexerRegistry::default();
+        let client = HttpClient::default();
+        let indexers = registry
+            .types()
+            .filter_map(|kind| {
+                let options = serde_json::Value::Object(Default::default());
+                match registry.build(kind, kind.to_string(), options, client.clone()) {
+                    Ok(indexer) => Some(Arc::new(IndexerHandle::new(kind.to_string(), indexer))),
+                    Err(error) => {
+                        tracing::warn!("skipping default indexer: {error}");
+                        None
+                    }
+                }
+            })
+            .collect();
         Self {
-            indexers: vec![
-                Box::<manteau_indexer_1337x::Indexer1337x>::default(),
-                Box::<manteau_indexer_bitsearch::IndexerBitsearch>::default(),
-                Box::<manteau_indexer_thepiratebay::IndexerThePirateBay>::default(),
-            ],
+            indexers,
+            ranking: Ranking::default(),
+            filters: Filters::default(),
+            heuristics: Heuristics::default(),
         }
     }
 }
@@ -59,23 +159,821 @@ impl Default for IndexerManager {
 impl IndexerManager {
     pub fn with_indexer<I: Indexer + Send + Sync + 'static>(indexer: I) -> Self {
         Self {
-            indexers: vec![Box::new(indexer)],
+            indexers: vec![Arc::new(IndexerHandle::new(
+                "default".into(),
+                Box::new(indexer),
+            ))],
+            ranking: Ranking::default(),
+            filters: Filters::default(),
+            heuristics: Heuristics::default(),
         }
     }
 
-    pub async fn search(&self, query: &str) -> IndexerResult {
-        let items =
-            futures::future::join_all(self.indexers.iter().map(|idx| idx.search(query))).await;
-        items
-            .into_iter()
-            .fold(IndexerResult::default(), |res, item| res.merge(item))
+    pub fn with_ranking(mut self, ranking: RankingConfig) -> Self {
+        self.ranking = ranking.build();
+        self
+    }
+
+    pub fn with_filters(mut self, filters: FiltersConfig) -> Self {
+        self.filters = filters.build();
+        self
+    }
+
+    pub fn with_heuristics(mut self, heuristics: HeuristicsConfig) -> Self {
+        self.heuristics = heuristics.build();
+        self
+    }
+
+    pub fn indexer_names(&self) -> impl Iterator<Item = &str> {
+        self.indexers.iter().map(|idx| idx.name.as_str())
+    }
+
+    fn find(&self, name: &str) -> Option<&IndexerHandle> {
+        self.indexers
+            .iter()
+            .find(|idx| idx.name == name)
+            .map(Arc::as_ref)
+    }
+
+    /// Same manager restricted to the given indexer, sharing its state.
+    pub fn only(&self, name: &str) -> Option<Self> {
+        let found = self.indexers.iter().find(|idx| idx.name == name)?;
+        Some(Self {
+            indexers: vec![found.clone()],
+            ..self.clone()
+        })
+    }
+
+    /// What the indexers can search, all together.
+    pub fn capabilities(&self) -> Capabilities {
+        self.indexers
+            .iter()
+            .fold(Capabilities::default(), |caps, idx| {
+                caps.union(&idx.capabilities())
+            })
+    }
+
+    /// Minimum delay between two feed refreshes tolerated by the given indexer.
+    pub fn feed_interval(&self, name: &str) -> Option<Duration> {
+        self.find(name).map(|idx| idx.feed_interval())
+    }
+
+    pub fn status(&self) -> Vec<IndexerStatus> {
+        self.indexers.iter().map(|idx| idx.status()).collect()
+    }
+
+    /// Merges the results of the indexers, the same release found twice only being kept once,
+    /// and ranks the ones going through the filters.
+    fn combine(
+        &self,
+        query: Option<&str>,
+        category: Option<Category>,
+        items: Vec<IndexerResult>,
+    ) -> IndexerResult {
+        let mut priorities = HashMap::new();
+        let mut result = IndexerResult::default();
+        for (handle, mut item) in self.indexers.iter().zip(items) {
+            if let Some(ref filter) = handle.filter {
+                filter.apply(&handle.name, &mut item.entries);
+            }
+            for entry in item.entries.iter() {
+                priorities.insert((entry.origin, entry.url.clone()), handle.priority);
+            }
+            result = result.merge(item);
+        }
+        result.entries = dedup::deduplicate(result.entries);
+        self.refine(query, category, &mut result.entries, |entry| {
+            std::iter::once((entry.origin, &entry.url))
+                .chain(entry.duplicates.iter().map(|dup| (dup.origin, &dup.url)))
+                .filter_map(|(origin, url)| priorities.get(&(origin, url.clone())).copied())
+                .reduce(f64::max)
+                .unwrap_or(1.0)
+        });
+        result
+    }
+
+    /// Parses the releases, drops the entries the filters reject and ranks the others.
+    fn refine<P>(
+        &self,
+        query: Option<&str>,
+        category: Option<Category>,
+        entries: &mut Vec<IndexerEntry>,
+        priority: P,
+    ) where
+        P: Fn(&IndexerEntry) -> f64,
+    {
+        for entry in entries.iter_mut() {
+            entry.release = release::parse(&entry.name);
+        }
+        self.filters.apply(category, entries);
+        self.heuristics.apply(entries);
+        if let Some(query) = query {
+            self.ranking.retain_relevant(query, entries);
+        }
+        self.ranking.sort(query, entries, priority);
+    }
+
+    /// Filters the entries added to the results afterwards, like the ones of the history, and
+    /// looks for the fakes among them.
+    pub fn screen(&self, category: Option<Category>, entries: &mut Vec<IndexerEntry>) {
+        for entry in entries.iter_mut() {
+            entry.release = release::parse(&entry.name);
+        }
+        self.filters.apply(category, entries);
+        self.heuristics.apply(entries);
+    }
+
+    /// Entries of a single indexer, without the ones of another episode.
+    ///
+    /// The season and episode are asked as they are to the indexers supporting them, the
+    /// others being asked with the different ways of writing them.
+    async fn search_handle(
+        &self,
+        handle: &IndexerHandle,
+        request: &SearchRequest,
+    ) -> IndexerResult {
+        match request.target() {
+            Some(target) if !handle.supports(request) => {
+                search_notations(request, target, |variant| async move {
+                    let mut result = handle.search(&variant).await;
+                    for entry in result.entries.iter_mut() {
+                        entry.release = release::parse(&entry.name);
+                    }
+                    result
+                })
+                .await
+            }
+            _ => handle.search(request).await,
+        }
+    }
+
+    /// The indexers unable to do anything with the request, like an id search without
+    /// keywords, are skipped.
+    async fn search_all(&self, request: &SearchRequest) -> Vec<IndexerResult> {
+        futures::future::join_all(self.indexers.iter().map(|idx| async move {
+            if !idx.can_search(request) {
+                tracing::debug!("{} skipped, unable to search {request:?}", idx.name);
+                return IndexerResult::default();
+            }
+            self.search_handle(idx, request).await
+        }))
+        .await
+    }
+
+    /// Searches with every indexer, the episodes of tv searches being looked for with
+    /// the different ways of writing them by the indexers only supporting keywords.
+    pub async fn search(&self, request: &SearchRequest) -> IndexerResult {
+        let items = self.search_all(request).await;
+        self.combine(Some(&relevance_query(request)), request.category(), items)
+    }
+
+    /// Entries of a single indexer, going through the same steps as the merged ones.
+    async fn search_one(
+        &self,
+        handle: &IndexerHandle,
+        request: &SearchRequest,
+        query: &SearchQuery,
+    ) -> IndexerResult {
+        let mut result = self.search_handle(handle, request).await;
+        if let Some(ref filter) = handle.filter {
+            filter.apply(&handle.name, &mut result.entries);
+        }
+        result.entries = dedup::deduplicate(result.entries);
+        self.refine(
+            Some(&relevance_query(request)),
+            request.category(),
+            &mut result.entries,
+            |_| handle.priority,
+        );
+        query.retain(&mut result.entries);
+        result
+    }
+
+    /// Searches with every indexer, yielding the entries of each one as soon as it answers.
+    ///
+    /// The entries are checked against the query typed by the user, and the releases already
+    /// sent by a faster indexer are not sent again.
+    pub fn search_stream<'a>(
+        &'a self,
+        request: &'a SearchRequest,
+        query: &'a SearchQuery,
+    ) -> impl Stream<Item = SearchBatch> + Send + 'a {
+        let mut sent = HashSet::new();
+        self.indexers
+            .iter()
+            .filter(|handle| handle.can_search(request))
+            .map(|handle| async move { (handle, self.search_one(handle, request, query).await) })
+            .collect::<FuturesUnordered<_>>()
+            .map(move |(handle, mut result)| {
+                // only once filtered, not to hide a release the slower indexers could send
+                result.entries.retain(|entry| match entry.info_hash {
+                    Some(ref hash) => sent.insert(hash.clone()),
+                    None => true,
+                });
+                SearchBatch {
+                    indexer: handle.name.clone(),
+                    result,
+                }
+            })
     }
 
     pub async fn feed(&self, category: Category) -> IndexerResult {
         let items =
             futures::future::join_all(self.indexers.iter().map(|idx| idx.feed(category))).await;
-        items
-            .into_iter()
-            .fold(IndexerResult::default(), |res, item| res.merge(item))
+        self.combine(None, Some(category), items)
+    }
+
+    /// Resolves a deferred link with the indexer that created it.
+    pub async fn resolve(&self, name: &str, token: &str) -> Option<Result<String, IndexerError>> {
+        Some(self.find(name)?.resolve(token).await)
+    }
+
+    /// Refreshes the feed of a single indexer, that feed is then served without querying
+    /// the upstream until `valid_for` is elapsed.
+    pub async fn prefetch(
+        &self,
+        name: &str,
+        category: Category,
+        valid_for: Duration,
+    ) -> Option<IndexerResult> {
+        Some(self.find(name)?.prefetch(category, valid_for).await)
+    }
+}
+
+/// Text the entries should be relevant to, without the episode the season packs don't name.
+fn relevance_query(request: &SearchRequest) -> String {
+    match request.target() {
+        Some(_) => request.query.clone(),
+        None => request.keywords(),
+    }
+}
+
+/// Tries the ways of writing the episode one after the other, until the search finds releases
+/// about it, their names being parsed by then.
+async fn search_notations<F, Fut>(
+    request: &SearchRequest,
+    target: Target,
+    search: F,
+) -> IndexerResult
+where
+    F: Fn(SearchRequest) -> Fut,
+    Fut: Future<Output = IndexerResult>,
+{
+    let matcher = target.matcher();
+    let mut errors = Vec::new();
+    for notation in target.notations() {
+        let variant = SearchRequest {
+            query: format!("{}{notation}", request.query),
+            season: None,
+            episode: None,
+            ..request.clone()
+        };
+        let mut result = search(variant).await;
+        result
+            .entries
+            .retain(|entry| matcher.matches(&entry.name, entry.release.episodes));
+        errors.append(&mut result.errors);
+        if !result.entries.is_empty() {
+            result.errors = errors;
+            return result;
+        }
+        tracing::debug!("nothing found with {notation:?}");
+    }
+    IndexerResult::from(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexerHandle, IndexerManager, SearchQuery};
+    use futures::StreamExt;
+    use manteau_indexer_prelude::bytesize::ByteSize;
+    use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode, SearchParam};
+    use manteau_indexer_prelude::{
+        Category, Indexer, IndexerEntry, IndexerError, IndexerErrorReason, IndexerResult,
+        SearchRequest,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[derive(Debug, Default)]
+    struct CountingIndexer {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Indexer for CountingIndexer {
+        async fn search(&self, _request: &SearchRequest) -> IndexerResult {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            IndexerResult::default()
+        }
+
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            IndexerResult::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn should_coalesce_identical_searches() {
+        let indexer = CountingIndexer::default();
+        let calls = indexer.calls.clone();
+        let manager = IndexerManager::with_indexer(indexer);
+
+        let (foo, bar) = (SearchRequest::new("foo"), SearchRequest::new("bar"));
+        tokio::join!(
+            manager.search(&foo),
+            manager.search(&foo),
+            manager.search(&bar),
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn should_coalesce_feeds_with_same_kind() {
+        let indexer = CountingIndexer::default();
+        let calls = indexer.calls.clone();
+        let manager = IndexerManager::with_indexer(indexer);
+
+        tokio::join!(
+            manager.feed(Category::Audio),
+            manager.feed(Category::Music),
+            manager.feed(Category::Movie),
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn should_serve_prefetched_feeds() {
+        let indexer = CountingIndexer::default();
+        let calls = indexer.calls.clone();
+        let manager = IndexerManager::with_indexer(indexer);
+
+        manager
+            .prefetch("default", Category::Tv, Duration::from_secs(60))
+            .await
+            .unwrap();
+        manager.feed(Category::Tv).await;
+        manager.feed(Category::Movie).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(manager.status()[0].requests, 2);
+    }
+
+    #[test]
+    #[cfg(all(feature = "indexer-1337x", feature = "indexer-bitsearch"))]
+    fn should_report_rate_limit_usage() {
+        use super::IndexerManagerConfig;
+
+        let config: IndexerManagerConfig = toml::from_str(
+            r#"
+[limited]
+type = "1337x"
+base_url = "http://localhost"
+
+[limited.rate_limit]
+per_second = 2
+daily = 1000
+
+[unlimited]
+type = "bitsearch"
+"#,
+        )
+        .unwrap();
+        let manager = config.build(Default::default()).unwrap();
+        let mut status = manager.status();
+        status.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(status[0].name, "limited");
+        let usage = status[0].rate_limit.clone().unwrap();
+        assert_eq!(usage.daily_budget, Some(1000));
+        assert_eq!(usage.daily_used, 0);
+        assert_eq!(status[1].name, "unlimited");
+        assert!(status[1].rate_limit.is_none());
+    }
+
+    #[derive(Debug, Default)]
+    struct EpisodeIndexer {
+        queries: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Indexer for EpisodeIndexer {
+        async fn search(&self, request: &SearchRequest) -> IndexerResult {
+            let query = request.keywords();
+            self.queries.lock().unwrap().push(query.clone());
+            let names: &[&str] = match query.as_str() {
+                "Dark S01E02" => &["Dark.S01E20.1080p", "Dark.S02.1080p"],
+                "Dark 1x02" => &[],
+                "Dark Season 1" => &["Dark.S01.COMPLETE.1080p", "Dark.S01E01-E03.720p"],
+                _ => &[],
+            };
+            IndexerResult::from(
+                names
+                    .iter()
+                    .map(|name| IndexerEntry {
+                        size: ByteSize::gb(1),
+                        seeders: 1,
+                        leechers: 1,
+                        ..IndexerEntry::new("fake", *name, format!("https://example.com/{name}"))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            IndexerResult::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn should_search_episode_variants() {
+        let indexer = EpisodeIndexer::default();
+        let queries = indexer.queries.clone();
+        let manager = IndexerManager::with_indexer(indexer);
+
+        let request = SearchRequest {
+            query: "Dark".into(),
+            season: Some(1),
+            episode: Some("2".into()),
+            ..Default::default()
+        };
+        let result = manager.search(&request.with_category(Category::Tv)).await;
+
+        let mut names = result
+            .entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["Dark.S01.COMPLETE.1080p", "Dark.S01E01-E03.720p"]
+        );
+        assert_eq!(
+            *queries.lock().unwrap(),
+            vec!["Dark S01E02", "Dark 1x02", "Dark Season 1"]
+        );
+    }
+
+    /// Supports the season and episode, recording the requests.
+    #[derive(Debug, Default)]
+    struct SeasonIndexer {
+        requests: Arc<std::sync::Mutex<Vec<SearchRequest>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Indexer for SeasonIndexer {
+        async fn search(&self, request: &SearchRequest) -> IndexerResult {
+            self.requests.lock().unwrap().push(request.clone());
+            IndexerResult::from(vec![IndexerEntry {
+                size: ByteSize::gb(1),
+                seeders: 1,
+                ..IndexerEntry::new("season", "Dark.S01E02.720p", "https://season/")
+            }])
+        }
+
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            IndexerResult::default()
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            use SearchParam::*;
+
+            Capabilities::default()
+                .with_keyword_searches()
+                .with_search(SearchMode::TvSearch, &[Query, Season, Episode])
+        }
+    }
+
+    #[tokio::test]
+    async fn should_ask_episodes_as_they_are_when_supported() {
+        let season = SeasonIndexer::default();
+        let requests = season.requests.clone();
+        let keywords = EpisodeIndexer::default();
+        let queries = keywords.queries.clone();
+        let manager = IndexerManager {
+            indexers: vec![
+                Arc::new(IndexerHandle::new("season".into(), Box::new(season))),
+                Arc::new(IndexerHandle::new("keywords".into(), Box::new(keywords))),
+            ],
+            ..IndexerManager::with_indexer(CountingIndexer::default())
+        };
+        let request = SearchRequest {
+            query: "Dark".into(),
+            season: Some(1),
+            episode: Some("2".into()),
+            ..Default::default()
+        }
+        .with_category(Category::Tv);
+
+        let result = manager.search(&request).await;
+
+        let mut names = result
+            .entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "Dark.S01.COMPLETE.1080p",
+                "Dark.S01E01-E03.720p",
+                "Dark.S01E02.720p"
+            ]
+        );
+        assert_eq!(*requests.lock().unwrap(), vec![request]);
+        assert_eq!(
+            *queries.lock().unwrap(),
+            vec!["Dark S01E02", "Dark 1x02", "Dark Season 1"]
+        );
+    }
+
+    /// Offset and limit of the searches.
+    type Pages = Arc<std::sync::Mutex<Vec<(usize, Option<usize>)>>>;
+
+    /// Records the pages asked, supporting them or not.
+    #[derive(Debug)]
+    struct PagingIndexer {
+        paging: bool,
+        pages: Pages,
+    }
+
+    #[async_trait::async_trait]
+    impl Indexer for PagingIndexer {
+        async fn search(&self, request: &SearchRequest) -> IndexerResult {
+            self.pages
+                .lock()
+                .unwrap()
+                .push((request.offset, request.limit));
+            IndexerResult::default()
+        }
+
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            IndexerResult::default()
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::default()
+                .with_keyword_searches()
+                .with_paging(self.paging)
+        }
+    }
+
+    #[tokio::test]
+    async fn should_ask_pages_to_paging_indexers_only() {
+        let pages = Pages::default();
+        let indexer = |name: &str, paging: bool| {
+            Arc::new(IndexerHandle::new(
+                name.into(),
+                Box::new(PagingIndexer {
+                    paging,
+                    pages: pages.clone(),
+                }),
+            ))
+        };
+        let manager = IndexerManager {
+            indexers: vec![indexer("paging", true), indexer("single", false)],
+            ..IndexerManager::with_indexer(CountingIndexer::default())
+        };
+        assert!(manager.capabilities().paging);
+
+        let request = SearchRequest {
+            offset: 100,
+            limit: Some(50),
+            ..SearchRequest::new("dune")
+        };
+        manager.search(&request).await;
+
+        let mut pages = pages.lock().unwrap().clone();
+        pages.sort();
+        assert_eq!(pages, vec![(0, None), (0, Some(150))]);
+    }
+
+    /// Searches by imdb id, with an entry named after the id.
+    #[derive(Debug)]
+    struct IdIndexer;
+
+    #[async_trait::async_trait]
+    impl Indexer for IdIndexer {
+        async fn search(&self, request: &SearchRequest) -> IndexerResult {
+            let id = request.imdb_id.clone().unwrap_or_default();
+            IndexerResult::from(vec![IndexerEntry {
+                size: ByteSize::gb(1),
+                seeders: 1,
+                ..IndexerEntry::new("ids", format!("The.Matrix.1999.{id}"), "https://ids/")
+            }])
+        }
+
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            IndexerResult::default()
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::default()
+                .with_search(
+                    SearchMode::MovieSearch,
+                    &[SearchParam::Query, SearchParam::ImdbId],
+                )
+                .with_category("Movies", Category::Movie)
+        }
+    }
+
+    #[tokio::test]
+    async fn should_skip_keyword_indexers_without_keywords() {
+        let indexer = CountingIndexer::default();
+        let calls = indexer.calls.clone();
+        let manager = IndexerManager {
+            indexers: vec![
+                Arc::new(IndexerHandle::new("keywords".into(), Box::new(indexer))),
+                Arc::new(IndexerHandle::new("ids".into(), Box::new(IdIndexer))),
+            ],
+            ..IndexerManager::with_indexer(CountingIndexer::default())
+        };
+        let request = SearchRequest {
+            imdb_id: Some("tt0133093".into()),
+            ..Default::default()
+        }
+        .with_category(Category::Movie);
+
+        let result = manager.search(&request).await;
+        assert_eq!(result.entries.len(), 1);
+        assert_eq!(result.entries[0].name, "The.Matrix.1999.tt0133093");
+        let batches = manager
+            .search_stream(&request, &SearchQuery::default())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].indexer, "ids");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // with keywords, the ids are left out for the others
+        manager
+            .search(&SearchRequest {
+                query: "matrix".into(),
+                ..request
+            })
+            .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn should_not_prefetch_unknown_indexer() {
+        let manager = IndexerManager::with_indexer(CountingIndexer::default());
+        assert!(manager
+            .prefetch("unknown", Category::Tv, Duration::from_secs(60))
+            .await
+            .is_none());
+    }
+
+    /// Answers after the delay, with the given magnets or an error when there's none.
+    #[derive(Debug)]
+    struct DelayedIndexer {
+        delay: Duration,
+        hashes: Vec<char>,
+    }
+
+    #[async_trait::async_trait]
+    impl Indexer for DelayedIndexer {
+        async fn search(&self, _request: &SearchRequest) -> IndexerResult {
+            tokio::time::sleep(self.delay).await;
+            if self.hashes.is_empty() {
+                return IndexerResult::from(IndexerError::new(
+                    "delayed",
+                    IndexerErrorReason::EntryNameNotFound,
+                ));
+            }
+            IndexerResult::from(
+                self.hashes
+                    .iter()
+                    .map(|letter| letter.to_string().repeat(40))
+                    .map(|hash| IndexerEntry {
+                        size: ByteSize::gb(1),
+                        // the slower, the more seeded
+                        seeders: self.delay.as_millis() as usize,
+                        leechers: 1,
+                        magnet: format!("magnet:?xt=urn:btih:{hash}"),
+                        ..IndexerEntry::new(
+                            "delayed",
+                            format!("Dune.2021.{hash}"),
+                            format!("https://example.com/{hash}"),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            IndexerResult::default()
+        }
+    }
+
+    fn delayed_manager() -> IndexerManager {
+        let indexer = |name: &str, delay: u64, hashes: Vec<char>| {
+            Arc::new(IndexerHandle::new(
+                name.into(),
+                Box::new(DelayedIndexer {
+                    delay: Duration::from_millis(delay),
+                    hashes,
+                }),
+            ))
+        };
+        IndexerManager {
+            indexers: vec![
+                indexer("slow", 100, vec!['b', 'c']),
+                indexer("failing", 50, vec![]),
+                indexer("fast", 0, vec!['a', 'b']),
+            ],
+            ..IndexerManager::with_indexer(CountingIndexer::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn should_stream_results_as_indexers_answer() {
+        let manager = delayed_manager();
+        let request = SearchRequest::new("dune");
+        let query = SearchQuery::parse("dune");
+        let batches = manager
+            .search_stream(&request, &query)
+            .collect::<Vec<_>>()
+            .await;
+
+        let summary = batches
+            .iter()
+            .map(|batch| {
+                (
+                    batch.indexer.as_str(),
+                    batch.result.entries.len(),
+                    batch.result.is_failure(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![("fast", 2, false), ("failing", 0, true), ("slow", 1, false)]
+        );
+        assert_eq!(batches[2].result.entries[0].info_hash, Some("C".repeat(40)));
+    }
+
+    #[tokio::test]
+    async fn should_stream_releases_filtered_out_of_faster_indexers() {
+        let manager = delayed_manager();
+        let query = SearchQuery::parse("dune seeders>10");
+        let request = SearchRequest::new(query.upstream());
+        let batches = manager
+            .search_stream(&request, &query)
+            .collect::<Vec<_>>()
+            .await;
+
+        let fast = batches
+            .iter()
+            .find(|batch| batch.indexer == "fast")
+            .unwrap();
+        assert!(fast.result.entries.is_empty());
+        let slow = batches
+            .iter()
+            .find(|batch| batch.indexer == "slow")
+            .unwrap();
+        assert_eq!(slow.result.entries.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_stream_episode_variants() {
+        let indexer = EpisodeIndexer::default();
+        let queries = indexer.queries.clone();
+        let manager = IndexerManager::with_indexer(indexer);
+
+        let request = SearchRequest {
+            query: "Dark".into(),
+            season: Some(1),
+            episode: Some("2".into()),
+            ..Default::default()
+        }
+        .with_category(Category::Tv);
+        let batches = manager
+            .search_stream(&request, &SearchQuery::parse("Dark"))
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut names = batches[0]
+            .result
+            .entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["Dark.S01.COMPLETE.1080p", "Dark.S01E01-E03.720p"]
+        );
+        assert_eq!(
+            *queries.lock().unwrap(),
+            vec!["Dark S01E02", "Dark 1x02", "Dark Season 1"]
+        );
     }
 }
@@ -0,0 +1,208 @@
+use manteau_indexer_helper::text::contains_words;
+use manteau_indexer_prelude::bytesize::ByteSize;
+use manteau_indexer_prelude::IndexerEntry;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    Less,
+}
+
+impl Comparison {
+    fn check<T: PartialOrd>(&self, value: T, bound: T) -> bool {
+        match self {
+            Self::Greater => value > bound,
+            Self::Less => value < bound,
+        }
+    }
+}
+
+/// Condition on the numbers of a release, like `size>2GB` or `seeders>10`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Predicate {
+    Size(Comparison, ByteSize),
+    Seeders(Comparison, usize),
+}
+
+impl Predicate {
+    fn parse(word: &str) -> Option<Self> {
+        let index = word.find(['>', '<'])?;
+        let comparison = match &word[index..index + 1] {
+            ">" => Comparison::Greater,
+            _ => Comparison::Less,
+        };
+        let value = &word[index + 1..];
+        match word[..index].to_lowercase().as_str() {
+            "size" => Some(Self::Size(comparison, ByteSize::from_str(value).ok()?)),
+            "seeders" | "seeds" => Some(Self::Seeders(comparison, value.parse().ok()?)),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, entry: &IndexerEntry) -> bool {
+        match self {
+            Self::Size(comparison, bound) => comparison.check(entry.size, *bound),
+            Self::Seeders(comparison, bound) => comparison.check(entry.seeders, *bound),
+        }
+    }
+}
+
+/// Search typed by a user, like `dune 2021 -cam "2160p" size>10GB`.
+///
+/// The upstream indexers only get the keywords and the phrases, the rest is checked
+/// on the merged results.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub keywords: Vec<String>,
+    /// Words that should be found next to each other, in this order.
+    pub phrases: Vec<String>,
+    /// Words or phrases the release name shouldn't contain.
+    pub excluded: Vec<String>,
+    pub predicates: Vec<Predicate>,
+}
+
+/// Splits on whitespaces, keeping quoted phrases together, with whether they were quoted.
+fn split(input: &str) -> Vec<(bool, String, bool)> {
+    let mut result = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let negated = c == '-' && chars.peek().is_some_and(|next| !next.is_whitespace());
+        let first = if negated { chars.next() } else { Some(c) };
+        let mut value = String::new();
+        let quoted = first == Some('"');
+        if quoted {
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+                value.push(c);
+            }
+        } else {
+            value.extend(first);
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+        result.push((negated, value, quoted));
+    }
+    result
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Self {
+        let mut query = Self::default();
+        for (negated, value, quoted) in split(input) {
+            if value.trim().is_empty() {
+                continue;
+            }
+            if negated {
+                query.excluded.push(value);
+            } else if quoted {
+                query.phrases.push(value);
+            } else if let Some(predicate) = Predicate::parse(&value) {
+                query.predicates.push(predicate);
+            } else {
+                query.keywords.push(value);
+            }
+        }
+        query
+    }
+
+    /// What to send to the upstream indexers.
+    pub fn upstream(&self) -> String {
+        self.keywords
+            .iter()
+            .chain(self.phrases.iter())
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn matches(&self, entry: &IndexerEntry) -> bool {
+        self.phrases
+            .iter()
+            .all(|phrase| contains_words(&entry.name, phrase))
+            && !self
+                .excluded
+                .iter()
+                .any(|word| contains_words(&entry.name, word))
+            && self.predicates.iter().all(|pred| pred.matches(entry))
+    }
+
+    /// Removes the entries not matching the query.
+    pub fn retain(&self, entries: &mut Vec<IndexerEntry>) {
+        let before = entries.len();
+        entries.retain(|entry| self.matches(entry));
+        if entries.len() < before {
+            tracing::debug!("query filtered out {} entries", before - entries.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Comparison, Predicate, SearchQuery};
+    use crate::testing::entry;
+    use manteau_indexer_prelude::bytesize::ByteSize;
+
+    #[test]
+    fn should_parse_query() {
+        let query =
+            SearchQuery::parse(r#"dune 2021 -cam "2160p"  -"hd ts" size>10GB seeders<5 x-men"#);
+        assert_eq!(query.keywords, vec!["dune", "2021", "x-men"]);
+        assert_eq!(query.phrases, vec!["2160p"]);
+        assert_eq!(query.excluded, vec!["cam", "hd ts"]);
+        assert_eq!(
+            query.predicates,
+            vec![
+                Predicate::Size(Comparison::Greater, ByteSize::gb(10)),
+                Predicate::Seeders(Comparison::Less, 5),
+            ]
+        );
+        assert_eq!(query.upstream(), "dune 2021 x-men 2160p");
+    }
+
+    #[test]
+    fn should_keep_unknown_predicates_as_keywords() {
+        let query = SearchQuery::parse("size>big a<b - foo");
+        assert_eq!(query.keywords, vec!["size>big", "a<b", "-", "foo"]);
+        assert!(query.predicates.is_empty());
+    }
+
+    #[test]
+    fn should_filter_entries() {
+        let query = SearchQuery::parse(r#"dune -cam "2160p web" size>10GB"#);
+        let mut entries = vec![
+            entry("Dune.2021.2160p.WEB-DL.x265")
+                .size(ByteSize::gb(20))
+                .seeders(10)
+                .build(),
+            entry("Dune.2021.2160p.WEB.CAM")
+                .size(ByteSize::gb(20))
+                .seeders(10)
+                .build(),
+            entry("Dune.2021.2160p.WEB-DL.x265")
+                .size(ByteSize::gb(5))
+                .seeders(10)
+                .build(),
+            entry("Dune.2021.2160p.BluRay.WEB")
+                .size(ByteSize::gb(20))
+                .seeders(10)
+                .build(),
+            entry("Dune.Camera.2160p.WEB")
+                .size(ByteSize::gb(20))
+                .seeders(10)
+                .build(),
+        ];
+        query.retain(&mut entries);
+        let names = entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["Dune.2021.2160p.WEB-DL.x265", "Dune.Camera.2160p.WEB"]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use crate::relevance::score as relevance;
+use chrono::{DateTime, Utc};
+use manteau_indexer_prelude::IndexerEntry;
+use std::cmp::Ordering;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RankingStrategy {
+    #[default]
+    Seeders,
+    Date,
+    Size,
+    /// How well the name matches the query, the seeders when there is no query.
+    Relevance,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct RankingConfig {
+    #[serde(default)]
+    pub strategy: RankingStrategy,
+    /// Search results less relevant than this, between 0 and 1, are dropped.
+    #[serde(default)]
+    pub min_relevance: f64,
+}
+
+impl RankingConfig {
+    pub fn build(self) -> Ranking {
+        Ranking {
+            strategy: self.strategy,
+            min_relevance: self.min_relevance,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Ranking {
+    strategy: RankingStrategy,
+    min_relevance: f64,
+}
+
+impl Ranking {
+    /// Drops the entries not relevant enough to the query.
+    pub fn retain_relevant(&self, query: &str, entries: &mut Vec<IndexerEntry>) {
+        if self.min_relevance <= 0.0 {
+            return;
+        }
+        let before = entries.len();
+        entries.retain(|entry| relevance(query, &entry.name) >= self.min_relevance);
+        tracing::debug!(
+            "dropped {} entries not relevant to {query:?}",
+            before - entries.len()
+        );
+    }
+
+    fn score(&self, query: Option<&str>, now: DateTime<Utc>, entry: &IndexerEntry) -> f64 {
+        match (self.strategy, query) {
+            // the entries without a date come after the dated ones
+            (RankingStrategy::Date, _) => entry.date.map_or(0.0, |date| {
+                let age = (now - date).num_minutes().max(0) as f64;
+                1.0 / (1.0 + age)
+            }),
+            (RankingStrategy::Size, _) => entry.size.as_u64() as f64,
+            (RankingStrategy::Relevance, Some(query)) => {
+                // the seeders only break the ties between equally relevant entries
+                relevance(query, &entry.name) * 1_000_000.0 + (entry.seeders as f64).min(999_999.0)
+            }
+            (RankingStrategy::Seeders | RankingStrategy::Relevance, _) => entry.seeders as f64,
+        }
+    }
+
+    /// Sorts the entries by decreasing score, weighted by the priority of their indexer.
+    ///
+    /// Entries with the same score are ordered on their other fields so that the output
+    /// doesn't depend on the order the indexers answered in.
+    pub fn sort<P>(&self, query: Option<&str>, entries: &mut Vec<IndexerEntry>, priority: P)
+    where
+        P: Fn(&IndexerEntry) -> f64,
+    {
+        let now = Utc::now();
+        let mut scored = std::mem::take(entries)
+            .into_iter()
+            .map(|entry| (self.score(query, now, &entry) * priority(&entry), entry))
+            .collect::<Vec<_>>();
+        scored.sort_by(|(left_score, left), (right_score, right)| {
+            right_score
+                .partial_cmp(left_score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| right.seeders.cmp(&left.seeders))
+                .then_with(|| right.date.cmp(&left.date))
+                .then_with(|| left.name.cmp(&right.name))
+                .then_with(|| left.origin.cmp(right.origin))
+                .then_with(|| left.url.cmp(&right.url))
+        });
+        entries.extend(scored.into_iter().map(|(_, entry)| entry));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RankingConfig, RankingStrategy};
+    use crate::testing::entry;
+    use chrono::{Duration, Utc};
+    use manteau_indexer_prelude::bytesize::ByteSize;
+
+    fn names(strategy: RankingStrategy, query: Option<&str>) -> Vec<String> {
+        let mut entries = vec![
+            entry("foo bar")
+                .seeders(10)
+                .size(ByteSize::mb(300))
+                .age(Duration::hours(3))
+                .build(),
+            entry("bar")
+                .seeders(30)
+                .size(ByteSize::mb(100))
+                .age(Duration::hours(1))
+                .build(),
+            entry("foo baz")
+                .seeders(20)
+                .size(ByteSize::mb(200))
+                .age(Duration::hours(2))
+                .build(),
+        ];
+        RankingConfig {
+            strategy,
+            ..Default::default()
+        }
+        .build()
+        .sort(query, &mut entries, |_| 1.0);
+        entries.into_iter().map(|entry| entry.name).collect()
+    }
+
+    #[test]
+    fn should_sort_with_strategy() {
+        assert_eq!(
+            names(RankingStrategy::Seeders, None),
+            vec!["bar", "foo baz", "foo bar"]
+        );
+        assert_eq!(
+            names(RankingStrategy::Date, None),
+            vec!["bar", "foo baz", "foo bar"]
+        );
+        assert_eq!(
+            names(RankingStrategy::Size, None),
+            vec!["foo bar", "foo baz", "bar"]
+        );
+        assert_eq!(
+            names(RankingStrategy::Relevance, Some("Foo.Bar")),
+            vec!["foo bar", "bar", "foo baz"]
+        );
+    }
+
+    #[test]
+    fn should_drop_irrelevant_entries() {
+        let mut entries = vec![
+            entry("Dark.S01E01.1080p").build(),
+            entry("Dark.Phoenix.2019.1080p").build(),
+            entry("Darkwing.Duck.S01E01").build(),
+        ];
+        RankingConfig {
+            min_relevance: 0.8,
+            ..Default::default()
+        }
+        .build()
+        .retain_relevant("dark", &mut entries);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "Dark.S01E01.1080p");
+    }
+
+    #[test]
+    fn should_apply_priority() {
+        let mut entries = vec![
+            entry("foo").seeders(10).build(),
+            entry("bar").seeders(15).build(),
+        ];
+        RankingConfig::default()
+            .build()
+            .sort(None, &mut entries, |entry| {
+                if entry.name == "foo" {
+                    2.0
+                } else {
+                    1.0
+                }
+            });
+        assert_eq!(entries[0].name, "foo");
+    }
+
+    #[test]
+    fn should_break_ties_deterministically() {
+        let date = Some(Utc::now());
+        let mut first = vec![
+            entry("b").seeders(10).build(),
+            entry("a").seeders(10).build(),
+        ];
+        first.iter_mut().for_each(|entry| entry.date = date);
+        let mut second = first.iter().rev().cloned().collect::<Vec<_>>();
+        let ranking = RankingConfig::default().build();
+        ranking.sort(None, &mut first, |_| 1.0);
+        ranking.sort(None, &mut second, |_| 1.0);
+        assert_eq!(first[0].name, "a");
+        assert_eq!(second[0].name, "a");
+    }
+}
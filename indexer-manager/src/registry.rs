@@ -0,0 +1,303 @@
+use manteau_indexer_prelude::http::HttpClient;
+use manteau_indexer_prelude::{Indexer, IndexerBuilder};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+
+pub type BoxedIndexer = Box<dyn Indexer + Send + Sync + 'static>;
+
+type Factory = Box<
+    dyn Fn(String, serde_json::Value, HttpClient) -> Result<BoxedIndexer, serde_json::Error>
+        + Send
+        + Sync,
+>;
+
+#[derive(Debug)]
+pub enum BuildError {
+    UnknownType {
+        name: String,
+        kind: String,
+    },
+    /// The type exists but its cargo feature wasn't enabled.
+    NotCompiled {
+        name: String,
+        kind: String,
+        feature: &'static str,
+    },
+    InvalidConfig {
+        name: String,
+        cause: serde_json::Error,
+    },
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownType { name, kind } => {
+                write!(f, "indexer {name:?} has an unknown type {kind:?}")
+            }
+            Self::NotCompiled {
+                name,
+                kind,
+                feature,
+            } => write!(
+                f,
+                "indexer {name:?} has the type {kind:?} that wasn't compiled in, \
+                 the {feature:?} feature should be enabled"
+            ),
+            Self::InvalidConfig { name, cause } => {
+                write!(f, "indexer {name:?} has an invalid configuration: {cause}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Indexer types, by the name given in the `type` field of their configuration.
+///
+/// The default one knows the indexers of this repository, applications can add their own.
+pub struct IndexerRegistry {
+    factories: BTreeMap<String, Factory>,
+    // types of this repository left out at compile time, with their feature
+    missing: BTreeMap<String, &'static str>,
+}
+
+impl std::fmt::Debug for IndexerRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IndexerRegistry")
+            .field("types", &self.factories.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Default for IndexerRegistry {
+    fn default() -> Self {
+        let registry = Self::empty();
+        #[cfg(feature = "indexer-1337x")]
+        let registry = registry
+            .with_type::<manteau_indexer_1337x::Indexer1337xConfig>(manteau_indexer_1337x::NAME);
+        #[cfg(not(feature = "indexer-1337x"))]
+        let registry = registry.with_missing("1337x", "indexer-1337x");
+        #[cfg(feature = "indexer-bitsearch")]
+        let registry = registry.with_type::<manteau_indexer_bitsearch::IndexerBitsearchConfig>(
+            manteau_indexer_bitsearch::NAME,
+        );
+        #[cfg(not(feature = "indexer-bitsearch"))]
+        let registry = registry.with_missing("bitsearch", "indexer-bitsearch");
+        #[cfg(feature = "indexer-definition")]
+        let registry = registry.with_type::<manteau_indexer_definition::IndexerDefinitionConfig>(
+            manteau_indexer_definition::NAME,
+        );
+        #[cfg(not(feature = "indexer-definition"))]
+        let registry = registry.with_missing("definition", "indexer-definition");
+        #[cfg(feature = "indexer-feed")]
+        let registry = registry
+            .with_type::<manteau_indexer_feed::IndexerFeedConfig>(manteau_indexer_feed::NAME);
+        #[cfg(not(feature = "indexer-feed"))]
+        let registry = registry.with_missing("feed", "indexer-feed");
+        #[cfg(feature = "indexer-thepiratebay")]
+        let registry = registry
+            .with_type::<manteau_indexer_thepiratebay::IndexerThePirateBayConfig>(
+                manteau_indexer_thepiratebay::NAME,
+            );
+        #[cfg(not(feature = "indexer-thepiratebay"))]
+        let registry = registry.with_missing("thepiratebay", "indexer-thepiratebay");
+        #[cfg(feature = "indexer-torznab")]
+        let registry = registry.with_type::<manteau_indexer_torznab::IndexerTorznabConfig>(
+            manteau_indexer_torznab::NAME,
+        );
+        #[cfg(not(feature = "indexer-torznab"))]
+        let registry = registry.with_missing("torznab", "indexer-torznab");
+        registry
+    }
+}
+
+impl IndexerRegistry {
+    pub fn empty() -> Self {
+        Self {
+            factories: BTreeMap::new(),
+            missing: BTreeMap::new(),
+        }
+    }
+
+    #[cfg_attr(
+        all(
+            feature = "indexer-1337x",
+            feature = "indexer-bitsearch",
+            feature = "indexer-definition",
+            feature = "indexer-feed",
+            feature = "indexer-thepiratebay",
+            feature = "indexer-torznab",
+            not(test)
+        ),
+        allow(dead_code)
+    )]
+    fn with_missing(mut self, kind: &str, feature: &'static str) -> Self {
+        self.missing.insert(kind.to_string(), feature);
+        self
+    }
+
+    /// Registers the type, its configuration being read from the indexer settings.
+    pub fn with_type<C>(mut self, kind: &str) -> Self
+    where
+        C: IndexerBuilder + DeserializeOwned + 'static,
+    {
+        let factory: Factory = Box::new(|name, options, client| {
+            let config: C = serde_json::from_value(options)?;
+            Ok(config.build(name, client))
+        });
+        self.missing.remove(kind);
+        self.factories.insert(kind.to_string(), factory);
+        self
+    }
+
+    pub fn types(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    pub fn build(
+        &self,
+        kind: &str,
+        name: String,
+        options: serde_json::Value,
+        client: HttpClient,
+    ) -> Result<BoxedIndexer, BuildError> {
+        let Some(factory) = self.factories.get(kind) else {
+            let kind = kind.to_string();
+            return Err(match self.missing.get(&kind) {
+                Some(feature) => BuildError::NotCompiled {
+                    name,
+                    kind,
+                    feature,
+                },
+                None => BuildError::UnknownType { name, kind },
+            });
+        };
+        tracing::info!("building indexer {name:?} of type {kind:?}");
+        factory(name.clone(), options, client)
+            .map_err(|cause| BuildError::InvalidConfig { name, cause })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuildError, IndexerRegistry};
+    use crate::IndexerManagerConfig;
+    use manteau_indexer_prelude::http::HttpClient;
+    use manteau_indexer_prelude::{
+        Category, Indexer, IndexerBuilder, IndexerResult, SearchRequest,
+    };
+
+    #[derive(Debug, serde::Deserialize)]
+    struct PrivateConfig {
+        answer: usize,
+    }
+
+    #[derive(Debug)]
+    struct PrivateIndexer {
+        name: String,
+    }
+
+    impl IndexerBuilder for PrivateConfig {
+        fn build(
+            self,
+            name: String,
+            _client: HttpClient,
+        ) -> Box<dyn Indexer + Send + Sync + 'static> {
+            Box::new(PrivateIndexer {
+                name: format!("{name}-{}", self.answer),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Indexer for PrivateIndexer {
+        async fn search(&self, _request: &SearchRequest) -> IndexerResult {
+            tracing::debug!("{} searching", self.name);
+            IndexerResult::default()
+        }
+
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            IndexerResult::default()
+        }
+    }
+
+    #[test]
+    fn should_build_registered_types() {
+        let registry = IndexerRegistry::default().with_type::<PrivateConfig>("private");
+        let config: IndexerManagerConfig = toml::from_str(
+            r#"
+[mine]
+type = "private"
+answer = 42
+priority = 2.0
+
+[other]
+type = "private"
+answer = 1
+"#,
+        )
+        .unwrap();
+        let manager = config.build_with(&registry, Default::default()).unwrap();
+        assert_eq!(
+            manager.indexer_names().collect::<Vec<_>>(),
+            vec!["mine", "other"]
+        );
+        let built = registry
+            .build(
+                "private",
+                "other".into(),
+                serde_json::json!({ "answer": 1 }),
+                Default::default(),
+            )
+            .unwrap();
+        assert!(format!("{built:?}").contains("other-1"));
+    }
+
+    #[test]
+    fn should_reject_unknown_types_and_invalid_configs() {
+        let config: IndexerManagerConfig = toml::from_str(
+            r#"
+[mine]
+type = "private"
+"#,
+        )
+        .unwrap();
+        let error = config.build(Default::default()).unwrap_err();
+        assert!(matches!(error, BuildError::UnknownType { ref kind, .. } if kind == "private"));
+        assert_eq!(
+            error.to_string(),
+            r#"indexer "mine" has an unknown type "private""#
+        );
+
+        let registry = IndexerRegistry::empty().with_type::<PrivateConfig>("private");
+        let config: IndexerManagerConfig = toml::from_str(
+            r#"
+[mine]
+type = "private"
+answer = "many"
+"#,
+        )
+        .unwrap();
+        let error = config
+            .build_with(&registry, Default::default())
+            .unwrap_err();
+        assert!(matches!(error, BuildError::InvalidConfig { .. }));
+
+        let registry = IndexerRegistry::empty().with_missing("private", "indexer-private");
+        let config: IndexerManagerConfig = toml::from_str(
+            r#"
+[mine]
+type = "private"
+"#,
+        )
+        .unwrap();
+        let error = config
+            .build_with(&registry, Default::default())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"indexer "mine" has the type "private" that wasn't compiled in, the "indexer-private" feature should be enabled"#
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use manteau_indexer_helper::text::{tokenize, Token};
+
+fn words(tokens: &[Token]) -> impl Iterator<Item = &str> {
+    tokens.iter().filter_map(|token| match token {
+        Token::Word(word) => Some(word.as_str()),
+        _ => None,
+    })
+}
+
+/// A year was asked and the release is from another one.
+fn other_year(query: &[Token], name: &[Token]) -> bool {
+    let years = name
+        .iter()
+        .filter_map(|token| match token {
+            Token::Year(year) => Some(*year),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    !years.is_empty()
+        && query
+            .iter()
+            .any(|token| matches!(token, Token::Year(year) if !years.contains(year)))
+}
+
+/// An episode was asked and the release is about another one.
+fn other_episode(query: &[Token], name: &[Token]) -> bool {
+    let episodes = name
+        .iter()
+        .filter_map(|token| match token {
+            Token::Episode { season, episode } => Some((*season, *episode)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    !episodes.is_empty()
+        && query.iter().any(|token| match token {
+            Token::Episode { season, episode } => !episodes.iter().any(|(found, found_ep)| {
+                found == season && (episode.is_none() || found_ep.is_none() || found_ep == episode)
+            }),
+            _ => false,
+        })
+}
+
+/// How well a release name matches the query, between 0 and 1.
+///
+/// Every word of the query should be in the name, and the title part of the name,
+/// before its year or episode, shouldn't have many more words than the query.
+pub fn score(query: &str, name: &str) -> f64 {
+    let query = tokenize(query);
+    let name = tokenize(name);
+    if other_year(&query, &name) || other_episode(&query, &name) {
+        return 0.0;
+    }
+
+    let expected = words(&query).collect::<Vec<_>>();
+    if expected.is_empty() {
+        return 1.0;
+    }
+    let found = words(&name).collect::<Vec<_>>();
+    let recall =
+        expected.iter().filter(|word| found.contains(word)).count() as f64 / expected.len() as f64;
+
+    // without year or episode, the end of the title is unknown
+    let precision = match name.iter().position(|token| !token.is_word()) {
+        Some(end) if end > 0 => {
+            let title = words(&name[..end]).collect::<Vec<_>>();
+            title.iter().filter(|word| expected.contains(word)).count() as f64 / title.len() as f64
+        }
+        _ => 1.0,
+    };
+
+    recall * precision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::score;
+
+    #[test]
+    fn should_match_exact_title() {
+        assert_eq!(score("Dark", "Dark.S01E01.1080p.WEB.x264"), 1.0);
+        assert_eq!(score("the office", "The_Office.S02.720p"), 1.0);
+        assert_eq!(score("amelie", "Amélie (2001) FRENCH 1080p"), 1.0);
+    }
+
+    #[test]
+    fn should_penalize_longer_titles() {
+        assert_eq!(score("Dark", "Dark Phoenix 2019 1080p"), 0.5);
+        assert_eq!(score("Dark", "Darkwing Duck S01 DVDRip"), 0.0);
+    }
+
+    #[test]
+    fn should_reject_other_year_and_episode() {
+        assert_eq!(score("Dune 2021", "Dune.2021.2160p"), 1.0);
+        assert_eq!(score("Dune 2021", "Dune.1984.1080p"), 0.0);
+        assert_eq!(score("Dark S01E02", "Dark.S01E02.720p"), 1.0);
+        assert_eq!(score("Dark S01E02", "Dark.S01.COMPLETE.720p"), 1.0);
+        assert_eq!(score("Dark S01E02", "Dark.S01E03.720p"), 0.0);
+        assert_eq!(score("Dark S01E02", "Dark.S02.COMPLETE.720p"), 0.0);
+    }
+}
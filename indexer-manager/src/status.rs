@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use manteau_indexer_prelude::ratelimit::RateLimitUsage;
+use manteau_indexer_prelude::IndexerResult;
+use std::sync::Mutex;
+
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct IndexerStatus {
+    pub name: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_failure: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Number of entries returned by the last successful request.
+    pub last_entries: usize,
+    /// Consumption of the rate limits, when the indexer has some.
+    pub rate_limit: Option<RateLimitUsage>,
+}
+
+#[derive(Debug)]
+pub(crate) struct StatusTracker(Mutex<IndexerStatus>);
+
+impl StatusTracker {
+    pub fn new(name: String) -> Self {
+        Self(Mutex::new(IndexerStatus {
+            name,
+            ..Default::default()
+        }))
+    }
+
+    pub fn record(&self, result: &IndexerResult) {
+        let mut inner = self.0.lock().expect("status lock poisoned");
+        inner.requests += 1;
+        if result.is_failure() {
+            inner.failures += 1;
+            inner.last_failure = Some(Utc::now());
+            inner.last_error = result.errors.first().map(|err| err.to_string());
+        } else {
+            inner.last_success = Some(Utc::now());
+            inner.last_entries = result.entries.len();
+        }
+    }
+
+    pub fn snapshot(&self) -> IndexerStatus {
+        self.0.lock().expect("status lock poisoned").clone()
+    }
+}
@@ -0,0 +1,82 @@
+//! Entries built by the tests, here and in the crates using the manager.
+
+use chrono::{Duration, Utc};
+use manteau_indexer_helper::release;
+use manteau_indexer_prelude::bytesize::ByteSize;
+use manteau_indexer_prelude::IndexerEntry;
+
+/// Entry found at `https://example.com/{name}`, like `entry("Dune.2021").seeders(10).build()`.
+pub fn entry(name: &str) -> EntryBuilder {
+    EntryBuilder(IndexerEntry::new(
+        "example",
+        name,
+        format!("https://example.com/{name}"),
+    ))
+}
+
+#[derive(Clone, Debug)]
+pub struct EntryBuilder(IndexerEntry);
+
+impl EntryBuilder {
+    pub fn origin(mut self, origin: &'static str) -> Self {
+        self.0.origin = origin;
+        self
+    }
+
+    pub fn url<S: Into<String>>(mut self, url: S) -> Self {
+        self.0.url = url.into();
+        self
+    }
+
+    pub fn size(mut self, size: ByteSize) -> Self {
+        self.0.size = size;
+        self
+    }
+
+    pub fn seeders(mut self, seeders: usize) -> Self {
+        self.0.seeders = seeders;
+        self
+    }
+
+    pub fn leechers(mut self, leechers: usize) -> Self {
+        self.0.leechers = leechers;
+        self
+    }
+
+    pub fn magnet<S: Into<String>>(mut self, magnet: S) -> Self {
+        self.0.magnet = magnet.into();
+        self
+    }
+
+    pub fn uploader(mut self, uploader: &str) -> Self {
+        self.0.uploader = Some(uploader.into());
+        self
+    }
+
+    /// Uploaded that long ago.
+    pub fn age(mut self, age: Duration) -> Self {
+        self.0.date = Some(Utc::now() - age);
+        self
+    }
+
+    /// Without the upload date, like when the indexer doesn't tell.
+    pub fn undated(mut self) -> Self {
+        self.0.date = None;
+        self
+    }
+
+    pub fn files(mut self, files: &[&str]) -> Self {
+        self.0.files = files.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    /// With the release told by the name, as once the results are merged.
+    pub fn parsed(mut self) -> Self {
+        self.0.release = release::parse(&self.0.name);
+        self
+    }
+
+    pub fn build(self) -> IndexerEntry {
+        self.0
+    }
+}
@@ -0,0 +1,249 @@
+use crate::Category;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SearchMode {
+    Search,
+    TvSearch,
+    MovieSearch,
+    MusicSearch,
+    BookSearch,
+}
+
+impl SearchMode {
+    pub const ALL: [Self; 5] = [
+        Self::Search,
+        Self::TvSearch,
+        Self::MovieSearch,
+        Self::MusicSearch,
+        Self::BookSearch,
+    ];
+
+    /// Name of the element in the caps document.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Search => "search",
+            Self::TvSearch => "tv-search",
+            Self::MovieSearch => "movie-search",
+            Self::MusicSearch => "music-search",
+            Self::BookSearch => "book-search",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SearchParam {
+    Query,
+    Season,
+    Episode,
+    ImdbId,
+    TvdbId,
+    Artist,
+    Album,
+    Author,
+}
+
+impl SearchParam {
+    pub const ALL: [Self; 8] = [
+        Self::Query,
+        Self::Season,
+        Self::Episode,
+        Self::ImdbId,
+        Self::TvdbId,
+        Self::Artist,
+        Self::Album,
+        Self::Author,
+    ];
+
+    /// Name of the parameter in the torznab api.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Query => "q",
+            Self::Season => "season",
+            Self::Episode => "ep",
+            Self::ImdbId => "imdbid",
+            Self::TvdbId => "tvdbid",
+            Self::Artist => "artist",
+            Self::Album => "album",
+            Self::Author => "author",
+        }
+    }
+}
+
+/// Category of the site, with the newznab one it belongs to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CategoryMapping {
+    pub site: String,
+    pub category: Category,
+}
+
+/// What an indexer is able to search, declared to the clients in the caps document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Supported search modes, with their parameters.
+    pub searching: BTreeMap<SearchMode, BTreeSet<SearchParam>>,
+    /// Parameters only supported by being written in the keywords, like the season.
+    pub textual: BTreeSet<SearchParam>,
+    pub categories: Vec<CategoryMapping>,
+    /// The site can be asked for the next pages of results.
+    pub paging: bool,
+}
+
+impl Capabilities {
+    pub fn with_search(mut self, mode: SearchMode, params: &[SearchParam]) -> Self {
+        self.searching
+            .entry(mode)
+            .or_default()
+            .extend(params.iter().copied());
+        self.textual.retain(|param| !params.contains(param));
+        self
+    }
+
+    pub fn with_category<S: Into<String>>(mut self, site: S, category: Category) -> Self {
+        self.categories.push(CategoryMapping {
+            site: site.into(),
+            category,
+        });
+        self
+    }
+
+    pub fn with_paging(mut self, paging: bool) -> Self {
+        self.paging = paging;
+        self
+    }
+
+    /// Checks that the mode is supported with all the parameters, none of them being turned
+    /// into keywords.
+    pub fn supports(&self, mode: SearchMode, params: &[SearchParam]) -> bool {
+        self.searching.get(&mode).is_some_and(|supported| {
+            params
+                .iter()
+                .all(|param| supported.contains(param) && !self.textual.contains(param))
+        })
+    }
+
+    /// Parameters supported as they are by at least one of the modes.
+    fn native(&self) -> BTreeSet<SearchParam> {
+        self.searching
+            .values()
+            .flatten()
+            .filter(|param| !self.textual.contains(param))
+            .copied()
+            .collect()
+    }
+
+    /// Searches only made with keywords, every parameter being turned into text.
+    pub fn with_keyword_searches(self) -> Self {
+        use SearchParam::*;
+
+        let native = self.native();
+        let mut caps = self
+            .with_search(SearchMode::Search, &[Query])
+            .with_search(SearchMode::TvSearch, &[Query, Season, Episode])
+            .with_search(SearchMode::MovieSearch, &[Query])
+            .with_search(SearchMode::MusicSearch, &[Query, Artist, Album])
+            .with_search(SearchMode::BookSearch, &[Query, Author]);
+        caps.textual.extend(
+            [Season, Episode, Artist, Album, Author]
+                .into_iter()
+                .filter(|param| !native.contains(param)),
+        );
+        caps
+    }
+
+    /// What several indexers support together.
+    pub fn union(mut self, other: &Self) -> Self {
+        let native = self
+            .native()
+            .union(&other.native())
+            .copied()
+            .collect::<Vec<_>>();
+        self.textual.extend(other.textual.iter().copied());
+        self.textual.retain(|param| !native.contains(param));
+        for (mode, params) in other.searching.iter() {
+            self.searching
+                .entry(*mode)
+                .or_default()
+                .extend(params.iter().copied());
+        }
+        for mapping in other.categories.iter() {
+            if !self.categories.contains(mapping) {
+                self.categories.push(mapping.clone());
+            }
+        }
+        self.paging |= other.paging;
+        self
+    }
+
+    /// Newznab categories, each kind only once and by increasing id.
+    pub fn newznab_categories(&self) -> Vec<Category> {
+        let mut found: Vec<Category> = Vec::new();
+        for mapping in self.categories.iter() {
+            if !found
+                .iter()
+                .any(|category| category.kind() == mapping.category.kind())
+            {
+                found.push(mapping.category);
+            }
+        }
+        found.sort_by_key(Category::kind);
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Capabilities, SearchMode, SearchParam};
+    use crate::Category;
+
+    #[test]
+    fn should_merge_capabilities() {
+        let first = Capabilities::default()
+            .with_search(SearchMode::Search, &[SearchParam::Query])
+            .with_category("Movies", Category::Movie)
+            .with_category("Music", Category::Music);
+        let second = Capabilities::default()
+            .with_search(
+                SearchMode::Search,
+                &[SearchParam::Query, SearchParam::ImdbId],
+            )
+            .with_search(SearchMode::TvSearch, &[SearchParam::Query])
+            .with_category("FLAC", Category::Audio)
+            .with_category("Movies", Category::Movie)
+            .with_paging(true);
+
+        let merged = first.union(&second);
+
+        assert_eq!(
+            merged.searching[&SearchMode::Search]
+                .iter()
+                .map(SearchParam::name)
+                .collect::<Vec<_>>(),
+            vec!["q", "imdbid"]
+        );
+        assert!(merged.searching.contains_key(&SearchMode::TvSearch));
+        assert!(merged.supports(SearchMode::Search, &[SearchParam::ImdbId]));
+        assert!(!merged.supports(SearchMode::TvSearch, &[SearchParam::ImdbId]));
+        assert_eq!(merged.categories.len(), 3);
+
+        let keywords = Capabilities::default()
+            .with_keyword_searches()
+            .with_search(SearchMode::MovieSearch, &[SearchParam::ImdbId]);
+        let episodes = [
+            SearchParam::Query,
+            SearchParam::Season,
+            SearchParam::Episode,
+        ];
+        assert!(keywords.supports(SearchMode::MovieSearch, &[SearchParam::ImdbId]));
+        assert!(!keywords.supports(SearchMode::TvSearch, &episodes));
+        let native = Capabilities::default().with_search(SearchMode::TvSearch, &episodes);
+        assert!(keywords
+            .union(&native)
+            .supports(SearchMode::TvSearch, &episodes));
+        assert_eq!(
+            merged.newznab_categories(),
+            vec![Category::Movie, Category::Music]
+        );
+        assert!(merged.paging);
+    }
+}
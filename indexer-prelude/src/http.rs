@@ -0,0 +1,385 @@
+use crate::ratelimit::RateLimiter;
+use crate::{IndexerError, IndexerErrorReason};
+use reqwest::IntoUrl;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct HttpConfig {
+    /// Maximum number of requests running at the same time, across all the indexers.
+    #[serde(default = "HttpConfig::default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Minimum delay, in milliseconds, between two requests to the same host.
+    #[serde(default = "HttpConfig::default_min_host_delay")]
+    pub min_host_delay: u64,
+    /// Deadline, in seconds, of a request, including the time spent waiting for the rate limits.
+    #[serde(default = "HttpConfig::default_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: Self::default_max_concurrency(),
+            min_host_delay: Self::default_min_host_delay(),
+            timeout: Self::default_timeout(),
+        }
+    }
+}
+
+impl HttpConfig {
+    fn default_max_concurrency() -> usize {
+        16
+    }
+
+    fn default_min_host_delay() -> u64 {
+        200
+    }
+
+    fn default_timeout() -> u64 {
+        30
+    }
+
+    pub fn build(self) -> HttpClient {
+        let timeout = Duration::from_secs(self.timeout);
+        HttpClient {
+            inner: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("unable to build http client"),
+            timeout,
+            limits: Arc::new(Limits {
+                permits: Semaphore::new(self.max_concurrency.max(1)),
+                min_host_delay: Duration::from_millis(self.min_host_delay),
+                hosts: Mutex::new(HashMap::new()),
+            }),
+            rate_limiter: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Limits {
+    permits: Semaphore,
+    min_host_delay: Duration,
+    // next time a request can be sent to each host
+    hosts: Mutex<HashMap<String, Instant>>,
+}
+
+impl Limits {
+    async fn wait_for_host(&self, host: &str) {
+        if self.min_host_delay.is_zero() {
+            return;
+        }
+        let slot = {
+            let mut hosts = self.hosts.lock().expect("hosts lock poisoned");
+            let now = Instant::now();
+            let slot = hosts.get(host).copied().unwrap_or(now).max(now);
+            hosts.insert(host.to_string(), slot + self.min_host_delay);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// Http client shared by the indexers, so that the requests to the upstreams can be bounded.
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    inner: reqwest::Client,
+    timeout: Duration,
+    limits: Arc<Limits>,
+    // specific to the indexer using the client
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        HttpConfig::default().build()
+    }
+}
+
+impl HttpClient {
+    /// Client sharing the same limits, with an additional rate limiter.
+    pub fn with_rate_limiter(&self, rate_limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            rate_limiter: Some(rate_limiter),
+            ..self.clone()
+        }
+    }
+
+    async fn throttle(&self, origin: &'static str, deadline: Instant) -> Result<(), IndexerError> {
+        let Some(ref rate_limiter) = self.rate_limiter else {
+            return Ok(());
+        };
+        rate_limiter
+            .acquire(deadline.saturating_duration_since(Instant::now()))
+            .await
+            .map_err(|retry_in| {
+                IndexerError::new(origin, IndexerErrorReason::RateLimited { retry_in })
+            })
+    }
+
+    /// Sends the request once the host can be contacted, the permit being kept by the caller
+    /// until the body is read.
+    ///
+    /// The request only gets what remains before the deadline once the limits are waited for.
+    async fn send(
+        &self,
+        origin: &'static str,
+        url: reqwest::Url,
+        deadline: Instant,
+    ) -> Result<(reqwest::Response, SemaphorePermit<'_>), IndexerError> {
+        let url_str = url.to_string();
+        let error = |cause: String| {
+            IndexerError::new(
+                origin,
+                IndexerErrorReason::UnableToQuery {
+                    url: url_str.clone(),
+                    cause,
+                },
+            )
+        };
+        let limits = async {
+            // waiting for a slow host must not hold a permit needed by the other ones
+            self.limits
+                .wait_for_host(url.host_str().unwrap_or_default())
+                .await;
+            self.limits
+                .permits
+                .acquire()
+                .await
+                .expect("http permits closed")
+        };
+        let permit = tokio::time::timeout_at(deadline, limits)
+            .await
+            .map_err(|_| error("timed out waiting for the limits".into()))?;
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let res = self
+            .inner
+            .get(url)
+            .timeout(remaining)
+            .send()
+            .await
+            .map_err(|cause| error(cause.to_string()))?;
+        Ok((res, permit))
+    }
+
+    fn parse_url<U: IntoUrl + ToString>(
+        origin: &'static str,
+        url: U,
+    ) -> Result<reqwest::Url, IndexerError> {
+        let url_str = url.to_string();
+        url.into_url().map_err(|cause| {
+            IndexerError::new(
+                origin,
+                IndexerErrorReason::UnableToQuery {
+                    url: url_str,
+                    cause: cause.to_string(),
+                },
+            )
+        })
+    }
+
+    pub async fn get_text<U: IntoUrl + ToString>(
+        &self,
+        origin: &'static str,
+        url: U,
+    ) -> Result<String, IndexerError> {
+        let url = Self::parse_url(origin, url)?;
+        let url_str = url.to_string();
+        let deadline = Instant::now() + self.timeout;
+        self.throttle(origin, deadline).await?;
+        let (res, _permit) = self.send(origin, url, deadline).await?;
+        res.text().await.map_err(|cause| {
+            IndexerError::new(
+                origin,
+                IndexerErrorReason::UnableToRead {
+                    url: url_str,
+                    cause: cause.to_string(),
+                },
+            )
+        })
+    }
+
+    pub async fn get_json<T: serde::de::DeserializeOwned, U: IntoUrl + ToString>(
+        &self,
+        origin: &'static str,
+        url: U,
+    ) -> Result<T, IndexerError> {
+        let url = Self::parse_url(origin, url)?;
+        let url_str = url.to_string();
+        let deadline = Instant::now() + self.timeout;
+        self.throttle(origin, deadline).await?;
+        let (res, _permit) = self.send(origin, url, deadline).await?;
+        res.json().await.map_err(|cause| {
+            IndexerError::new(
+                origin,
+                IndexerErrorReason::UnableToRead {
+                    url: url_str,
+                    cause: cause.to_string(),
+                },
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HttpConfig;
+    use crate::ratelimit::RateLimitConfig;
+    use crate::IndexerErrorReason;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn should_space_requests_to_same_host() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_body("hello")
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = HttpConfig {
+            max_concurrency: 10,
+            min_host_delay: 100,
+            ..Default::default()
+        }
+        .build();
+        let url = server.url();
+
+        let start = Instant::now();
+        let (first, second, third) = tokio::join!(
+            client.get_text("test", url.as_str()),
+            client.get_text("test", url.as_str()),
+            client.get_text("test", url.as_str()),
+        );
+        assert_eq!(first.unwrap(), "hello");
+        assert_eq!(second.unwrap(), "hello");
+        assert_eq!(third.unwrap(), "hello");
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_not_hold_permits_while_waiting_for_host() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_body("hello")
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = HttpConfig {
+            max_concurrency: 1,
+            min_host_delay: 500,
+            ..Default::default()
+        }
+        .build();
+        let url = server.url();
+        // same server, seen as another host
+        let other = url.replace("127.0.0.1", "localhost");
+
+        let start = Instant::now();
+        let (first, second, elapsed) = tokio::join!(
+            client.get_text("test", url.as_str()),
+            client.get_text("test", url.as_str()),
+            async {
+                client.get_text("test", other.as_str()).await.unwrap();
+                start.elapsed()
+            },
+        );
+        assert!(first.is_ok() && second.is_ok());
+        assert!(elapsed < Duration::from_millis(400), "{elapsed:?}");
+        assert!(start.elapsed() >= Duration::from_millis(500));
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_count_the_waits_in_the_timeout() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_body_from_request(|_| {
+                std::thread::sleep(Duration::from_millis(500));
+                b"hello".to_vec()
+            })
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let client = HttpConfig {
+            min_host_delay: 700,
+            timeout: 1,
+            ..Default::default()
+        }
+        .build();
+        let url = server.url();
+
+        // the second one waits 700ms for the host, leaving too little for the answer
+        let (first, second) = tokio::join!(
+            client.get_text("test", url.as_str()),
+            client.get_text("test", url.as_str()),
+        );
+        assert_eq!(first.unwrap(), "hello");
+        assert!(matches!(
+            second.unwrap_err().reason,
+            IndexerErrorReason::UnableToQuery { .. }
+        ));
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_rate_limited() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_body("hello")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = HttpConfig {
+            min_host_delay: 0,
+            timeout: 1,
+            ..Default::default()
+        }
+        .build()
+        .with_rate_limiter(Arc::new(
+            RateLimitConfig {
+                per_minute: Some(1),
+                ..Default::default()
+            }
+            .build(),
+        ));
+        let url = server.url();
+
+        assert!(client.get_text("test", url.as_str()).await.is_ok());
+        let error = client.get_text("test", url.as_str()).await.unwrap_err();
+        assert!(matches!(
+            error.reason,
+            IndexerErrorReason::RateLimited { .. }
+        ));
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_report_invalid_urls() {
+        let client = HttpConfig::default().build();
+        assert!(client.get_text("test", "not an url").await.is_err());
+    }
+}
@@ -1,12 +1,23 @@
 pub use bytesize;
 
+pub mod capabilities;
+pub mod http;
+pub mod ratelimit;
+mod request;
+
+pub use request::{SearchRequest, DEFAULT_LIMIT};
+
 use bytesize::ByteSize;
 use chrono::{DateTime, Utc};
 use manteau_indexer_helper::numeric::ParseNumberError;
+use manteau_indexer_helper::release::Release;
+use std::collections::HashSet;
 use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 use url::ParseError;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Category {
     Audio,
     Book,
@@ -25,6 +36,16 @@ impl Category {
         }
     }
 
+    /// Name of the newznab category.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Audio | Self::Music => "Audio",
+            Self::Movie => "Movies",
+            Self::Tv => "TV",
+            Self::Book => "Books",
+        }
+    }
+
     pub fn kind_str(&self) -> &'static str {
         match self {
             Self::Audio | Self::Music => "3000",
@@ -63,14 +84,90 @@ impl FromStr for Category {
     }
 }
 
+impl<'de> serde::Deserialize<'de> for Category {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct CategoryVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for CategoryVisitor {
+            type Value = Category;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("2000, 3000, 5000 or 7000 are the expected values")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Category::from_str(v).map_err(serde::de::Error::custom)
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                u32::try_from(value)
+                    .map_err(serde::de::Error::custom)
+                    .and_then(|value| Category::try_from(value).map_err(serde::de::Error::custom))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                u32::try_from(value)
+                    .map_err(serde::de::Error::custom)
+                    .and_then(|value| Category::try_from(value).map_err(serde::de::Error::custom))
+            }
+        }
+
+        deserializer.deserialize_any(CategoryVisitor)
+    }
+}
+
 pub trait IndexerBuilder: std::fmt::Debug {
-    fn build(self, name: String) -> Box<dyn Indexer + Send + Sync + 'static>;
+    fn build(
+        self,
+        name: String,
+        client: http::HttpClient,
+    ) -> Box<dyn Indexer + Send + Sync + 'static>;
 }
 
 #[async_trait::async_trait]
 pub trait Indexer: std::fmt::Debug {
-    async fn search(&self, query: &str) -> IndexerResult;
+    async fn search(&self, request: &SearchRequest) -> IndexerResult;
     async fn feed(&self, category: Category) -> IndexerResult;
+
+    /// Resolves a link deferred by the indexer into a magnet or torrent url.
+    async fn resolve(&self, _token: &str) -> Result<String, IndexerError> {
+        Err(IndexerError::new(
+            std::any::type_name::<Self>(),
+            IndexerErrorReason::EntryLinkNotResolvable,
+        ))
+    }
+
+    /// What the indexer can search, by default only keywords in any category.
+    fn capabilities(&self) -> capabilities::Capabilities {
+        [
+            Category::Movie,
+            Category::Audio,
+            Category::Tv,
+            Category::Book,
+        ]
+        .into_iter()
+        .fold(
+            capabilities::Capabilities::default().with_keyword_searches(),
+            |caps, category| caps.with_category(category.name(), category),
+        )
+    }
+
+    /// Minimum delay between two feed refreshes tolerated by the site.
+    fn feed_interval(&self) -> Duration {
+        Duration::from_secs(300)
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -112,6 +209,11 @@ impl IndexerResult {
         self.errors.extend(other.errors);
         self
     }
+
+    /// Nothing but errors came back from the indexers.
+    pub fn is_failure(&self) -> bool {
+        self.entries.is_empty() && !self.errors.is_empty()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -141,6 +243,7 @@ pub enum IndexerErrorReason {
         cause: chrono::format::ParseErrorKind,
     },
     EntryMagnetNotFound,
+    EntryLinkNotResolvable,
     UnableToQuery {
         url: String,
         cause: String,
@@ -152,6 +255,9 @@ pub enum IndexerErrorReason {
     UnableToBuildUrl {
         cause: ParseError,
     },
+    RateLimited {
+        retry_in: Duration,
+    },
 }
 
 impl IndexerError {
@@ -176,20 +282,105 @@ impl std::error::Error for IndexerError {}
 pub struct IndexerEntry {
     pub name: String,
     pub url: String,
-    pub date: DateTime<Utc>,
+    /// Upload date, when the indexer tells.
+    pub date: Option<DateTime<Utc>>,
     pub size: ByteSize,
     pub seeders: usize,
     pub leechers: usize,
     pub magnet: String,
     pub origin: &'static str,
+    /// Account that uploaded the release, when the indexer tells.
+    pub uploader: Option<String>,
+    /// Uppercase hexadecimal info hash, when known from the magnet.
+    pub info_hash: Option<String>,
+    /// What the name tells about the release, filled when the results are merged.
+    pub release: Release,
+    /// Set when the magnet is only resolved once the entry is downloaded.
+    pub deferred: Option<DeferredLink>,
+    /// Same release found on other indexers, filled when the results are merged.
+    pub duplicates: Vec<EntrySource>,
+    /// Names of the files in the torrent, empty when not fetched.
+    pub files: Vec<String>,
+    /// Why the entry looks like a fake, filled when the results are merged.
+    pub suspicious: Vec<&'static str>,
+}
+
+/// Origin of the entries of an indexer only named at runtime, like by the configuration.
+///
+/// The origin lives as long as the application, each name being only allocated once.
+pub fn leak_origin(name: &str) -> &'static str {
+    static ORIGINS: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut origins = ORIGINS
+        .get_or_init(Default::default)
+        .lock()
+        .expect("origins lock poisoned");
+    if let Some(found) = origins.get(name) {
+        return found;
+    }
+    let leaked: &'static str = Box::leak(name.to_string().into_boxed_str());
+    origins.insert(leaked);
+    leaked
+}
+
+/// Place where a release has been found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntrySource {
+    pub origin: &'static str,
+    pub url: String,
+}
+
+/// Reference to an entry whose magnet is resolved by the indexer on demand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeferredLink {
+    /// Name of the indexer able to resolve the link.
+    pub indexer: String,
+    pub token: String,
 }
 
 impl IndexerEntry {
+    /// Entry found just now, the values not given being empty or unknown.
+    pub fn new<N, U>(origin: &'static str, name: N, url: U) -> Self
+    where
+        N: Into<String>,
+        U: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            url: url.into(),
+            date: None,
+            size: ByteSize::default(),
+            seeders: 0,
+            leechers: 0,
+            magnet: String::new(),
+            origin,
+            uploader: None,
+            info_hash: None,
+            release: Release::default(),
+            deferred: None,
+            duplicates: Vec::new(),
+            files: Vec::new(),
+            suspicious: Vec::new(),
+        }
+    }
+
+    /// Upload date, the current one when unknown as the feeds require one.
     pub fn date_str(&self) -> String {
-        self.date.to_rfc2822()
+        self.date.unwrap_or_else(Utc::now).to_rfc2822()
     }
 
     pub fn size_str(&self) -> String {
         self.size.as_u64().to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn should_leak_each_origin_once() {
+        let first = super::leak_origin("upstream");
+        let second = super::leak_origin(&String::from("upstream"));
+        assert_eq!(first, "upstream");
+        assert!(std::ptr::eq(first, second));
+        assert_ne!(super::leak_origin("other"), first);
+    }
+}
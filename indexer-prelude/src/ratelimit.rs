@@ -0,0 +1,240 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct RateLimitConfig {
+    /// Maximum number of requests per second.
+    #[serde(default)]
+    pub per_second: Option<u32>,
+    /// Maximum number of requests per minute.
+    #[serde(default)]
+    pub per_minute: Option<u32>,
+    /// Maximum number of requests per day, the budget is reset every 24 hours.
+    #[serde(default)]
+    pub daily: Option<u64>,
+}
+
+impl RateLimitConfig {
+    pub fn build(self) -> RateLimiter {
+        let now = Instant::now();
+        let buckets = [
+            self.per_second.map(|max| (max, Duration::from_secs(1))),
+            self.per_minute.map(|max| (max, Duration::from_secs(60))),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|(max, period)| Bucket::new(max, period, now))
+        .collect();
+        RateLimiter {
+            daily: self.daily,
+            state: Mutex::new(State {
+                buckets,
+                day_start: now,
+                day_used: 0,
+                rejected: 0,
+            }),
+        }
+    }
+}
+
+/// Budget consumption of a rate limiter, as reported in the indexer status.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct RateLimitUsage {
+    /// Requests sent since the daily budget was last reset.
+    pub daily_used: u64,
+    pub daily_budget: Option<u64>,
+    /// Requests rejected because they couldn't be sent in time.
+    pub rejected: u64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    // tokens added per second
+    rate: f64,
+    // can go below zero when requests are waiting for their turn
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(max: u32, period: Duration, now: Instant) -> Self {
+        let capacity = f64::from(max.max(1));
+        Self {
+            capacity,
+            rate: capacity / period.as_secs_f64(),
+            tokens: capacity,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity);
+        self.updated_at = now;
+    }
+
+    fn wait(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    buckets: Vec<Bucket>,
+    day_start: Instant,
+    day_used: u64,
+    rejected: u64,
+}
+
+/// Token bucket rate limiter, with an optional daily budget.
+#[derive(Debug)]
+pub struct RateLimiter {
+    daily: Option<u64>,
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    /// Reserves a slot for a request, waiting at most `max_wait` for it.
+    ///
+    /// When the slot cannot be obtained in time, nothing is consumed and the delay
+    /// after which a retry could succeed is returned.
+    pub async fn acquire(&self, max_wait: Duration) -> Result<(), Duration> {
+        let wait = self.reserve(max_wait, Instant::now())?;
+        if !wait.is_zero() {
+            tracing::debug!("rate limited, waiting {wait:?}");
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Takes a slot only if one is available right away.
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        self.reserve(Duration::ZERO, Instant::now()).map(|_| ())
+    }
+
+    fn reserve(&self, max_wait: Duration, now: Instant) -> Result<Duration, Duration> {
+        let mut state = self.state.lock().expect("rate limiter lock poisoned");
+        if now.saturating_duration_since(state.day_start) >= DAY {
+            state.day_start = now;
+            state.day_used = 0;
+        }
+        if self.daily.is_some_and(|daily| state.day_used >= daily) {
+            state.rejected += 1;
+            return Err((state.day_start + DAY).saturating_duration_since(now));
+        }
+        state
+            .buckets
+            .iter_mut()
+            .for_each(|bucket| bucket.refill(now));
+        let wait = state
+            .buckets
+            .iter()
+            .map(Bucket::wait)
+            .max()
+            .unwrap_or_default();
+        if wait > max_wait {
+            state.rejected += 1;
+            return Err(wait);
+        }
+        state
+            .buckets
+            .iter_mut()
+            .for_each(|bucket| bucket.tokens -= 1.0);
+        state.day_used += 1;
+        Ok(wait)
+    }
+
+    pub fn usage(&self) -> RateLimitUsage {
+        let state = self.state.lock().expect("rate limiter lock poisoned");
+        RateLimitUsage {
+            daily_used: state.day_used,
+            daily_budget: self.daily,
+            rejected: state.rejected,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimitConfig, DAY};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[test]
+    fn should_queue_requests_until_max_wait() {
+        let limiter = RateLimitConfig {
+            per_second: Some(2),
+            ..Default::default()
+        }
+        .build();
+        let now = Instant::now();
+        let max_wait = Duration::from_secs(1);
+        assert_eq!(limiter.reserve(max_wait, now), Ok(Duration::ZERO));
+        assert_eq!(limiter.reserve(max_wait, now), Ok(Duration::ZERO));
+        assert_eq!(
+            limiter.reserve(max_wait, now),
+            Ok(Duration::from_millis(500))
+        );
+        assert_eq!(limiter.reserve(max_wait, now), Ok(Duration::from_secs(1)));
+        assert_eq!(
+            limiter.reserve(max_wait, now),
+            Err(Duration::from_millis(1500))
+        );
+        // the bucket refills with time
+        let later = now + Duration::from_secs(2);
+        assert_eq!(limiter.reserve(max_wait, later), Ok(Duration::ZERO));
+        assert_eq!(limiter.usage().rejected, 1);
+        assert_eq!(limiter.usage().daily_used, 5);
+    }
+
+    #[test]
+    fn should_apply_the_strictest_bucket() {
+        let limiter = RateLimitConfig {
+            per_second: Some(10),
+            per_minute: Some(1),
+            ..Default::default()
+        }
+        .build();
+        let now = Instant::now();
+        assert!(limiter.reserve(Duration::from_secs(1), now).is_ok());
+        let retry_in = limiter
+            .reserve(Duration::from_secs(1), now)
+            .expect_err("should be limited by the minute bucket");
+        assert!(retry_in > Duration::from_secs(59));
+    }
+
+    #[test]
+    fn should_enforce_the_daily_budget() {
+        let limiter = RateLimitConfig {
+            daily: Some(2),
+            ..Default::default()
+        }
+        .build();
+        let now = Instant::now();
+        assert!(limiter.reserve(Duration::ZERO, now).is_ok());
+        assert!(limiter.reserve(Duration::ZERO, now).is_ok());
+        let retry_in = limiter
+            .reserve(Duration::ZERO, now)
+            .expect_err("should have consumed the budget");
+        assert!(retry_in <= DAY && retry_in > DAY - Duration::from_secs(1));
+        assert_eq!(
+            limiter.usage(),
+            super::RateLimitUsage {
+                daily_used: 2,
+                daily_budget: Some(2),
+                rejected: 1,
+            }
+        );
+        // the budget is reset the next day
+        assert!(limiter.reserve(Duration::ZERO, now + DAY).is_ok());
+        assert_eq!(limiter.usage().daily_used, 1);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::capabilities::{SearchMode, SearchParam};
+use crate::{Category, IndexerEntry};
+use manteau_indexer_helper::episode::Target;
+
+/// Entries in a page when the client doesn't tell.
+pub const DEFAULT_LIMIT: usize = 100;
+
+/// What a client is looking for, each indexer making the best of what it supports.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SearchRequest {
+    /// Free text, usually the title.
+    pub query: String,
+    /// Any of them, every category when empty.
+    pub categories: Vec<Category>,
+    /// Like `tt0460649`.
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u32>,
+    /// Season, or year of a daily show.
+    pub season: Option<u16>,
+    /// Episode number, or `month/day` of a daily show.
+    pub episode: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub author: Option<String>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl SearchRequest {
+    pub fn new<S: Into<String>>(query: S) -> Self {
+        Self {
+            query: query.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_category(mut self, category: Category) -> Self {
+        self.categories.push(category);
+        self
+    }
+
+    /// The only category asked, if there's one.
+    pub fn category(&self) -> Option<Category> {
+        let (first, others) = self.categories.split_first()?;
+        others
+            .iter()
+            .all(|other| other.kind() == first.kind())
+            .then_some(*first)
+    }
+
+    /// Mode of the torznab api fitting the parameters.
+    pub fn mode(&self) -> SearchMode {
+        if self.season.is_some() || self.episode.is_some() || self.tvdb_id.is_some() {
+            SearchMode::TvSearch
+        } else if self.artist.is_some() || self.album.is_some() {
+            SearchMode::MusicSearch
+        } else if self.author.is_some() {
+            SearchMode::BookSearch
+        } else if self.imdb_id.is_some() {
+            match self.category() {
+                Some(Category::Tv) => SearchMode::TvSearch,
+                _ => SearchMode::MovieSearch,
+            }
+        } else {
+            SearchMode::Search
+        }
+    }
+
+    /// Parameters given a value, the query only when it isn't blank.
+    pub fn params(&self) -> Vec<SearchParam> {
+        let given = |value: &Option<String>| value.as_deref().is_some_and(|v| !v.trim().is_empty());
+        [
+            (SearchParam::Query, !self.query.trim().is_empty()),
+            (SearchParam::Season, self.season.is_some()),
+            (SearchParam::Episode, given(&self.episode)),
+            (SearchParam::ImdbId, given(&self.imdb_id)),
+            (SearchParam::TvdbId, self.tvdb_id.is_some()),
+            (SearchParam::Artist, given(&self.artist)),
+            (SearchParam::Album, given(&self.album)),
+            (SearchParam::Author, given(&self.author)),
+        ]
+        .into_iter()
+        .filter_map(|(param, found)| found.then_some(param))
+        .collect()
+    }
+
+    /// Episode or season asked by a tv search.
+    pub fn target(&self) -> Option<Target> {
+        let season = self.season.map(|value| value.to_string());
+        Target::from_params(season.as_deref(), self.episode.as_deref())
+    }
+
+    /// Text search for the indexers not supporting anything else.
+    pub fn keywords(&self) -> String {
+        let query = self.query.trim();
+        let lowercase = query.to_lowercase();
+        let mut words = vec![query.to_string()];
+        for extra in [&self.artist, &self.album, &self.author]
+            .into_iter()
+            .flatten()
+        {
+            // clients often repeat the artist in the query
+            if !lowercase.contains(&extra.trim().to_lowercase()) {
+                words.push(extra.trim().to_string());
+            }
+        }
+        if let Some(target) = self.target() {
+            words.push(target.notations()[0].trim().to_string());
+        }
+        words.retain(|word| !word.is_empty());
+        words.join(" ")
+    }
+
+    /// Same search from the first entry to the end of the asked page, for the indexers
+    /// able to page, the merged results being paginated afterwards.
+    pub fn up_to_page(&self) -> Self {
+        let limit = match self.offset {
+            0 => self.limit,
+            offset => Some(offset + self.limit.unwrap_or(DEFAULT_LIMIT)),
+        };
+        Self {
+            offset: 0,
+            limit,
+            ..self.clone()
+        }
+    }
+
+    /// Same search without the page, for the indexers only answering with their first one.
+    pub fn without_page(&self) -> Self {
+        Self {
+            offset: 0,
+            limit: None,
+            ..self.clone()
+        }
+    }
+
+    /// Keeps the requested page of the entries.
+    pub fn paginate(&self, entries: &mut Vec<IndexerEntry>) {
+        entries.drain(..self.offset.min(entries.len()));
+        if let Some(limit) = self.limit {
+            entries.truncate(limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchRequest;
+    use crate::capabilities::{SearchMode, SearchParam};
+    use crate::Category;
+
+    #[test]
+    fn should_build_keywords() {
+        assert_eq!(SearchRequest::new(" dune ").keywords(), "dune");
+        let request = SearchRequest {
+            query: "Dark".into(),
+            season: Some(1),
+            episode: Some("2".into()),
+            ..Default::default()
+        };
+        assert_eq!(request.keywords(), "Dark S01E02");
+        let request = SearchRequest {
+            query: "daft punk".into(),
+            artist: Some("Daft Punk".into()),
+            album: Some("Discovery".into()),
+            ..Default::default()
+        };
+        assert_eq!(request.keywords(), "daft punk Discovery");
+    }
+
+    #[test]
+    fn should_tell_mode_and_params() {
+        let request = SearchRequest {
+            imdb_id: Some("tt0133093".into()),
+            ..Default::default()
+        };
+        assert_eq!(request.mode(), SearchMode::MovieSearch);
+        assert_eq!(request.params(), vec![SearchParam::ImdbId]);
+        let request = SearchRequest {
+            query: "Dark".into(),
+            season: Some(1),
+            artist: Some(" ".into()),
+            ..Default::default()
+        };
+        assert_eq!(request.mode(), SearchMode::TvSearch);
+        assert_eq!(
+            request.params(),
+            vec![SearchParam::Query, SearchParam::Season]
+        );
+    }
+
+    #[test]
+    fn should_ask_pages_before_the_requested_one() {
+        let request = SearchRequest {
+            offset: 100,
+            limit: Some(50),
+            ..SearchRequest::new("dune")
+        };
+        assert_eq!(
+            (request.up_to_page().offset, request.up_to_page().limit),
+            (0, Some(150))
+        );
+        let request = SearchRequest {
+            offset: 100,
+            ..SearchRequest::new("dune")
+        };
+        assert_eq!(request.up_to_page().limit, Some(200));
+        assert_eq!(SearchRequest::new("dune").up_to_page().limit, None);
+        assert_eq!(request.without_page(), SearchRequest::new("dune"));
+    }
+
+    #[test]
+    fn should_find_single_category() {
+        let request = SearchRequest::new("foo");
+        assert_eq!(request.category(), None);
+        let request = request.with_category(Category::Audio);
+        assert_eq!(request.category(), Some(Category::Audio));
+        let request = request.with_category(Category::Music);
+        assert_eq!(request.category(), Some(Category::Audio));
+        let request = request.with_category(Category::Movie);
+        assert_eq!(request.category(), None);
+    }
+}
@@ -2,7 +2,7 @@ use manteau_indexer_prelude::{IndexerError, IndexerErrorReason};
 use url::Url;
 
 pub fn create_magnet(name: &str, info_hash: &str) -> Result<String, IndexerError> {
-    let xt = format!("urn:bith:{info_hash}");
+    let xt = format!("urn:btih:{info_hash}");
     Url::parse_with_params(
         "magnet:",
         [
@@ -1,6 +1,8 @@
 use bytesize::ByteSize;
 use chrono::format::ParseErrorKind;
 use chrono::{DateTime, Utc};
+use manteau_indexer_helper::magnet::info_hash;
+use manteau_indexer_prelude::http::HttpClient;
 use manteau_indexer_prelude::{IndexerEntry, IndexerError, IndexerErrorReason, IndexerResult};
 
 #[derive(Debug, serde::Deserialize)]
@@ -13,6 +15,8 @@ pub(crate) struct Entry {
     seeders: usize,
     added: i64,
     size: u64,
+    #[serde(default)]
+    username: Option<String>,
 }
 
 impl Entry {
@@ -37,46 +41,48 @@ impl Entry {
     }
 
     pub(crate) fn try_into(self, base_url: &str) -> Result<IndexerEntry, IndexerError> {
+        let magnet = crate::common::create_magnet(self.name.as_str(), self.info_hash.as_str())?;
         Ok(IndexerEntry {
             name: self.name.trim().to_string(),
             url: self.url(base_url),
-            date: self.date()?,
+            date: Some(self.date()?),
             size: self.size(),
             seeders: self.seeders,
             leechers: self.leechers,
-            magnet: crate::common::create_magnet(self.name.as_str(), self.info_hash.as_str())?,
+            info_hash: info_hash(&magnet),
+            release: Default::default(),
+            magnet,
             origin: super::NAME,
+            uploader: self.username.filter(|name| !name.is_empty()),
+            deferred: None,
+            duplicates: Vec::new(),
+            files: Vec::new(),
+            suspicious: Vec::new(),
         })
     }
 }
 
-async fn fetch(api_url: &str, category: u16) -> Result<Vec<Entry>, IndexerError> {
+async fn fetch(
+    client: &HttpClient,
+    api_url: &str,
+    category: u16,
+) -> Result<Vec<Entry>, IndexerError> {
     let url = format!("{api_url}/precompiled/data_top100_{category}.json");
-
-    let req = reqwest::get(url.as_str()).await.map_err(|err| {
-        IndexerError::new(
-            super::NAME,
-            IndexerErrorReason::UnableToQuery {
-                url: url.clone(),
-                cause: err.to_string(),
-            },
-        )
-    })?;
-    req.json().await.map_err(|err| {
-        IndexerError::new(
-            super::NAME,
-            IndexerErrorReason::UnableToRead {
-                url,
-                cause: err.to_string(),
-            },
-        )
-    })
+    client.get_json(super::NAME, url).await
 }
 
-pub async fn execute(api_url: &str, base_url: &str, categories: &[u16]) -> IndexerResult {
-    let category_responses =
-        futures::future::join_all(categories.iter().map(|category| fetch(api_url, *category)))
-            .await;
+pub async fn execute(
+    client: &HttpClient,
+    api_url: &str,
+    base_url: &str,
+    categories: &[u16],
+) -> IndexerResult {
+    let category_responses = futures::future::join_all(
+        categories
+            .iter()
+            .map(|category| fetch(client, api_url, *category)),
+    )
+    .await;
 
     let mut res = IndexerResult::default();
     for category_list in category_responses {
@@ -114,6 +120,7 @@ mod tests {
             .await;
 
         let results = execute(
+            &Default::default(),
             server.url().as_str(),
             "http://tpb.org",
             &crate::MUSIC_CATEGORIES,
@@ -130,7 +137,7 @@ mod tests {
             results.entries[0].url,
             "http://tpb.org/description.php?id=67117416"
         );
-        assert_eq!(results.entries[0].magnet, "magnet:?xt=urn%3Abith%3A19370E3FD96FB1ADA86ED5892BE5B791A2A32254&dn=John.Wick.Chapter.4.2023.HDCAM.c1nem4.x264-SUNSCREEN%5BTGx%5D&tr=udp%3A%2F%2Ftracker.coppersurfer.tk%3A6969%2Fannounce&tr=udp%3A%2F%2Ftracker.openbittorrent.com%3A6969%2Fannounce&tr=udp%3A%2F%2F9.rarbg.to%3A2710%2Fannounce&tr=udp%3A%2F%2F9.rarbg.to%3A2780%2Fannounce&tr=udp%3A%2F%2F9.rarbg.to%3A2730%2Fannounce&tr=udp%3A%2F%2Ftracker.opentrackr.org%3A1337&tr=http%3A%2F%2Fp4p.arenabg.com%3A1337%2Fannounce&tr=udp%3A%2F%2Ftracker.torrent.eu.org%3A451%2Fannounce&tr=udp%3A%2F%2Ftracker.tiny-vps.com%3A6969%2Fannounce&tr=udp%3A%2F%2Fopen.stealth.si%3A80%2Fannounce");
+        assert_eq!(results.entries[0].magnet, "magnet:?xt=urn%3Abtih%3A19370E3FD96FB1ADA86ED5892BE5B791A2A32254&dn=John.Wick.Chapter.4.2023.HDCAM.c1nem4.x264-SUNSCREEN%5BTGx%5D&tr=udp%3A%2F%2Ftracker.coppersurfer.tk%3A6969%2Fannounce&tr=udp%3A%2F%2Ftracker.openbittorrent.com%3A6969%2Fannounce&tr=udp%3A%2F%2F9.rarbg.to%3A2710%2Fannounce&tr=udp%3A%2F%2F9.rarbg.to%3A2780%2Fannounce&tr=udp%3A%2F%2F9.rarbg.to%3A2730%2Fannounce&tr=udp%3A%2F%2Ftracker.opentrackr.org%3A1337&tr=http%3A%2F%2Fp4p.arenabg.com%3A1337%2Fannounce&tr=udp%3A%2F%2Ftracker.torrent.eu.org%3A451%2Fannounce&tr=udp%3A%2F%2Ftracker.tiny-vps.com%3A6969%2Fannounce&tr=udp%3A%2F%2Fopen.stealth.si%3A80%2Fannounce");
         assert_eq!(results.entries[0].seeders, 1068);
         assert_eq!(results.entries[0].leechers, 1074);
         assert_eq!(results.entries[0].size.to_string(), "1044.3 MB");
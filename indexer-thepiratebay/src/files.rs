@@ -0,0 +1,95 @@
+use manteau_indexer_prelude::http::HttpClient;
+use manteau_indexer_prelude::{IndexerEntry, IndexerError, IndexerErrorReason};
+use url::Url;
+
+// the api answers with this name when it doesn't know the files
+const NOT_FOUND: &str = "Filelist not found";
+
+#[derive(Debug, serde::Deserialize)]
+struct File {
+    name: Vec<String>,
+}
+
+/// Id of the torrent, from the description link of the entry.
+fn torrent_id(entry: &IndexerEntry) -> Option<String> {
+    let url = Url::parse(&entry.url).ok()?;
+    url.query_pairs()
+        .find(|(key, _)| key == "id")
+        .map(|(_, value)| value.into_owned())
+}
+
+async fn fetch(client: &HttpClient, api_url: &str, id: &str) -> Result<Vec<String>, IndexerError> {
+    let url = Url::parse_with_params(format!("{api_url}/f.php").as_str(), &[("id", id)]).map_err(
+        |cause| IndexerError::new(super::NAME, IndexerErrorReason::UnableToBuildUrl { cause }),
+    )?;
+    let files: Vec<File> = client.get_json(super::NAME, url).await?;
+    Ok(files
+        .into_iter()
+        .flat_map(|file| file.name)
+        .filter(|name| name != NOT_FOUND)
+        .collect())
+}
+
+/// Fills the file lists of the entries, the ones that couldn't be fetched staying empty.
+pub async fn complete(client: &HttpClient, api_url: &str, entries: &mut [IndexerEntry]) {
+    let lists = futures::future::join_all(entries.iter().map(|entry| async move {
+        let id = torrent_id(entry)?;
+        match fetch(client, api_url, &id).await {
+            Ok(files) => Some(files),
+            Err(error) => {
+                tracing::debug!("couldn't fetch the files of {id}: {error:?}");
+                None
+            }
+        }
+    }))
+    .await;
+    for (entry, files) in entries.iter_mut().zip(lists) {
+        entry.files = files.unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_complete_file_lists() {
+        let mut server = mockito::Server::new_async().await;
+
+        let found = server
+            .mock("GET", "/f.php?id=1")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[{"name":["Movie.2023.2160p.mkv"],"size":[1200]},{"name":["Setup.exe"],"size":[12]}]"#,
+            )
+            .create_async()
+            .await;
+        let missing = server
+            .mock("GET", "/f.php?id=2")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"name":["Filelist not found"],"size":[0]}]"#)
+            .create_async()
+            .await;
+
+        let entry = |id: &str| IndexerEntry {
+            size: bytesize::ByteSize::gb(2),
+            seeders: 1,
+            leechers: 1,
+            ..IndexerEntry::new(
+                super::super::NAME,
+                "Movie.2023.2160p",
+                format!("http://tpb.org/description.php?id={id}"),
+            )
+        };
+        let mut entries = vec![entry("1"), entry("2")];
+        complete(&Default::default(), server.url().as_str(), &mut entries).await;
+
+        assert_eq!(entries[0].files, vec!["Movie.2023.2160p.mkv", "Setup.exe"]);
+        assert!(entries[1].files.is_empty());
+
+        found.assert_async().await;
+        missing.assert_async().await;
+    }
+}
@@ -1,7 +1,10 @@
-use manteau_indexer_prelude::{Category, Indexer, IndexerBuilder, IndexerResult};
+use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode, SearchParam};
+use manteau_indexer_prelude::http::HttpClient;
+use manteau_indexer_prelude::{Category, Indexer, IndexerBuilder, IndexerResult, SearchRequest};
 
 mod common;
 mod feed;
+mod files;
 mod search;
 
 const MUSIC_CATEGORIES: [u16; 2] = [101, 104];
@@ -13,12 +16,25 @@ const API_URL: &str = "https://apibay.org";
 const BASE_URL: &str = "https://thepiratebay.org";
 pub const NAME: &str = "thepiratebay";
 
+/// Top level categories of the api, `0` standing for all of them.
+fn search_category(category: Option<Category>) -> u16 {
+    match category {
+        Some(Category::Audio | Category::Music) => 100,
+        Some(Category::Movie | Category::Tv) => 200,
+        Some(Category::Book) => 600,
+        None => 0,
+    }
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct IndexerThePirateBayConfig {
     #[serde(default = "IndexerThePirateBayConfig::default_api_url")]
     pub api_url: String,
     #[serde(default = "IndexerThePirateBayConfig::default_base_url")]
     pub base_url: String,
+    /// Fetch the file list of every entry, one more request each.
+    #[serde(default)]
+    pub file_list: bool,
 }
 
 impl IndexerThePirateBayConfig {
@@ -31,12 +47,14 @@ impl IndexerThePirateBayConfig {
 }
 
 impl IndexerBuilder for IndexerThePirateBayConfig {
-    fn build(self, name: String) -> Box<dyn Indexer + Send + Sync + 'static> {
+    fn build(self, name: String, client: HttpClient) -> Box<dyn Indexer + Send + Sync + 'static> {
         tracing::info!("building {NAME} indexer named {name:?}");
         Box::new(IndexerThePirateBay {
             name,
             api_url: self.api_url,
             base_url: self.base_url,
+            file_list: self.file_list,
+            client,
         })
     }
 }
@@ -46,6 +64,8 @@ pub struct IndexerThePirateBay {
     name: String,
     api_url: String,
     base_url: String,
+    file_list: bool,
+    client: HttpClient,
 }
 
 impl Default for IndexerThePirateBay {
@@ -60,15 +80,43 @@ impl IndexerThePirateBay {
             name: "ThePirateBay".into(),
             api_url: api_url.into(),
             base_url: base_url.into(),
+            file_list: false,
+            client: HttpClient::default(),
+        }
+    }
+
+    pub fn with_file_list(mut self, file_list: bool) -> Self {
+        self.file_list = file_list;
+        self
+    }
+
+    async fn complete(&self, mut result: IndexerResult) -> IndexerResult {
+        if self.file_list {
+            files::complete(&self.client, &self.api_url, &mut result.entries).await;
         }
+        result
     }
 }
 
 #[async_trait::async_trait]
 impl Indexer for IndexerThePirateBay {
-    async fn search(&self, query: &str) -> IndexerResult {
+    async fn search(&self, request: &SearchRequest) -> IndexerResult {
+        // the api finds the releases by imdb id
+        let query = match request.imdb_id {
+            Some(ref id) => id.clone(),
+            None => request.keywords(),
+        };
         tracing::debug!("{} searching {query:?}", self.name);
-        search::execute(&self.api_url, &self.base_url, query, 0).await
+        let category = search_category(request.category());
+        let result = search::execute(
+            &self.client,
+            &self.api_url,
+            &self.base_url,
+            &query,
+            category,
+        )
+        .await;
+        self.complete(result).await
     }
 
     async fn feed(&self, category: Category) -> IndexerResult {
@@ -79,6 +127,53 @@ impl Indexer for IndexerThePirateBay {
             Category::Tv => &TVSHOW_CATEGORIES,
             Category::Book => &BOOK_CATEGORIES,
         };
-        feed::execute(&self.api_url, &self.base_url, cats).await
+        let result = feed::execute(&self.client, &self.api_url, &self.base_url, cats).await;
+        self.complete(result).await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        use SearchParam::ImdbId;
+
+        Capabilities::default()
+            .with_keyword_searches()
+            .with_search(SearchMode::Search, &[ImdbId])
+            .with_search(SearchMode::TvSearch, &[ImdbId])
+            .with_search(SearchMode::MovieSearch, &[ImdbId])
+            .with_category("Music", Category::Music)
+            .with_category("Audio books", Category::Audio)
+            .with_category("Movies", Category::Movie)
+            .with_category("Movies DVDR", Category::Movie)
+            .with_category("HD Movies", Category::Movie)
+            .with_category("TV shows", Category::Tv)
+            .with_category("HD TV shows", Category::Tv)
+            .with_category("E-books", Category::Book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IndexerThePirateBay;
+    use manteau_indexer_prelude::{Category, Indexer, SearchRequest};
+
+    #[tokio::test]
+    async fn should_search_by_imdb_id_only() {
+        let mut server = mockito::Server::new_async().await;
+        let search = server
+            .mock("GET", "/q.php?q=tt0460649&cat=200")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("./search.json"))
+            .create_async()
+            .await;
+
+        let indexer = IndexerThePirateBay::new(server.url(), "http://tpb.org");
+        let request = SearchRequest {
+            imdb_id: Some("tt0460649".into()),
+            ..Default::default()
+        };
+        let result = indexer.search(&request.with_category(Category::Tv)).await;
+        assert!(!result.entries.is_empty());
+
+        search.assert_async().await;
     }
 }
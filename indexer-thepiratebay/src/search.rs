@@ -2,7 +2,9 @@ use bytesize::ByteSize;
 use chrono::format::ParseErrorKind;
 use chrono::{DateTime, Utc};
 
+use manteau_indexer_helper::magnet::info_hash;
 use manteau_indexer_helper::numeric::Number;
+use manteau_indexer_prelude::http::HttpClient;
 use manteau_indexer_prelude::{IndexerEntry, IndexerError, IndexerErrorReason, IndexerResult};
 use url::Url;
 
@@ -16,6 +18,8 @@ pub(crate) struct Entry {
     added: String,
     // num_files: String,
     size: String,
+    #[serde(default)]
+    username: Option<String>,
     // category: String,
     // imdb: Option<String>,
 }
@@ -82,20 +86,33 @@ impl Entry {
     }
 
     pub(crate) fn try_into(self, base_url: &str) -> Result<IndexerEntry, IndexerError> {
+        let magnet = crate::common::create_magnet(self.name.as_str(), self.info_hash.as_str())?;
         Ok(IndexerEntry {
             name: self.name.trim().to_string(),
             url: self.url(base_url),
-            date: self.date()?,
+            date: Some(self.date()?),
             size: self.size()?,
             seeders: self.seeders()?,
             leechers: self.leechers()?,
-            magnet: crate::common::create_magnet(self.name.as_str(), self.info_hash.as_str())?,
+            info_hash: info_hash(&magnet),
+            release: Default::default(),
+            magnet,
             origin: super::NAME,
+            uploader: self.username.filter(|name| !name.is_empty()),
+            deferred: None,
+            duplicates: Vec::new(),
+            files: Vec::new(),
+            suspicious: Vec::new(),
         })
     }
 }
 
-async fn fetch(base_url: &str, query: &str, category: u16) -> Result<Vec<Entry>, IndexerError> {
+async fn fetch(
+    client: &HttpClient,
+    base_url: &str,
+    query: &str,
+    category: u16,
+) -> Result<Vec<Entry>, IndexerError> {
     let url = Url::parse_with_params(
         format!("{base_url}/q.php").as_str(),
         &[("q", query), ("cat", category.to_string().as_str())],
@@ -103,30 +120,17 @@ async fn fetch(base_url: &str, query: &str, category: u16) -> Result<Vec<Entry>,
     .map_err(|cause| {
         IndexerError::new(super::NAME, IndexerErrorReason::UnableToBuildUrl { cause })
     })?;
-    let url_str = url.to_string();
-
-    let req = reqwest::get(url).await.map_err(|err| {
-        IndexerError::new(
-            super::NAME,
-            IndexerErrorReason::UnableToQuery {
-                url: url_str.clone(),
-                cause: err.to_string(),
-            },
-        )
-    })?;
-    req.json().await.map_err(|err| {
-        IndexerError::new(
-            super::NAME,
-            IndexerErrorReason::UnableToRead {
-                url: url_str,
-                cause: err.to_string(),
-            },
-        )
-    })
+    client.get_json(super::NAME, url).await
 }
 
-pub async fn execute(api_url: &str, base_url: &str, query: &str, category: u16) -> IndexerResult {
-    let entries = match fetch(api_url, query, category).await {
+pub async fn execute(
+    client: &HttpClient,
+    api_url: &str,
+    base_url: &str,
+    query: &str,
+    category: u16,
+) -> IndexerResult {
+    let entries = match fetch(client, api_url, query, category).await {
         Ok(value) => value,
         Err(error) => return IndexerResult::from(error),
     };
@@ -158,6 +162,7 @@ mod tests {
             .await;
 
         let results = execute(
+            &Default::default(),
             server.url().as_str(),
             "http://tpb.org",
             "how i met your mother",
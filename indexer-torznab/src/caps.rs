@@ -0,0 +1,146 @@
+use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode, SearchParam};
+use manteau_indexer_prelude::Category;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// Category of a newznab id, like `5040` for `TV/HD`.
+pub fn newznab_category(id: u32) -> Option<Category> {
+    match id / 1000 * 1000 {
+        2000 => Some(Category::Movie),
+        3000 if id == 3030 => Some(Category::Audio),
+        3000 => Some(Category::Music),
+        5000 => Some(Category::Tv),
+        7000 => Some(Category::Book),
+        _ => None,
+    }
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    let found = element.try_get_attribute(name).ok()??;
+    found.unescape_value().ok().map(|value| value.into_owned())
+}
+
+fn search_mode(name: &[u8]) -> Option<SearchMode> {
+    match name {
+        b"search" => Some(SearchMode::Search),
+        b"tv-search" => Some(SearchMode::TvSearch),
+        b"movie-search" => Some(SearchMode::MovieSearch),
+        // older name of the music search in newznab
+        b"music-search" | b"audio-search" => Some(SearchMode::MusicSearch),
+        b"book-search" => Some(SearchMode::BookSearch),
+        _ => None,
+    }
+}
+
+fn search_params(element: &BytesStart) -> Vec<SearchParam> {
+    let supported = attribute(element, "supportedParams").unwrap_or_else(|| "q".into());
+    supported
+        .split(',')
+        .filter_map(|name| {
+            SearchParam::ALL
+                .into_iter()
+                .find(|param| param.name() == name.trim())
+        })
+        .collect()
+}
+
+/// Reads the caps document of the upstream api, or the error it answered with.
+pub fn parse(xml: &str) -> Result<Capabilities, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    let mut caps = Capabilities::default();
+    let mut searching = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) | Ok(Event::Empty(element)) => {
+                match element.name().as_ref() {
+                    b"error" => {
+                        return Err(attribute(&element, "description")
+                            .unwrap_or_else(|| "unknown error".into()))
+                    }
+                    b"searching" => searching = true,
+                    b"category" | b"subcat" => {
+                        let id = attribute(&element, "id").unwrap_or_default();
+                        let category = id.parse::<u32>().ok().and_then(newznab_category);
+                        if let Some(category) = category {
+                            caps = caps.with_category(id, category);
+                        }
+                    }
+                    name if searching => {
+                        let available = attribute(&element, "available");
+                        if let (Some(mode), Some("yes")) = (search_mode(name), available.as_deref())
+                        {
+                            caps = caps.with_search(mode, &search_params(&element));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::End(element)) if element.name().as_ref() == b"searching" => searching = false,
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(error) => return Err(error.to_string()),
+        }
+    }
+    if caps.searching.is_empty() {
+        return Err("no search mode available".into());
+    }
+    // any torznab api takes an offset and a limit
+    Ok(caps.with_paging(true))
+}
+
+/// Description of the error document sent instead of the results.
+pub fn parse_error(xml: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) | Ok(Event::Empty(element))
+                if element.name().as_ref() == b"error" =>
+            {
+                return attribute(&element, "description");
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            Ok(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manteau_indexer_prelude::capabilities::{SearchMode, SearchParam};
+    use manteau_indexer_prelude::Category;
+
+    #[test]
+    fn should_parse_caps() {
+        let caps = super::parse(include_str!("./caps.xml")).unwrap();
+        assert_eq!(
+            caps.searching[&SearchMode::TvSearch]
+                .iter()
+                .map(SearchParam::name)
+                .collect::<Vec<_>>(),
+            vec!["q", "season", "ep", "imdbid", "tvdbid"]
+        );
+        assert!(caps.searching.contains_key(&SearchMode::MusicSearch));
+        assert!(!caps.searching.contains_key(&SearchMode::BookSearch));
+        assert_eq!(
+            caps.newznab_categories(),
+            vec![Category::Movie, Category::Music, Category::Tv]
+        );
+        let tv = caps
+            .categories
+            .iter()
+            .filter(|mapping| mapping.category == Category::Tv)
+            .map(|mapping| mapping.site.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(tv, vec!["5000", "5040"]);
+    }
+
+    #[test]
+    fn should_parse_errors() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<error code="100" description="Invalid API Key" />"#;
+        assert_eq!(super::parse(xml).unwrap_err(), "Invalid API Key");
+        assert_eq!(super::parse_error(xml).as_deref(), Some("Invalid API Key"));
+        assert_eq!(super::parse_error("<rss></rss>"), None);
+    }
+}
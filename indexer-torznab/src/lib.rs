@@ -0,0 +1,497 @@
+use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode, SearchParam};
+use manteau_indexer_prelude::http::HttpClient;
+use manteau_indexer_prelude::{
+    leak_origin, Category, Indexer, IndexerBuilder, IndexerError, IndexerErrorReason,
+    IndexerResult, SearchRequest,
+};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+mod caps;
+mod search;
+
+pub const NAME: &str = "torznab";
+
+/// Name of the `t` parameter for each search mode.
+fn function(mode: SearchMode) -> &'static str {
+    match mode {
+        SearchMode::Search => "search",
+        SearchMode::TvSearch => "tvsearch",
+        SearchMode::MovieSearch => "movie",
+        SearchMode::MusicSearch => "music",
+        SearchMode::BookSearch => "book",
+    }
+}
+
+/// Parameters of the search, turned into keywords when the upstream doesn't support them all.
+///
+/// Without keywords, that search would return the latest releases, so nothing is searched.
+fn search_params(
+    caps: &Capabilities,
+    request: &SearchRequest,
+) -> Option<Vec<(&'static str, String)>> {
+    let params = [
+        (SearchParam::Query, Some(request.query.trim().to_string())),
+        (
+            SearchParam::Season,
+            request.season.map(|value| value.to_string()),
+        ),
+        (SearchParam::Episode, request.episode.clone()),
+        (
+            SearchParam::ImdbId,
+            request
+                .imdb_id
+                .as_deref()
+                .map(|value| value.trim_start_matches("tt").to_string()),
+        ),
+        (
+            SearchParam::TvdbId,
+            request.tvdb_id.map(|value| value.to_string()),
+        ),
+        (SearchParam::Artist, request.artist.clone()),
+        (SearchParam::Album, request.album.clone()),
+        (SearchParam::Author, request.author.clone()),
+    ]
+    .into_iter()
+    .filter_map(|(param, value)| Some((param, value.filter(|value| !value.is_empty())?)))
+    .collect::<Vec<_>>();
+    let mode = request.mode();
+    let given = params.iter().map(|(param, _)| *param).collect::<Vec<_>>();
+    if !caps.supports(mode, &given) {
+        tracing::debug!("falling back to a keyword search instead of {mode:?}");
+        let keywords = request.keywords();
+        return (!keywords.is_empty()).then(|| vec![("t", "search".into()), ("q", keywords)]);
+    }
+    let params = std::iter::once(("t", function(mode).to_string()))
+        .chain(
+            params
+                .into_iter()
+                .map(|(param, value)| (param.name(), value)),
+        )
+        .collect();
+    Some(params)
+}
+
+/// Ids of the upstream categories, the standard ones when it didn't tell.
+fn site_categories(caps: &Capabilities, categories: &[Category]) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    for category in categories {
+        let mut sites = caps
+            .categories
+            .iter()
+            .filter(|mapping| mapping.category.kind() == category.kind())
+            .map(|mapping| mapping.site.clone())
+            .peekable();
+        if sites.peek().is_none() {
+            found.push(category.kind_str().to_string());
+        }
+        for site in sites {
+            if !found.contains(&site) {
+                found.push(site);
+            }
+        }
+    }
+    found
+}
+
+/// Hides the api key from a text ending up in the logs, like an url or an error message.
+fn redact_text(apikey: Option<&str>, text: &str) -> String {
+    let Some(apikey) = apikey.filter(|apikey| !apikey.is_empty()) else {
+        return text.to_string();
+    };
+    let encoded = url::form_urlencoded::byte_serialize(apikey.as_bytes()).collect::<String>();
+    text.replace(apikey, "REDACTED")
+        .replace(&encoded, "REDACTED")
+}
+
+fn redact(apikey: Option<&str>, mut error: IndexerError) -> IndexerError {
+    if let IndexerErrorReason::UnableToQuery { url, cause }
+    | IndexerErrorReason::UnableToRead { url, cause } = &mut error.reason
+    {
+        *url = redact_text(apikey, url);
+        *cause = redact_text(apikey, cause);
+    }
+    error
+}
+
+async fn fetch_caps(
+    client: &HttpClient,
+    origin: &'static str,
+    url: url::Url,
+    apikey: Option<&str>,
+) -> Result<Capabilities, IndexerError> {
+    let url_str = url.to_string();
+    let xml = client
+        .get_text(origin, url)
+        .await
+        .map_err(|error| redact(apikey, error))?;
+    caps::parse(&xml).map_err(|cause| {
+        IndexerError::new(
+            origin,
+            IndexerErrorReason::UnableToRead {
+                url: redact_text(apikey, &url_str),
+                cause,
+            },
+        )
+    })
+}
+
+#[derive(serde::Deserialize)]
+pub struct IndexerTorznabConfig {
+    /// Endpoint of the api, like `https://jackett.local/api/v2.0/indexers/all/results/torznab/api`.
+    pub url: String,
+    #[serde(default)]
+    pub apikey: Option<String>,
+}
+
+// without the api key
+impl std::fmt::Debug for IndexerTorznabConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IndexerTorznabConfig")
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl IndexerBuilder for IndexerTorznabConfig {
+    fn build(self, name: String, client: HttpClient) -> Box<dyn Indexer + Send + Sync + 'static> {
+        tracing::info!("building torznab indexer named {name:?}");
+        let mut indexer = IndexerTorznab::new(self.url).with_apikey(self.apikey);
+        indexer.origin = leak_origin(&name);
+        indexer.name = name;
+        indexer.client = client;
+        indexer.prefetch_caps();
+        Box::new(indexer)
+    }
+}
+
+/// Indexer relaying the searches to another torznab or newznab api.
+pub struct IndexerTorznab {
+    name: String,
+    origin: &'static str,
+    url: String,
+    apikey: Option<String>,
+    client: HttpClient,
+    // fetched once, when first needed
+    caps: Arc<OnceCell<Capabilities>>,
+}
+
+// without the api key
+impl std::fmt::Debug for IndexerTorznab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IndexerTorznab")
+            .field("name", &self.name)
+            .field("url", &self.url)
+            .field("caps", &self.caps)
+            .finish_non_exhaustive()
+    }
+}
+
+impl IndexerTorznab {
+    pub fn new<S: Into<String>>(url: S) -> Self {
+        Self {
+            name: NAME.into(),
+            origin: NAME,
+            url: url.into(),
+            apikey: None,
+            client: HttpClient::default(),
+            caps: Arc::new(OnceCell::new()),
+        }
+    }
+
+    pub fn with_apikey(mut self, apikey: Option<String>) -> Self {
+        self.apikey = apikey;
+        self
+    }
+
+    fn url(&self, params: &[(&str, String)]) -> Result<url::Url, IndexerError> {
+        let mut url = url::Url::parse(&self.url).map_err(|cause| {
+            IndexerError::new(self.origin, IndexerErrorReason::UnableToBuildUrl { cause })
+        })?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.extend_pairs(params);
+            if let Some(ref apikey) = self.apikey {
+                query.append_pair("apikey", apikey);
+            }
+        }
+        Ok(url)
+    }
+
+    /// Fetches the caps in background, so that they're known when the clients ask for ours.
+    fn prefetch_caps(&self) {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let Ok(url) = self.url(&[("t", "caps".into())]) else {
+            return;
+        };
+        let caps = self.caps.clone();
+        let client = self.client.clone();
+        let origin = self.origin;
+        let apikey = self.apikey.clone();
+        handle.spawn(async move {
+            if let Err(error) = caps
+                .get_or_try_init(|| fetch_caps(&client, origin, url, apikey.as_deref()))
+                .await
+            {
+                tracing::warn!("unable to fetch the caps of {origin}: {error}");
+            }
+        });
+    }
+
+    async fn caps(&self) -> Result<&Capabilities, IndexerError> {
+        let url = self.url(&[("t", "caps".into())])?;
+        self.caps
+            .get_or_try_init(|| fetch_caps(&self.client, self.origin, url, self.apikey.as_deref()))
+            .await
+    }
+
+    async fn query(&self, mut params: Vec<(&'static str, String)>) -> IndexerResult {
+        // asks for every torznab attribute
+        params.push(("extended", "1".into()));
+        let url = match self.url(&params) {
+            Ok(value) => value,
+            Err(error) => return IndexerResult::from(error),
+        };
+        let apikey = self.apikey.as_deref();
+        let url_str = redact_text(apikey, url.as_str());
+        match self.client.get_text(self.origin, url).await {
+            Ok(xml) => search::parse(self.origin, &url_str, &xml),
+            Err(error) => IndexerResult::from(redact(apikey, error)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Indexer for IndexerTorznab {
+    async fn search(&self, request: &SearchRequest) -> IndexerResult {
+        tracing::debug!("{} searching {:?}", self.name, request.query);
+        let caps = match self.caps().await {
+            Ok(value) => value,
+            Err(error) => return IndexerResult::from(error),
+        };
+        let Some(mut params) = search_params(caps, request) else {
+            return IndexerResult::default();
+        };
+        let categories = site_categories(caps, &request.categories);
+        if !categories.is_empty() {
+            params.push(("cat", categories.join(",")));
+        }
+        if request.offset > 0 {
+            params.push(("offset", request.offset.to_string()));
+        }
+        if let Some(limit) = request.limit {
+            params.push(("limit", limit.to_string()));
+        }
+        self.query(params).await
+    }
+
+    async fn feed(&self, category: Category) -> IndexerResult {
+        tracing::debug!("{} fetching feed for {category:?}", self.name);
+        let caps = match self.caps().await {
+            Ok(value) => value,
+            Err(error) => return IndexerResult::from(error),
+        };
+        let categories = site_categories(caps, &[category]);
+        self.query(vec![("t", "search".into()), ("cat", categories.join(","))])
+            .await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        if let Some(caps) = self.caps.get() {
+            return caps.clone();
+        }
+        // not fetched yet, only the keyword searches are sure to work
+        [
+            Category::Movie,
+            Category::Audio,
+            Category::Tv,
+            Category::Book,
+        ]
+        .into_iter()
+        .fold(
+            Capabilities::default()
+                .with_search(SearchMode::Search, &[SearchParam::Query])
+                .with_paging(true),
+            |caps, category| caps.with_category(category.kind_str(), category),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexerTorznab, IndexerTorznabConfig};
+    use manteau_indexer_prelude::capabilities::SearchMode;
+    use manteau_indexer_prelude::{Category, Indexer, IndexerBuilder, SearchRequest};
+
+    async fn caps_mock(server: &mut mockito::Server) -> mockito::Mock {
+        server
+            .mock("GET", "/api?t=caps&apikey=secret")
+            .with_status(200)
+            .with_header("content-type", "application/xml")
+            .with_body(include_str!("./caps.xml"))
+            .expect(1)
+            .create_async()
+            .await
+    }
+
+    #[tokio::test]
+    async fn should_search_upstream() {
+        let mut server = mockito::Server::new_async().await;
+        let indexer =
+            IndexerTorznab::new(format!("{}/api", server.url())).with_apikey(Some("secret".into()));
+
+        let caps = caps_mock(&mut server).await;
+        let tv_search = server
+            .mock(
+                "GET",
+                "/api?t=tvsearch&q=The+Last+of+Us&season=1&ep=1&cat=5000%2C5040&extended=1&apikey=secret",
+            )
+            .with_status(200)
+            .with_header("content-type", "application/rss+xml")
+            .with_body(include_str!("./search.xml"))
+            .create_async()
+            .await;
+        // the upstream doesn't support the author in book searches
+        let keyword_search = server
+            .mock(
+                "GET",
+                "/api?t=search&q=Dune+Frank+Herbert&offset=100&limit=50&extended=1&apikey=secret",
+            )
+            .with_status(200)
+            .with_body(include_str!("./search.xml"))
+            .create_async()
+            .await;
+
+        let request = SearchRequest {
+            query: "The Last of Us".into(),
+            season: Some(1),
+            episode: Some("1".into()),
+            ..Default::default()
+        }
+        .with_category(Category::Tv);
+        let result = indexer.search(&request).await;
+        assert_eq!(result.entries.len(), 2);
+        assert_eq!(result.entries[0].origin, "torznab");
+
+        let request = SearchRequest {
+            query: "Dune".into(),
+            author: Some("Frank Herbert".into()),
+            offset: 100,
+            limit: Some(50),
+            ..Default::default()
+        };
+        let result = indexer.search(&request).await;
+        assert_eq!(result.entries.len(), 2);
+
+        assert!(indexer
+            .capabilities()
+            .searching
+            .contains_key(&SearchMode::TvSearch));
+        assert!(indexer.capabilities().paging);
+
+        caps.assert_async().await;
+        tv_search.assert_async().await;
+        keyword_search.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_fetch_feed_and_prefetch_caps() {
+        let mut server = mockito::Server::new_async().await;
+        let config: IndexerTorznabConfig = serde_json::from_value(serde_json::json!({
+            "url": format!("{}/api", server.url()),
+            "apikey": "secret",
+        }))
+        .unwrap();
+
+        let caps = caps_mock(&mut server).await;
+        let feed = server
+            .mock(
+                "GET",
+                "/api?t=search&cat=2000%2C2040&extended=1&apikey=secret",
+            )
+            .with_status(200)
+            .with_body(include_str!("./search.xml"))
+            .create_async()
+            .await;
+
+        let indexer = config.build("upstream".into(), Default::default());
+        let result = indexer.feed(Category::Movie).await;
+        assert_eq!(result.entries.len(), 2);
+        assert_eq!(result.entries[0].origin, "upstream");
+
+        caps.assert_async().await;
+        feed.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_upstream_rejects() {
+        let mut server = mockito::Server::new_async().await;
+        let indexer = IndexerTorznab::new(format!("{}/api", server.url()));
+        let caps = server
+            .mock("GET", "/api?t=caps")
+            .with_status(200)
+            .with_body(r#"<error code="100" description="Invalid API Key"/>"#)
+            .create_async()
+            .await;
+
+        let result = indexer.search(&SearchRequest::new("dune")).await;
+        assert!(result.is_failure());
+        assert!(!indexer
+            .capabilities()
+            .searching
+            .contains_key(&SearchMode::TvSearch));
+
+        caps.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_not_search_without_keywords() {
+        let mut server = mockito::Server::new_async().await;
+        let indexer = IndexerTorznab::new(format!("{}/api", server.url()));
+        let caps = server
+            .mock("GET", "/api?t=caps")
+            .with_status(200)
+            .with_body(r#"<caps><searching><search available="yes"/></searching></caps>"#)
+            .create_async()
+            .await;
+
+        let request = SearchRequest {
+            imdb_id: Some("tt0133093".into()),
+            ..Default::default()
+        };
+        let result = indexer
+            .search(&request.with_category(Category::Movie))
+            .await;
+        assert!(result.entries.is_empty());
+        assert!(result.errors.is_empty());
+
+        caps.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_hide_apikey() {
+        let mut server = mockito::Server::new_async().await;
+        let indexer =
+            IndexerTorznab::new(format!("{}/api", server.url())).with_apikey(Some("secret".into()));
+        let caps = server
+            .mock("GET", "/api?t=caps&apikey=secret")
+            .with_status(200)
+            .with_body(r#"<error code="100" description="Invalid API Key"/>"#)
+            .create_async()
+            .await;
+        let result = indexer.search(&SearchRequest::new("dune")).await;
+        assert!(result.is_failure());
+        assert!(!format!("{:?}", result.errors).contains("secret"));
+        assert!(!format!("{indexer:?}").contains("secret"));
+        caps.assert_async().await;
+
+        // the error of the http client tells the url too
+        let indexer =
+            IndexerTorznab::new("http://127.0.0.1:1/api").with_apikey(Some("secret".into()));
+        let result = indexer.search(&SearchRequest::new("dune")).await;
+        assert!(result.is_failure());
+        assert!(!format!("{:?}", result.errors).contains("secret"));
+    }
+}
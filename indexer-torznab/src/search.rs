@@ -0,0 +1,190 @@
+use bytesize::ByteSize;
+use chrono::{DateTime, Utc};
+use manteau_indexer_helper::magnet::info_hash;
+use manteau_indexer_helper::numeric::{Number, ParseNumberError};
+use manteau_indexer_prelude::{IndexerEntry, IndexerError, IndexerErrorReason, IndexerResult};
+use std::collections::HashMap;
+
+/// Values of the `torznab:attr` or `newznab:attr` elements of an item.
+fn attributes(item: &rss::Item) -> HashMap<String, String> {
+    ["torznab", "newznab"]
+        .into_iter()
+        .filter_map(|prefix| item.extensions().get(prefix)?.get("attr"))
+        .flatten()
+        .filter_map(|extension| {
+            let name = extension.attrs().get("name")?;
+            let value = extension.attrs().get("value")?;
+            Some((name.clone(), value.clone()))
+        })
+        .collect()
+}
+
+fn parse_count(value: Option<&String>) -> Result<usize, ParseNumberError> {
+    match value {
+        Some(value) => value.trim().parse::<Number>().map(|num| num.as_value()),
+        None => Ok(0),
+    }
+}
+
+fn parse_item(origin: &'static str, item: &rss::Item) -> Result<IndexerEntry, IndexerError> {
+    let attrs = attributes(item);
+    let name = item
+        .title()
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .ok_or_else(|| IndexerError::new(origin, IndexerErrorReason::EntryNameNotFound))?
+        .to_string();
+    let enclosure = item.enclosure();
+    let magnet = attrs
+        .get("magneturl")
+        .map(String::as_str)
+        .or(item.link())
+        .or(enclosure.map(|enclosure| enclosure.url()))
+        .ok_or_else(|| IndexerError::new(origin, IndexerErrorReason::EntryLinkNotFound))?
+        .to_string();
+    let url = item
+        .comments()
+        .or(item
+            .guid()
+            .filter(|guid| guid.is_permalink() && guid.value().starts_with("http"))
+            .map(|guid| guid.value()))
+        .unwrap_or(magnet.as_str())
+        .to_string();
+    let size = attrs
+        .get("size")
+        .map(String::as_str)
+        .or(enclosure.map(|enclosure| enclosure.length()))
+        .map(|value| value.trim().parse::<u64>())
+        .transpose()
+        .map_err(|cause| {
+            IndexerError::new(
+                origin,
+                IndexerErrorReason::EntrySizeInvalid {
+                    cause: cause.to_string(),
+                },
+            )
+        })?
+        .map(ByteSize::b)
+        .unwrap_or_default();
+    let seeders = parse_count(attrs.get("seeders")).map_err(|cause| {
+        IndexerError::new(origin, IndexerErrorReason::EntrySeedersInvalid { cause })
+    })?;
+    let leechers = match attrs.get("leechers") {
+        Some(value) => parse_count(Some(value)),
+        // the peers count the seeders too
+        None => parse_count(attrs.get("peers")).map(|peers| peers.saturating_sub(seeders)),
+    }
+    .map_err(|cause| {
+        IndexerError::new(origin, IndexerErrorReason::EntryLeechersInvalid { cause })
+    })?;
+    let date = match item.pub_date() {
+        Some(value) => Some(
+            DateTime::parse_from_rfc2822(value.trim())
+                .map(|date| date.with_timezone(&Utc))
+                .map_err(|err| {
+                    IndexerError::new(
+                        origin,
+                        IndexerErrorReason::EntryDateInvalid { cause: err.kind() },
+                    )
+                })?,
+        ),
+        None => None,
+    };
+    let info_hash = attrs
+        .get("infohash")
+        .filter(|hash| hash.len() == 40)
+        .map(|hash| hash.to_uppercase())
+        .or_else(|| info_hash(&magnet));
+
+    Ok(IndexerEntry {
+        name,
+        url,
+        date,
+        size,
+        seeders,
+        leechers,
+        magnet,
+        origin,
+        uploader: attrs.get("poster").cloned(),
+        info_hash,
+        release: Default::default(),
+        deferred: None,
+        duplicates: Vec::new(),
+        files: Vec::new(),
+        suspicious: Vec::new(),
+    })
+}
+
+/// Reads the items of a torznab answer, or the error sent instead.
+pub fn parse(origin: &'static str, url: &str, xml: &str) -> IndexerResult {
+    let channel = match rss::Channel::read_from(xml.as_bytes()) {
+        Ok(value) => value,
+        Err(error) => {
+            let cause = crate::caps::parse_error(xml).unwrap_or_else(|| error.to_string());
+            return IndexerResult::from(IndexerError::new(
+                origin,
+                IndexerErrorReason::UnableToRead {
+                    url: url.to_string(),
+                    cause,
+                },
+            ));
+        }
+    };
+    let mut results = IndexerResult::default();
+    for item in channel.items() {
+        match parse_item(origin, item) {
+            Ok(found) => results.entries.push(found),
+            Err(error) => results.errors.push(error),
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use manteau_indexer_prelude::IndexerErrorReason;
+
+    #[test]
+    fn should_parse_items() {
+        let result = super::parse("upstream", "http://upstream", include_str!("./search.xml"));
+        assert_eq!(result.entries.len(), 2);
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(
+            result.errors[0].reason,
+            IndexerErrorReason::EntryNameNotFound
+        ));
+
+        let first = &result.entries[0];
+        assert_eq!(first.name, "The Last of Us S01E01 1080p WEB h264");
+        assert_eq!(first.url, "https://tracker.example/details/1");
+        assert!(first.magnet.starts_with("magnet:?xt=urn:btih:"));
+        assert_eq!(first.info_hash, Some("A".repeat(40)));
+        assert_eq!(first.size.as_u64(), 2_147_483_648);
+        assert_eq!((first.seeders, first.leechers), (120, 30));
+        assert_eq!(
+            first.date.unwrap().to_rfc3339(),
+            "2023-01-16T03:05:00+00:00"
+        );
+        assert_eq!(first.uploader.as_deref(), Some("group"));
+
+        let second = &result.entries[1];
+        assert_eq!(second.magnet, "https://tracker.example/download/2.torrent");
+        assert_eq!(second.info_hash, Some("B".repeat(40)));
+        assert_eq!(second.size.as_u64(), 1_073_741_824);
+        assert_eq!((second.seeders, second.leechers), (8, 2));
+    }
+
+    #[test]
+    fn should_report_upstream_errors() {
+        let result = super::parse(
+            "upstream",
+            "http://upstream",
+            r#"<?xml version="1.0" encoding="UTF-8"?><error code="100" description="Invalid API Key"/>"#,
+        );
+        assert!(result.entries.is_empty());
+        assert!(matches!(
+            result.errors[0].reason,
+            IndexerErrorReason::UnableToRead { ref cause, .. } if cause == "Invalid API Key"
+        ));
+    }
+}
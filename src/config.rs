@@ -5,9 +5,23 @@ use std::path::PathBuf;
 pub struct Config {
     pub indexers: IndexerManagerConfig,
     #[serde(default)]
+    pub ranking: manteau_indexer_manager::RankingConfig,
+    #[serde(default)]
+    pub filters: manteau_indexer_manager::FiltersConfig,
+    #[serde(default)]
+    pub heuristics: manteau_indexer_manager::HeuristicsConfig,
+    #[serde(default)]
+    pub http: manteau_indexer_prelude::http::HttpConfig,
+    #[serde(default)]
     pub torznab: crate::service::torznab::TorznabConfig,
     #[serde(default)]
     pub cache: crate::service::cache::CacheConfig,
+    #[serde(default)]
+    pub history: Option<crate::service::history::HistoryConfig>,
+    #[serde(default)]
+    pub access: crate::service::access::AccessConfig,
+    #[serde(default)]
+    pub scheduler: crate::service::scheduler::SchedulerConfig,
 }
 
 impl Config {
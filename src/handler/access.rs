@@ -0,0 +1,158 @@
+use crate::service::access::{Access, Rejection};
+use axum::extract::ConnectInfo;
+use axum::http::{header, HeaderValue, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+fn api_key<B>(request: &Request<B>) -> Option<String> {
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("apikey="))
+        .filter(|value| !value.is_empty())
+        .and_then(|value| urlencoding::decode(value).ok())
+        .map(|value| value.into_owned())
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        let (status, code, description, retry_in) = match self {
+            Self::NotAllowed => (StatusCode::FORBIDDEN, 100, "Client not allowed", None),
+            Self::RateLimited { retry_in } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                500,
+                "Request limit reached",
+                Some(retry_in),
+            ),
+        };
+        let mut response = (
+            status,
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/xml; charset=utf-8"),
+            )],
+            crate::service::torznab::error(code, description),
+        )
+            .into_response();
+        if let Some(retry_in) = retry_in {
+            // rounded up so that the client doesn't come back too early
+            let seconds = retry_in.as_secs() + u64::from(retry_in.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+/// Rejects the clients that are not allowed or that exceeded their rate limit.
+pub async fn middleware<B>(
+    Extension(access): Extension<Arc<Access>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let forwarded_for = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok());
+    let address = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(peer)| access.client_address(peer.ip(), forwarded_for));
+    let api_key = api_key(&request);
+
+    if let Err(rejection) = access.check(address, api_key.as_deref()) {
+        tracing::debug!("rejecting request from {address:?}: {rejection:?}");
+        return rejection.into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::access::AccessConfig;
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{header, Request, StatusCode};
+    use manteau_indexer_manager::IndexerManager;
+    use manteau_indexer_prelude::ratelimit::RateLimitConfig;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn app(access: AccessConfig) -> axum::Router {
+        crate::router(
+            crate::service::cache::build(),
+            Default::default(),
+            Arc::new(IndexerManager::default()),
+            Arc::new(Default::default()),
+            Arc::new(access.build()),
+        )
+    }
+
+    async fn get(
+        app: &axum::Router,
+        peer: &str,
+        forwarded_for: Option<&str>,
+    ) -> axum::response::Response {
+        let mut request = Request::builder().uri("/api/torznab?t=caps&apikey=");
+        if let Some(value) = forwarded_for {
+            request = request.header("x-forwarded-for", value);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_reject_clients_out_of_allowlist() {
+        let app = app(AccessConfig {
+            allow: vec!["192.168.0.0/16".parse().unwrap()],
+            trusted_proxies: vec!["10.0.0.1/32".parse().unwrap()],
+            ..Default::default()
+        });
+
+        let response = get(&app, "192.168.1.1:1234", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get(&app, "10.0.0.1:1234", Some("192.168.1.2")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get(&app, "10.0.0.1:1234", Some("8.8.8.8")).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains(r#"<error code="100""#));
+    }
+
+    #[tokio::test]
+    async fn should_rate_limit_clients() {
+        let app = app(AccessConfig {
+            rate_limit: Some(RateLimitConfig {
+                per_minute: Some(2),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        for _ in 0..2 {
+            let response = get(&app, "1.1.1.1:1234", None).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = get(&app, "1.1.1.1:1234", None).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "30");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains(r#"<error code="500""#));
+
+        let response = get(&app, "2.2.2.2:1234", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -1 +1,3 @@
+pub mod search;
+pub mod status;
 pub mod torznab;
@@ -0,0 +1,214 @@
+use super::torznab::QueryParams;
+use crate::service::history::History;
+use crate::service::torznab::TorznabBuilder;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use futures::StreamExt;
+use manteau_indexer_manager::{IndexerManager, SearchQuery};
+use manteau_indexer_prelude::{IndexerEntry, SearchRequest};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+#[derive(Debug, serde::Serialize)]
+struct Item<'a> {
+    name: &'a str,
+    link: String,
+    url: &'a str,
+    origin: &'static str,
+    size: u64,
+    seeders: usize,
+    leechers: usize,
+    date: Option<String>,
+    suspicious: &'a [&'static str],
+}
+
+impl<'a> Item<'a> {
+    fn new(torznab: &TorznabBuilder, entry: &'a IndexerEntry) -> Self {
+        Self {
+            name: &entry.name,
+            link: torznab.link(entry).into_owned(),
+            url: &entry.url,
+            origin: entry.origin,
+            size: entry.size.as_u64(),
+            seeders: entry.seeders,
+            leechers: entry.leechers,
+            date: entry.date.map(|date| date.to_rfc3339()),
+            suspicious: &entry.suspicious,
+        }
+    }
+}
+
+/// Entries of an indexer that just answered.
+#[derive(Debug, serde::Serialize)]
+struct Batch<'a> {
+    indexer: &'a str,
+    entries: Vec<Item<'a>>,
+    errors: usize,
+}
+
+/// Last event, once every indexer answered.
+#[derive(Debug, serde::Serialize)]
+struct Summary {
+    total: usize,
+    failed: Vec<String>,
+}
+
+/// Sends the events of the search, until the client goes away.
+async fn produce(
+    history: History,
+    indexer: Arc<IndexerManager>,
+    torznab: Arc<TorznabBuilder>,
+    request: SearchRequest,
+    sender: mpsc::Sender<Event>,
+) {
+    let parsed = SearchQuery::parse(&request.query);
+    let request = SearchRequest {
+        query: parsed.upstream(),
+        ..request
+    };
+    let mut summary = Summary {
+        total: 0,
+        failed: Vec::new(),
+    };
+    let mut batches = Box::pin(indexer.search_stream(&request, &parsed));
+    while let Some(batch) = batches.next().await {
+        history.record(&batch.result.entries).await;
+        if batch.result.is_failure() {
+            tracing::debug!("{} failed: {:?}", batch.indexer, batch.result.errors);
+            summary.failed.push(batch.indexer.clone());
+        }
+        summary.total += batch.result.entries.len();
+        let event = Event::default().event("batch").json_data(Batch {
+            indexer: &batch.indexer,
+            entries: batch
+                .result
+                .entries
+                .iter()
+                .map(|entry| Item::new(&torznab, entry))
+                .collect(),
+            errors: batch.result.errors.len(),
+        });
+        match event {
+            Ok(event) => {
+                if sender.send(event).await.is_err() {
+                    tracing::debug!("client left before the end of the search");
+                    return;
+                }
+            }
+            Err(error) => tracing::warn!("unable to serialize batch: {error:?}"),
+        }
+    }
+    match Event::default().event("done").json_data(&summary) {
+        Ok(event) => {
+            let _ = sender.send(event).await;
+        }
+        Err(error) => tracing::warn!("unable to serialize summary: {error:?}"),
+    }
+}
+
+/// Pushes the entries of each indexer as soon as it answers, as server sent events.
+pub async fn handler(
+    Extension(history): Extension<History>,
+    Extension(indexer): Extension<Arc<IndexerManager>>,
+    Extension(torznab): Extension<Arc<TorznabBuilder>>,
+    Query(params): Query<QueryParams>,
+) -> Response {
+    tracing::debug!("GET /api/search params={params:?}");
+    let Some(request) = params.request() else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let (sender, receiver) = mpsc::channel(8);
+    tokio::spawn(produce(history, indexer, torznab, request, sender));
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        Some((Ok::<_, Infallible>(event), receiver))
+    });
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use manteau_indexer_manager::IndexerManager;
+    use manteau_indexer_prelude::{bytesize, Category, IndexerEntry, IndexerResult, SearchRequest};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[derive(Debug, Default)]
+    struct MockIndexer;
+
+    #[async_trait::async_trait]
+    impl manteau_indexer_prelude::Indexer for MockIndexer {
+        async fn search(&self, request: &SearchRequest) -> IndexerResult {
+            IndexerResult::from(vec![IndexerEntry {
+                size: bytesize::ByteSize::gb(2),
+                seeders: 10,
+                leechers: 20,
+                magnet: "magnet-url".into(),
+                ..IndexerEntry::new(
+                    "fake",
+                    format!("{}.2021.1080p", request.query),
+                    "https://example.com/dune",
+                )
+            }])
+        }
+
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            IndexerResult::default()
+        }
+    }
+
+    fn app() -> axum::Router {
+        crate::router(
+            crate::service::cache::build(),
+            Default::default(),
+            Arc::new(IndexerManager::with_indexer(MockIndexer)),
+            Arc::new(Default::default()),
+            Default::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn should_stream_search_events() {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/search?t=search&cat=2000&q=Dune")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        let batch = body.find("event:batch").unwrap();
+        let done = body.find("event:done").unwrap();
+        assert!(batch < done);
+        assert!(body.contains(r#""indexer":"default""#));
+        assert!(body.contains(r#""name":"Dune.2021.1080p""#));
+        assert!(body.contains(r#"{"total":1,"failed":[]}"#));
+    }
+
+    #[tokio::test]
+    async fn should_reject_searches_without_text() {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/search?t=search&cat=2000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,9 @@
+use axum::{Extension, Json};
+use manteau_indexer_manager::{IndexerManager, IndexerStatus};
+use std::sync::Arc;
+
+pub async fn handler(
+    Extension(indexer): Extension<Arc<IndexerManager>>,
+) -> Json<Vec<IndexerStatus>> {
+    Json(indexer.status())
+}
@@ -1,10 +1,12 @@
+use crate::service::cache::{Cache, CacheLookup, RefreshGuard};
+use crate::service::history::History;
 use crate::service::torznab::TorznabBuilder;
-use axum::extract::Query;
-use axum::response::IntoResponse;
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
 use axum::Extension;
-use manteau_indexer_manager::IndexerManager;
-use manteau_indexer_prelude::Category;
-use moka::future::Cache;
+use manteau_indexer_manager::{IndexerManager, SearchQuery};
+use manteau_indexer_prelude::{Category, IndexerResult, SearchRequest};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -54,142 +56,542 @@ impl IntoResponse for ApplicationRssXml {
     }
 }
 
+/// Parameters of the searches, all optional and given as text.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct SearchParams {
+    #[serde(default)]
+    q: Option<String>,
+    #[serde(default)]
+    imdbid: Option<String>,
+    #[serde(default)]
+    tvdbid: Option<String>,
+    #[serde(default)]
+    season: Option<String>,
+    #[serde(default)]
+    ep: Option<String>,
+    #[serde(default)]
+    artist: Option<String>,
+    #[serde(default)]
+    album: Option<String>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    offset: Option<String>,
+    #[serde(default)]
+    limit: Option<String>,
+}
+
+fn text(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+}
+
+fn number<T: FromStr>(value: &Option<String>) -> Option<T> {
+    value.as_deref()?.trim().parse().ok()
+}
+
+impl SearchParams {
+    /// What to search, `None` when no text is given and the latest entries are expected.
+    fn request(&self, category: Category) -> Option<SearchRequest> {
+        let request = SearchRequest {
+            query: text(&self.q).unwrap_or_default(),
+            categories: vec![category],
+            imdb_id: text(&self.imdbid).map(|id| match id.starts_with("tt") {
+                true => id,
+                false => format!("tt{id}"),
+            }),
+            tvdb_id: number(&self.tvdbid),
+            season: number(&self.season),
+            episode: text(&self.ep),
+            artist: text(&self.artist),
+            album: text(&self.album),
+            author: text(&self.author),
+            offset: number(&self.offset).unwrap_or_default(),
+            limit: number(&self.limit),
+        };
+        let asked = !request.query.is_empty()
+            || request.imdb_id.is_some()
+            || request.tvdb_id.is_some()
+            || request.season.is_some()
+            || request.episode.is_some()
+            || request.artist.is_some()
+            || request.album.is_some()
+            || request.author.is_some();
+        asked.then_some(request)
+    }
+}
+
 #[derive(Debug, serde::Deserialize)]
 #[serde(tag = "t", rename_all = "lowercase")]
 pub enum QueryParams {
     Caps,
-    Music,
     Search {
         #[serde(deserialize_with = "deserialize_category")]
         cat: Category,
-        #[serde(default = "String::new")]
-        q: String,
+        #[serde(flatten)]
+        params: SearchParams,
     },
     #[serde(rename = "tvsearch")]
     TvSearch {
         #[serde(deserialize_with = "deserialize_category")]
         cat: Category,
-        q: Option<String>,
-        #[serde(default)]
-        season: Option<String>,
-        #[serde(default)]
-        ep: Option<String>,
+        #[serde(flatten)]
+        params: SearchParams,
     },
     Movie {
         #[serde(deserialize_with = "deserialize_category")]
         cat: Category,
-        #[serde(default)]
-        q: Option<String>,
+        #[serde(flatten)]
+        params: SearchParams,
+    },
+    Music {
+        #[serde(flatten)]
+        params: SearchParams,
+    },
+    Book {
+        #[serde(flatten)]
+        params: SearchParams,
     },
 }
 
+/// Body to send back, flagged as failed when none of the indexers could answer.
+pub struct Rendered {
+    body: String,
+    failed: bool,
+}
+
+impl Rendered {
+    fn new(torznab: &TorznabBuilder, category: Category, result: IndexerResult) -> Self {
+        if !result.errors.is_empty() {
+            tracing::debug!("had the following errors: {:?}", result.errors);
+        }
+        Self {
+            body: torznab.feed(category, &result.entries),
+            failed: result.is_failure(),
+        }
+    }
+}
+
 impl QueryParams {
-    async fn handle(self, indexer: Arc<IndexerManager>, torznab: Arc<TorznabBuilder>) -> String {
+    fn category(&self) -> Option<Category> {
         match self {
-            Self::Caps => torznab.capabilities(),
-            Self::Music => handle_feed(indexer, torznab, Category::Music).await,
-            Self::Search { cat, q } => {
-                if q.is_empty() {
-                    handle_feed(indexer, torznab, cat).await
-                } else {
-                    handle_search(indexer, torznab, cat, q).await
-                }
-            }
-            Self::TvSearch { cat, q, season, ep } => {
-                if let Some(query) = q {
-                    handle_tv_search(indexer, torznab, cat, query, season, ep).await
-                } else {
-                    handle_feed(indexer, torznab, cat).await
-                }
-            }
-            Self::Movie { cat, q } => {
-                if let Some(query) = q {
-                    handle_search(indexer, torznab, cat, query).await
-                } else {
-                    handle_feed(indexer, torznab, cat).await
-                }
+            Self::Caps => None,
+            Self::Search { cat, .. } | Self::TvSearch { cat, .. } | Self::Movie { cat, .. } => {
+                Some(*cat)
             }
+            Self::Music { .. } => Some(Category::Music),
+            Self::Book { .. } => Some(Category::Book),
+        }
+    }
+
+    pub(crate) fn request(&self) -> Option<SearchRequest> {
+        match self {
+            Self::Caps => None,
+            Self::Search { params, .. }
+            | Self::TvSearch { params, .. }
+            | Self::Movie { params, .. }
+            | Self::Music { params }
+            | Self::Book { params } => params.request(self.category()?),
+        }
+    }
+
+    /// Feed requests can be served from stale cache entries while being refreshed.
+    fn is_feed(&self) -> bool {
+        self.category().is_some() && self.request().is_none()
+    }
+
+    async fn handle(
+        self,
+        history: History,
+        indexer: Arc<IndexerManager>,
+        torznab: Arc<TorznabBuilder>,
+    ) -> Rendered {
+        let Some(category) = self.category() else {
+            return Rendered {
+                body: torznab.capabilities(&indexer.capabilities()),
+                failed: false,
+            };
+        };
+        match self.request() {
+            Some(request) => handle_search(history, indexer, torznab, category, request).await,
+            None => handle_feed(history, indexer, torznab, category).await,
         }
     }
 }
 
 async fn handle_feed(
+    history: History,
     indexer: Arc<IndexerManager>,
     torznab: Arc<TorznabBuilder>,
     category: Category,
-) -> String {
+) -> Rendered {
     let result = indexer.feed(category).await;
-    if !result.errors.is_empty() {
-        tracing::debug!("had the following errors: {:?}", result.errors);
-    }
-    torznab.feed(category, &result.entries)
+    history.record(&result.entries).await;
+    Rendered::new(&torznab, category, result)
 }
 
-fn format_number(input: String) -> String {
-    if input.len() == 1 {
-        format!("0{input}")
-    } else {
-        input
-    }
-}
-
-async fn handle_tv_search(
+async fn handle_search(
+    history: History,
     indexer: Arc<IndexerManager>,
     torznab: Arc<TorznabBuilder>,
     category: Category,
-    query: String,
-    season: Option<String>,
-    episode: Option<String>,
-) -> String {
-    // TODO handle category in search
-    let query = match (season, episode) {
-        (Some(s), Some(e)) => format!("{query} S{}E{}", format_number(s), format_number(e)),
-        (Some(s), None) => format!("{query} S{}", format_number(s)),
-        _ => query,
+    request: SearchRequest,
+) -> Rendered {
+    let parsed = SearchQuery::parse(&request.query);
+    let request = SearchRequest {
+        query: parsed.upstream(),
+        ..request
     };
-    let result = indexer.search(&query).await;
-    if !result.errors.is_empty() {
-        tracing::debug!("had the following errors: {:?}", result.errors);
+    let mut result = indexer.search(&request).await;
+    history.record(&result.entries).await;
+    let found = result.entries.len();
+    history
+        .complete(&request.keywords(), &mut result.entries)
+        .await;
+    let mut added = result.entries.split_off(found);
+    indexer.screen(request.category(), &mut added);
+    result.entries.append(&mut added);
+    // the entries of the history match the query too
+    parsed.retain(&mut result.entries);
+    // last, the history completing the results
+    request.paginate(&mut result.entries);
+    Rendered::new(&torznab, category, result)
+}
+
+async fn refresh(
+    cache: Arc<Cache>,
+    history: History,
+    indexer: Arc<IndexerManager>,
+    torznab: Arc<TorznabBuilder>,
+    params: QueryParams,
+    guard: RefreshGuard,
+) {
+    tracing::debug!("refreshing stale entry in background");
+    let result = params.handle(history, indexer, torznab).await;
+    if result.failed {
+        tracing::debug!("refresh failed, keeping stale entry");
+    } else {
+        cache.insert(guard.key().to_string(), result.body).await;
     }
-    torznab.feed(category, &result.entries)
 }
 
-async fn handle_search(
+async fn serve(
+    cache: Arc<Cache>,
+    history: History,
     indexer: Arc<IndexerManager>,
     torznab: Arc<TorznabBuilder>,
-    category: Category,
-    query: String,
-) -> String {
-    // TODO handle category in search
-    let result = indexer.search(query.as_str()).await;
-    if !result.errors.is_empty() {
-        tracing::debug!("had the following errors: {:?}", result.errors);
-    }
-    torznab.feed(category, &result.entries)
+    params: QueryParams,
+    key: String,
+) -> ApplicationRssXml {
+    match cache.get(&key) {
+        CacheLookup::Fresh(found) => {
+            tracing::debug!("found in cache");
+            ApplicationRssXml(found)
+        }
+        CacheLookup::Stale(found) if params.is_feed() => {
+            tracing::debug!("found stale entry in cache");
+            if let Some(guard) = cache.start_refresh(&key) {
+                tokio::spawn(refresh(
+                    cache.clone(),
+                    history,
+                    indexer,
+                    torznab,
+                    params,
+                    guard,
+                ));
+            }
+            ApplicationRssXml(found)
+        }
+        lookup => {
+            let result = params.handle(history, indexer, torznab).await;
+            match lookup.into_stale() {
+                Some(stale) if result.failed => {
+                    tracing::debug!("all indexers failed, falling back to stale entry");
+                    ApplicationRssXml(stale)
+                }
+                _ => {
+                    cache.insert(key, result.body.clone()).await;
+                    ApplicationRssXml(result.body)
+                }
+            }
+        }
+    }
 }
 
 pub async fn handler(
-    Extension(cache): Extension<Arc<Cache<String, String>>>,
+    Extension(cache): Extension<Arc<Cache>>,
+    Extension(history): Extension<History>,
     Extension(indexer): Extension<Arc<IndexerManager>>,
     Extension(torznab): Extension<Arc<TorznabBuilder>>,
     Query(params): Query<QueryParams>,
 ) -> ApplicationRssXml {
     tracing::debug!("GET /api/torznab params={params:?}");
     let key = format!("{params:?}");
-    if let Some(found) = cache.get(&key) {
-        tracing::debug!("found in cache");
-        ApplicationRssXml(found)
-    } else {
-        let result = params.handle(indexer, torznab).await;
-        cache.insert(key, result.clone()).await;
-        ApplicationRssXml(result)
-    }
+    serve(cache, history, indexer, torznab, params, key).await
+}
+
+/// Same api, restricted to a single indexer.
+pub async fn indexer_handler(
+    Extension(cache): Extension<Arc<Cache>>,
+    Extension(indexer): Extension<Arc<IndexerManager>>,
+    Extension(torznab): Extension<Arc<TorznabBuilder>>,
+    Path(name): Path<String>,
+    Query(params): Query<QueryParams>,
+) -> Response {
+    tracing::debug!("GET /api/torznab/{name} params={params:?}");
+    let Some(indexer) = indexer.only(&name) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let key = format!("{name}/{params:?}");
+    // the history mixes the entries of every indexer
+    serve(
+        cache,
+        History::default(),
+        Arc::new(indexer),
+        torznab,
+        params,
+        key,
+    )
+    .await
+    .into_response()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{handler, QueryParams};
+    use super::{handler, QueryParams, SearchParams};
+    use crate::service::cache::Cache;
+    use crate::service::history::{History, HistoryConfig};
+    use crate::service::torznab::TorznabBuilder;
     use axum::extract::{Extension, Query};
+    use manteau_indexer_manager::{FilterConfig, FiltersConfig, IndexerManager};
+    use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode, SearchParam};
+    use manteau_indexer_prelude::{
+        bytesize, Category, IndexerEntry, IndexerError, IndexerErrorReason, IndexerResult,
+        SearchRequest,
+    };
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Returns as many entries as the number of calls, or fails when `failing` is set.
+    #[derive(Debug, Default)]
+    struct GrowingIndexer {
+        calls: Arc<AtomicUsize>,
+        failing: Arc<AtomicBool>,
+    }
+
+    impl GrowingIndexer {
+        fn result(&self) -> IndexerResult {
+            if self.failing.load(Ordering::SeqCst) {
+                return IndexerResult::from(IndexerError::new(
+                    "fake",
+                    IndexerErrorReason::EntryNameNotFound,
+                ));
+            }
+            let count = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            IndexerResult::from(
+                (0..count)
+                    .map(|index| IndexerEntry {
+                        size: bytesize::ByteSize::mb(120),
+                        seeders: 10,
+                        leechers: 20,
+                        magnet: "magnet-url".into(),
+                        ..IndexerEntry::new(
+                            "fake",
+                            format!("entry {index}"),
+                            format!("https://example.com/{index}"),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl manteau_indexer_prelude::Indexer for GrowingIndexer {
+        async fn search(&self, _request: &SearchRequest) -> IndexerResult {
+            self.result()
+        }
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            self.result()
+        }
+    }
+
+    async fn params(uri: &str) -> QueryParams {
+        use axum::extract::FromRequestParts;
+
+        let request = axum::http::Request::builder().uri(uri).body(()).unwrap();
+        let (mut parts, _) = request.into_parts();
+        let Query(params) = Query::<QueryParams>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        params
+    }
+
+    #[tokio::test]
+    async fn should_read_search_params() {
+        let request = params(
+            "/api/torznab?t=tvsearch&cat=5000&q=Dark&season=1&ep=2&imdbid=5753856&offset=10&limit=5",
+        )
+        .await
+        .request()
+        .unwrap();
+        assert_eq!(request.query, "Dark");
+        assert_eq!(request.categories, vec![Category::Tv]);
+        assert_eq!(request.season, Some(1));
+        assert_eq!(request.episode.as_deref(), Some("2"));
+        assert_eq!(request.imdb_id.as_deref(), Some("tt5753856"));
+        assert_eq!((request.offset, request.limit), (10, Some(5)));
+
+        let request = params("/api/torznab?t=music&artist=Daft%20Punk&album=Discovery")
+            .await
+            .request()
+            .unwrap();
+        assert_eq!(request.keywords(), "Daft Punk Discovery");
+
+        let request = params("/api/torznab?t=tvsearch&cat=5000&tvdbid=81189&season=1")
+            .await
+            .request()
+            .unwrap();
+        assert_eq!((request.tvdb_id, request.season), (Some(81189), Some(1)));
+
+        let feed = params("/api/torznab?t=tvsearch&cat=5000&q=%20").await;
+        assert!(feed.request().is_none());
+        assert!(feed.is_feed());
+    }
+
+    /// Tells apart the searches and the feeds by the name of the entry.
+    #[derive(Debug, Default)]
+    struct RecordingIndexer {
+        searched: Arc<std::sync::Mutex<Vec<SearchRequest>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl manteau_indexer_prelude::Indexer for RecordingIndexer {
+        async fn search(&self, request: &SearchRequest) -> IndexerResult {
+            self.searched.lock().unwrap().push(request.clone());
+            IndexerResult::from(vec![IndexerEntry::new("fake", "searched", "https://s")])
+        }
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            IndexerResult::from(vec![IndexerEntry::new("fake", "latest", "https://l")])
+        }
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::default()
+                .with_keyword_searches()
+                .with_search(SearchMode::MovieSearch, &[SearchParam::ImdbId])
+        }
+    }
+
+    #[tokio::test]
+    async fn should_search_by_id_only() {
+        let indexer = RecordingIndexer::default();
+        let searched = indexer.searched.clone();
+        let res = handler(
+            Extension(crate::service::cache::build()),
+            Extension(Default::default()),
+            Extension(Arc::new(IndexerManager::with_indexer(indexer))),
+            Extension(Default::default()),
+            Query(params("/api/torznab?t=movie&cat=2000&imdbid=tt0133093").await),
+        )
+        .await;
+        assert!(res.0.contains("<title>searched</title>"));
+        assert!(!res.0.contains("latest"));
+        let searched = searched.lock().unwrap();
+        assert_eq!(searched.len(), 1);
+        assert_eq!(searched[0].imdb_id.as_deref(), Some("tt0133093"));
+        assert!(searched[0].query.is_empty());
+    }
+
+    async fn history(names: &[&str]) -> History {
+        let history = HistoryConfig {
+            path: ":memory:".into(),
+            retention_days: 30,
+            max_entries: 100,
+            search_limit: 100,
+        }
+        .build()
+        .unwrap();
+        let entries = names
+            .iter()
+            .map(|name| IndexerEntry::new("fake", *name, format!("https://example.com/{name}")))
+            .collect::<Vec<_>>();
+        history.record(&entries).await;
+        history
+    }
+
+    #[tokio::test]
+    async fn should_filter_entries_from_history() {
+        let history = history(&["Dune.2021.1080p.CAM", "Dune.2021.1080p.WEB"]).await;
+        let indexer =
+            IndexerManager::with_indexer(RecordingIndexer::default()).with_filters(FiltersConfig {
+                global: FilterConfig {
+                    block: vec!["cam".into()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        let res = handler(
+            Extension(crate::service::cache::build()),
+            Extension(history),
+            Extension(Arc::new(indexer)),
+            Extension(Default::default()),
+            Query(params("/api/torznab?t=movie&cat=2000&q=dune").await),
+        )
+        .await;
+        assert!(res.0.contains("Dune.2021.1080p.WEB"));
+        assert!(!res.0.contains("CAM"));
+    }
+
+    #[tokio::test]
+    async fn should_apply_query_to_entries_from_history() {
+        let history = history(&["Dune.2021.720p.WEB", "Dune.2021.1080p.WEB"]).await;
+        let res = handler(
+            Extension(crate::service::cache::build()),
+            Extension(history),
+            Extension(Arc::new(IndexerManager::with_indexer(
+                RecordingIndexer::default(),
+            ))),
+            Extension(Default::default()),
+            Query(params("/api/torznab?t=movie&cat=2000&q=dune%20-720p").await),
+        )
+        .await;
+        assert!(res.0.contains("Dune.2021.1080p.WEB"));
+        assert!(!res.0.contains("720p"));
+    }
+
+    #[tokio::test]
+    async fn should_mark_suspicious_entries_from_history() {
+        let history = history(&["Dune.2021.2160p.exe"]).await;
+        let res = handler(
+            Extension(crate::service::cache::build()),
+            Extension(history),
+            Extension(Arc::new(IndexerManager::with_indexer(
+                RecordingIndexer::default(),
+            ))),
+            Extension(Default::default()),
+            Query(params("/api/torznab?t=movie&cat=2000&q=dune").await),
+        )
+        .await;
+        assert!(res.0.contains("Dune.2021.2160p.exe"));
+        assert!(res
+            .0
+            .contains(r#"<torznab:attr name="tag" value="suspicious"/>"#));
+    }
+
+    fn stale_cache() -> Arc<Cache> {
+        Arc::new(Cache::new(
+            10,
+            Duration::from_millis(50),
+            Some(Duration::from_secs(10)),
+        ))
+    }
+
+    fn count_items(body: &str) -> usize {
+        body.matches("<item>").count()
+    }
 
     #[tokio::test]
     async fn success() {
@@ -197,21 +599,77 @@ mod tests {
             Extension(crate::service::cache::build()),
             Extension(Default::default()),
             Extension(Default::default()),
+            Extension(Default::default()),
             Query(QueryParams::Caps),
         )
         .await;
         assert!(res.0.contains("manteau"));
     }
+
+    #[tokio::test]
+    async fn should_serve_stale_feed_and_refresh() {
+        let cache = stale_cache();
+        let indexer = Arc::new(IndexerManager::with_indexer(GrowingIndexer::default()));
+        let torznab = Arc::new(TorznabBuilder::default());
+
+        let call = || {
+            handler(
+                Extension(cache.clone()),
+                Extension(Default::default()),
+                Extension(indexer.clone()),
+                Extension(torznab.clone()),
+                Query(QueryParams::Music {
+                    params: Default::default(),
+                }),
+            )
+        };
+
+        assert_eq!(count_items(&call().await.0), 1);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // expired, served right away while being refreshed
+        assert_eq!(count_items(&call().await.0), 1);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(count_items(&call().await.0), 2);
+    }
+
+    #[tokio::test]
+    async fn should_fallback_to_stale_when_failing() {
+        let cache = stale_cache();
+        let mock = GrowingIndexer::default();
+        let failing = mock.failing.clone();
+        let indexer = Arc::new(IndexerManager::with_indexer(mock));
+        let torznab = Arc::new(TorznabBuilder::default());
+
+        let call = || {
+            handler(
+                Extension(cache.clone()),
+                Extension(Default::default()),
+                Extension(indexer.clone()),
+                Extension(torznab.clone()),
+                Query(QueryParams::Search {
+                    cat: Category::Movie,
+                    params: SearchParams {
+                        q: Some("foo".into()),
+                        ..Default::default()
+                    },
+                }),
+            )
+        };
+
+        assert_eq!(count_items(&call().await.0), 1);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        failing.store(true, Ordering::SeqCst);
+        assert_eq!(count_items(&call().await.0), 1);
+    }
 }
 
 #[cfg(test)]
 mod integration_tests {
     use axum::body::Body;
     use axum::http::{Request, StatusCode};
-    use chrono::Utc;
     use manteau_indexer_manager::IndexerManager;
     use manteau_indexer_prelude::bytesize;
-    use manteau_indexer_prelude::{Category, IndexerEntry, IndexerResult};
+    use manteau_indexer_prelude::{Category, IndexerEntry, IndexerResult, SearchRequest};
     use std::sync::Arc;
     use tower::ServiceExt;
 
@@ -224,7 +682,7 @@ mod integration_tests {
 
     #[async_trait::async_trait]
     impl manteau_indexer_prelude::Indexer for MockIndexer {
-        async fn search(&self, _query: &str) -> IndexerResult {
+        async fn search(&self, _request: &SearchRequest) -> IndexerResult {
             IndexerResult::from(self.entries.clone())
         }
         async fn feed(&self, _category: Category) -> IndexerResult {
@@ -240,8 +698,10 @@ mod integration_tests {
         let torznab = TorznabBuilder::default();
         let app = crate::router(
             crate::service::cache::build(),
+            Default::default(),
             Arc::new(indexer),
             Arc::new(torznab),
+            Default::default(),
         );
 
         let response = app
@@ -268,22 +728,21 @@ mod integration_tests {
 
         let mut mock = MockIndexer::default();
         mock.entries.push(IndexerEntry {
-            name: "too".to_string(),
-            url: "https://example.com".into(),
-            date: Utc::now(),
             size: bytesize::ByteSize::mb(120),
             seeders: 10,
             leechers: 20,
             magnet: "magnet-url".into(),
-            origin: "fake",
+            ..IndexerEntry::new("fake", "too", "https://example.com")
         });
 
         let indexer = IndexerManager::with_indexer(mock);
         let torznab = TorznabBuilder::default();
         let app = crate::router(
             crate::service::cache::build(),
+            Default::default(),
             Arc::new(indexer),
             Arc::new(torznab),
+            Default::default(),
         );
 
         let response = app
@@ -318,8 +777,10 @@ mod integration_tests {
         let torznab = TorznabBuilder::default();
         let app = crate::router(
             crate::service::cache::build(),
+            Default::default(),
             Arc::new(indexer),
             Arc::new(torznab),
+            Default::default(),
         );
 
         let response = app
@@ -341,4 +802,44 @@ mod integration_tests {
         let items = channel.into_items();
         assert_eq!(items.len(), 0);
     }
+
+    #[tokio::test]
+    async fn should_serve_a_single_indexer() {
+        crate::init_logs();
+
+        let indexer = IndexerManager::with_indexer(MockIndexer::default());
+        let app = crate::router(
+            crate::service::cache::build(),
+            Default::default(),
+            Arc::new(indexer),
+            Arc::new(TorznabBuilder::default()),
+            Default::default(),
+        );
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/torznab/default?t=caps")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains(r#"<tv-search available="yes" supportedParams="q,season,ep"/>"#));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/torznab/unknown?t=caps")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
 }
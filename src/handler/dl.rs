@@ -0,0 +1,116 @@
+use crate::service::cache::{Cache, CacheLookup};
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::Extension;
+use manteau_indexer_manager::IndexerManager;
+use std::sync::Arc;
+
+pub async fn handler(
+    Extension(cache): Extension<Arc<Cache>>,
+    Extension(indexer): Extension<Arc<IndexerManager>>,
+    Path((name, token)): Path<(String, String)>,
+) -> Response {
+    tracing::debug!("GET /dl/{name}/{token}");
+    let key = format!("/dl/{name}/{token}");
+    let link = match cache.get(&key) {
+        // a resolved link doesn't change over time
+        CacheLookup::Fresh(found) | CacheLookup::Stale(found) => found,
+        CacheLookup::Missing => match indexer.resolve(&name, &token).await {
+            None => return StatusCode::NOT_FOUND.into_response(),
+            Some(Err(error)) => {
+                tracing::debug!("unable to resolve link: {error:?}");
+                return StatusCode::BAD_GATEWAY.into_response();
+            }
+            Some(Ok(link)) => {
+                cache.insert(key, link.clone()).await;
+                link
+            }
+        },
+    };
+    Redirect::to(&link).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use manteau_indexer_manager::IndexerManager;
+    use manteau_indexer_prelude::{Category, IndexerError, IndexerResult, SearchRequest};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[derive(Debug, Default)]
+    struct ResolvingIndexer {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl manteau_indexer_prelude::Indexer for ResolvingIndexer {
+        async fn search(&self, _request: &SearchRequest) -> IndexerResult {
+            IndexerResult::default()
+        }
+
+        async fn feed(&self, _category: Category) -> IndexerResult {
+            IndexerResult::default()
+        }
+
+        async fn resolve(&self, token: &str) -> Result<String, IndexerError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("magnet:?xt=urn:btih:{token}"))
+        }
+    }
+
+    fn app(indexer: IndexerManager) -> axum::Router {
+        crate::router(
+            crate::service::cache::build(),
+            Default::default(),
+            Arc::new(indexer),
+            Arc::new(Default::default()),
+            Default::default(),
+        )
+    }
+
+    async fn get(app: &axum::Router, uri: &str) -> axum::response::Response {
+        app.clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_redirect_to_resolved_magnet() {
+        let indexer = ResolvingIndexer::default();
+        let calls = indexer.calls.clone();
+        let app = app(IndexerManager::with_indexer(indexer));
+
+        for _ in 0..2 {
+            let response = get(&app, "/dl/default/abcdef").await;
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+            assert_eq!(
+                response.headers().get(header::LOCATION).unwrap(),
+                "magnet:?xt=urn:btih:abcdef"
+            );
+        }
+        // the second call is served from the cache
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn should_decode_token() {
+        let app = app(IndexerManager::with_indexer(ResolvingIndexer::default()));
+        let response = get(&app, "/dl/default/%2Ftorrent%2F42%2Ffoo%2F").await;
+        assert_eq!(
+            response.headers().get(header::LOCATION).unwrap(),
+            "magnet:?xt=urn:btih:/torrent/42/foo/"
+        );
+    }
+
+    #[tokio::test]
+    async fn should_fail_with_unknown_indexer() {
+        let app = app(IndexerManager::with_indexer(ResolvingIndexer::default()));
+        let response = get(&app, "/dl/unknown/abcdef").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
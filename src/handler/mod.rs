@@ -1 +1,3 @@
+pub mod access;
 pub mod api;
+pub mod dl;
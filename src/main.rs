@@ -30,14 +30,26 @@ fn address() -> std::net::SocketAddr {
 }
 
 fn router(
-    cache: Arc<crate::service::cache::Cache<String, String>>,
+    cache: Arc<crate::service::cache::Cache>,
+    history: crate::service::history::History,
     indexer: Arc<manteau_indexer_manager::IndexerManager>,
     torznab: Arc<crate::service::torznab::TorznabBuilder>,
+    access: Arc<crate::service::access::Access>,
 ) -> Router {
     Router::new()
+        .route("/api/search", routing::get(handler::api::search::handler))
+        .route("/api/status", routing::get(handler::api::status::handler))
         .route("/api/torznab", routing::get(handler::api::torznab::handler))
+        .route(
+            "/api/torznab/:indexer",
+            routing::get(handler::api::torznab::indexer_handler),
+        )
+        .route("/dl/:indexer/:token", routing::get(handler::dl::handler))
+        .layer(axum::middleware::from_fn(handler::access::middleware))
         .layer(tower_http::trace::TraceLayer::new_for_http())
+        .layer(Extension(access))
         .layer(Extension(cache))
+        .layer(Extension(history))
         .layer(Extension(indexer))
         .layer(Extension(torznab))
 }
@@ -48,16 +60,36 @@ async fn main() {
 
     let config = crate::config::Config::from_env().expect("couldn't load configuration");
     let cache = Arc::new(config.cache.build());
-    let indexer = Arc::new(config.indexers.build());
+    let history = config
+        .history
+        .map(|history| history.build())
+        .transpose()
+        .expect("couldn't open history")
+        .unwrap_or_default();
+    let indexer = Arc::new(
+        config
+            .indexers
+            .build(config.http.build())
+            .expect("couldn't build indexers")
+            .with_ranking(config.ranking)
+            .with_filters(config.filters)
+            .with_heuristics(config.heuristics),
+    );
     let torznab = Arc::new(config.torznab.build());
 
-    let app = router(cache, indexer, torznab);
+    config
+        .scheduler
+        .build()
+        .start(history.clone(), indexer.clone());
+
+    let access = Arc::new(config.access.build());
+    let app = router(cache, history, indexer, torznab, access);
 
     let addr = address();
     tracing::debug!("listening on {}", addr);
 
     axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<std::net::SocketAddr>())
         .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
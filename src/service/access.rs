@@ -0,0 +1,184 @@
+use ipnet::IpNet;
+use manteau_indexer_prelude::ratelimit::{RateLimitConfig, RateLimiter};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct AccessConfig {
+    /// Ranges of addresses allowed to use the server, everyone when empty.
+    #[serde(default)]
+    pub allow: Vec<IpNet>,
+    /// Proxies whose `X-Forwarded-For` header is used to find the client address.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpNet>,
+    /// Limits applied to each client, identified by its api key or its address.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Api keys given to the clients, the other keys being ignored.
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+}
+
+impl AccessConfig {
+    pub fn build(self) -> Access {
+        Access {
+            allow: self.allow,
+            trusted_proxies: self.trusted_proxies,
+            rate_limit: self.rate_limit,
+            api_keys: self.api_keys,
+            clients: moka::sync::Cache::builder()
+                .max_capacity(10_000)
+                // an idle client has its daily budget reset anyway
+                .time_to_idle(Duration::from_secs(24 * 60 * 60))
+                .build(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Rejection {
+    NotAllowed,
+    RateLimited { retry_in: Duration },
+}
+
+pub struct Access {
+    allow: Vec<IpNet>,
+    trusted_proxies: Vec<IpNet>,
+    rate_limit: Option<RateLimitConfig>,
+    api_keys: Vec<String>,
+    clients: moka::sync::Cache<String, Arc<RateLimiter>>,
+}
+
+impl Default for Access {
+    fn default() -> Self {
+        AccessConfig::default().build()
+    }
+}
+
+impl Access {
+    fn is_trusted_proxy(&self, address: &IpAddr) -> bool {
+        self.trusted_proxies
+            .iter()
+            .any(|range| range.contains(address))
+    }
+
+    /// Finds the address of the client, following `X-Forwarded-For` through the trusted proxies.
+    pub fn client_address(&self, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        if !self.is_trusted_proxy(&peer) {
+            return peer;
+        }
+        let Some(forwarded_for) = forwarded_for else {
+            return peer;
+        };
+        // each proxy appends the address it received the request from
+        let mut client = peer;
+        for hop in forwarded_for.rsplit(',') {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(address) => {
+                    client = address;
+                    if !self.is_trusted_proxy(&address) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        client
+    }
+
+    pub fn check(&self, address: Option<IpAddr>, api_key: Option<&str>) -> Result<(), Rejection> {
+        if !self.allow.is_empty()
+            && !address
+                .is_some_and(|address| self.allow.iter().any(|range| range.contains(&address)))
+        {
+            return Err(Rejection::NotAllowed);
+        }
+        let Some(ref rate_limit) = self.rate_limit else {
+            return Ok(());
+        };
+        // an unknown key would give a new budget to anyone making one up
+        let api_key = api_key.filter(|key| self.api_keys.iter().any(|known| known == key));
+        let key = match (api_key, address) {
+            (Some(api_key), _) => format!("key:{api_key}"),
+            (None, Some(address)) => format!("ip:{address}"),
+            (None, None) => String::from("unknown"),
+        };
+        self.clients
+            .get_with(key, || Arc::new(rate_limit.clone().build()))
+            .try_acquire()
+            .map_err(|retry_in| Rejection::RateLimited { retry_in })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccessConfig, Rejection};
+    use manteau_indexer_prelude::ratelimit::RateLimitConfig;
+    use std::net::IpAddr;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn should_only_follow_trusted_proxies() {
+        let access = AccessConfig {
+            trusted_proxies: vec!["10.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        }
+        .build();
+        let forwarded = Some("1.1.1.1, 2.2.2.2, 10.0.0.2");
+        assert_eq!(
+            access.client_address(ip("10.0.0.1"), forwarded),
+            ip("2.2.2.2")
+        );
+        assert_eq!(
+            access.client_address(ip("3.3.3.3"), forwarded),
+            ip("3.3.3.3")
+        );
+        assert_eq!(access.client_address(ip("10.0.0.1"), None), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn should_reject_addresses_out_of_allowlist() {
+        let access = AccessConfig {
+            allow: vec![
+                "192.168.0.0/16".parse().unwrap(),
+                "::1/128".parse().unwrap(),
+            ],
+            ..Default::default()
+        }
+        .build();
+        assert!(access.check(Some(ip("192.168.1.12")), None).is_ok());
+        assert!(access.check(Some(ip("::1")), None).is_ok());
+        assert_eq!(
+            access.check(Some(ip("8.8.8.8")), None),
+            Err(Rejection::NotAllowed)
+        );
+        assert_eq!(access.check(None, None), Err(Rejection::NotAllowed));
+    }
+
+    #[test]
+    fn should_limit_each_client() {
+        let access = AccessConfig {
+            rate_limit: Some(RateLimitConfig {
+                per_minute: Some(1),
+                ..Default::default()
+            }),
+            api_keys: vec!["secret".into()],
+            ..Default::default()
+        }
+        .build();
+        assert!(access.check(Some(ip("1.1.1.1")), None).is_ok());
+        assert!(access.check(Some(ip("1.1.1.1")), None).is_err());
+        assert!(access.check(Some(ip("2.2.2.2")), None).is_ok());
+        // the api key identifies the client, whatever its address
+        assert!(access.check(Some(ip("1.1.1.1")), Some("secret")).is_ok());
+        assert!(access.check(Some(ip("2.2.2.2")), Some("secret")).is_err());
+        // the unknown keys are limited with the address
+        assert!(access.check(Some(ip("2.2.2.2")), Some("random")).is_err());
+        assert!(access.check(Some(ip("3.3.3.3")), Some("random")).is_ok());
+        assert!(access.check(Some(ip("3.3.3.3")), Some("other")).is_err());
+    }
+}
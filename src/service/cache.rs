@@ -1,5 +1,6 @@
-pub use moka::future::Cache;
-use std::time::Duration;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, serde::Deserialize)]
 pub struct CacheConfig {
@@ -7,6 +8,12 @@ pub struct CacheConfig {
     pub capacity: u64,
     #[serde(default = "CacheConfig::default_ttl")]
     pub ttl: u64,
+    /// Serve expired feeds right away and refresh them in the background.
+    #[serde(default)]
+    pub stale_while_revalidate: bool,
+    /// How long, in seconds, an expired entry can still be served.
+    #[serde(default = "CacheConfig::default_max_stale")]
+    pub max_stale: u64,
 }
 
 impl Default for CacheConfig {
@@ -14,6 +21,8 @@ impl Default for CacheConfig {
         Self {
             capacity: Self::default_capacity(),
             ttl: Self::default_ttl(),
+            stale_while_revalidate: false,
+            max_stale: Self::default_max_stale(),
         }
     }
 }
@@ -27,15 +36,172 @@ impl CacheConfig {
         60
     }
 
-    pub fn build(self) -> Cache<String, String> {
-        Cache::builder()
-            .max_capacity(self.capacity)
-            .time_to_live(Duration::from_secs(self.ttl))
-            .build()
+    fn default_max_stale() -> u64 {
+        3600
+    }
+
+    pub fn build(self) -> Cache {
+        let max_stale = self
+            .stale_while_revalidate
+            .then(|| Duration::from_secs(self.max_stale));
+        Cache::new(self.capacity, Duration::from_secs(self.ttl), max_stale)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    value: String,
+    created_at: Instant,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CacheLookup {
+    Fresh(String),
+    Stale(String),
+    Missing,
+}
+
+impl CacheLookup {
+    pub fn into_stale(self) -> Option<String> {
+        match self {
+            Self::Stale(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+pub struct Cache {
+    inner: moka::future::Cache<String, CacheEntry>,
+    ttl: Duration,
+    // keys being refreshed in the background
+    refreshing: Mutex<HashSet<String>>,
+}
+
+impl Cache {
+    /// When `max_stale` is set, expired entries are kept that much longer and reported as stale.
+    pub fn new(capacity: u64, ttl: Duration, max_stale: Option<Duration>) -> Self {
+        Self {
+            inner: moka::future::Cache::builder()
+                .max_capacity(capacity)
+                .time_to_live(ttl + max_stale.unwrap_or_default())
+                .build(),
+            ttl,
+            refreshing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> CacheLookup {
+        match self.inner.get(key) {
+            Some(entry) if entry.created_at.elapsed() < self.ttl => CacheLookup::Fresh(entry.value),
+            Some(entry) => CacheLookup::Stale(entry.value),
+            None => CacheLookup::Missing,
+        }
+    }
+
+    pub async fn insert(&self, key: String, value: String) {
+        self.inner
+            .insert(
+                key,
+                CacheEntry {
+                    value,
+                    created_at: Instant::now(),
+                },
+            )
+            .await;
+    }
+
+    /// Flags the key as being refreshed, returns `None` if a refresh is already running.
+    ///
+    /// The flag is cleared when the returned guard is dropped, even by a panicking refresh.
+    pub fn start_refresh(self: &Arc<Self>, key: &str) -> Option<RefreshGuard> {
+        self.refreshing
+            .lock()
+            .expect("cache lock poisoned")
+            .insert(key.to_string())
+            .then(|| RefreshGuard {
+                cache: self.clone(),
+                key: key.to_string(),
+            })
+    }
+}
+
+/// Refresh running in the background for a key of the cache.
+pub struct RefreshGuard {
+    cache: Arc<Cache>,
+    key: String,
+}
+
+impl RefreshGuard {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Drop for RefreshGuard {
+    fn drop(&mut self) {
+        // panicking again while unwinding would abort
+        self.cache
+            .refreshing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.key);
     }
 }
 
 #[cfg(test)]
-pub fn build() -> std::sync::Arc<Cache<String, String>> {
+pub fn build() -> std::sync::Arc<Cache> {
     std::sync::Arc::new(CacheConfig::default().build())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Cache, CacheLookup};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn should_expire_without_max_stale() {
+        let cache = Cache::new(10, Duration::from_millis(50), None);
+        cache.insert("foo".into(), "bar".into()).await;
+        assert_eq!(cache.get("foo"), CacheLookup::Fresh("bar".into()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(cache.get("foo"), CacheLookup::Missing);
+    }
+
+    #[tokio::test]
+    async fn should_serve_stale_until_max_stale() {
+        let cache = Cache::new(
+            10,
+            Duration::from_millis(50),
+            Some(Duration::from_millis(100)),
+        );
+        cache.insert("foo".into(), "bar".into()).await;
+        assert_eq!(cache.get("foo"), CacheLookup::Fresh("bar".into()));
+        tokio::time::sleep(Duration::from_millis(75)).await;
+        assert_eq!(cache.get("foo"), CacheLookup::Stale("bar".into()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(cache.get("foo"), CacheLookup::Missing);
+    }
+
+    #[test]
+    fn should_refresh_only_once() {
+        let cache = Arc::new(Cache::new(10, Duration::from_secs(1), None));
+        let guard = cache.start_refresh("foo").unwrap();
+        assert_eq!(guard.key(), "foo");
+        assert!(cache.start_refresh("foo").is_none());
+        drop(guard);
+        assert!(cache.start_refresh("foo").is_some());
+    }
+
+    #[tokio::test]
+    async fn should_end_refresh_when_panicking() {
+        let cache = Arc::new(Cache::new(10, Duration::from_secs(1), None));
+        let guard = cache.start_refresh("foo").unwrap();
+        let task = tokio::spawn(async move {
+            let _guard = guard;
+            panic!("refresh failed");
+        });
+        assert!(task.await.is_err());
+        assert!(cache.start_refresh("foo").is_some());
+    }
+}
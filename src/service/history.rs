@@ -0,0 +1,376 @@
+use chrono::{Duration, TimeZone, Utc};
+use manteau_indexer_helper::magnet::info_hash;
+use manteau_indexer_helper::release;
+use manteau_indexer_prelude::bytesize::ByteSize;
+use manteau_indexer_prelude::{DeferredLink, IndexerEntry};
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Origin of the entries coming from the history.
+pub const NAME: &str = "history";
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS entries (
+    key TEXT NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    date INTEGER,
+    size INTEGER NOT NULL,
+    seeders INTEGER NOT NULL,
+    leechers INTEGER NOT NULL,
+    magnet TEXT NOT NULL,
+    origin TEXT NOT NULL,
+    deferred_indexer TEXT,
+    deferred_token TEXT,
+    seen_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS entries_seen_at ON entries (seen_at);
+CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts USING fts5(
+    name,
+    content='entries',
+    tokenize='unicode61 remove_diacritics 2'
+);
+CREATE TRIGGER IF NOT EXISTS entries_ai AFTER INSERT ON entries BEGIN
+    INSERT INTO entries_fts (rowid, name) VALUES (new.rowid, new.name);
+END;
+CREATE TRIGGER IF NOT EXISTS entries_ad AFTER DELETE ON entries BEGIN
+    INSERT INTO entries_fts (entries_fts, rowid, name) VALUES ('delete', old.rowid, old.name);
+END;
+CREATE TRIGGER IF NOT EXISTS entries_au AFTER UPDATE OF name ON entries BEGIN
+    INSERT INTO entries_fts (entries_fts, rowid, name) VALUES ('delete', old.rowid, old.name);
+    INSERT INTO entries_fts (rowid, name) VALUES (new.rowid, new.name);
+END;
+"#;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct HistoryConfig {
+    /// Path to the database, `:memory:` keeps it in memory.
+    pub path: PathBuf,
+    /// Entries not seen for that many days are removed.
+    #[serde(default = "HistoryConfig::default_retention_days")]
+    pub retention_days: u32,
+    /// Maximum number of entries kept, the oldest seen ones are removed first.
+    #[serde(default = "HistoryConfig::default_max_entries")]
+    pub max_entries: u32,
+    /// Maximum number of entries returned by a search.
+    #[serde(default = "HistoryConfig::default_search_limit")]
+    pub search_limit: u32,
+}
+
+impl HistoryConfig {
+    fn default_retention_days() -> u32 {
+        30
+    }
+
+    fn default_max_entries() -> u32 {
+        100_000
+    }
+
+    fn default_search_limit() -> u32 {
+        100
+    }
+
+    pub fn build(self) -> Result<History, rusqlite::Error> {
+        tracing::info!("opening history at {:?}", self.path);
+        let connection = if self.path.as_os_str() == ":memory:" {
+            Connection::open_in_memory()?
+        } else {
+            Connection::open(&self.path)?
+        };
+        connection.execute_batch(SCHEMA)?;
+        Ok(History {
+            inner: Some(Arc::new(Store {
+                connection: Mutex::new(connection),
+                retention: Duration::days(self.retention_days as i64),
+                max_entries: self.max_entries,
+                search_limit: self.search_limit,
+            })),
+        })
+    }
+}
+
+/// Key used to deduplicate the entries, the info hash when the magnet has one.
+fn entry_key(entry: &IndexerEntry) -> String {
+    entry
+        .info_hash
+        .clone()
+        .or_else(|| info_hash(&entry.magnet))
+        .unwrap_or_else(|| entry.url.clone())
+}
+
+/// Turns free text into a fts5 query where every word is required.
+fn fts_query(input: &str) -> Option<String> {
+    let words = input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{word}\""))
+        .collect::<Vec<_>>();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+#[derive(Debug)]
+struct Store {
+    connection: Mutex<Connection>,
+    retention: Duration,
+    max_entries: u32,
+    search_limit: u32,
+}
+
+impl Store {
+    fn record(&self, entries: &[IndexerEntry]) -> Result<(), rusqlite::Error> {
+        let now = Utc::now();
+        let mut connection = self.connection.lock().expect("history lock poisoned");
+        let tx = connection.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                r#"INSERT INTO entries (key, name, url, date, size, seeders, leechers, magnet, origin, deferred_indexer, deferred_token, seen_at)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+ON CONFLICT (key) DO UPDATE SET
+    seeders = excluded.seeders,
+    leechers = excluded.leechers,
+    seen_at = excluded.seen_at"#,
+            )?;
+            for entry in entries.iter().filter(|entry| entry.origin != NAME) {
+                stmt.execute(params![
+                    entry_key(entry),
+                    entry.name,
+                    entry.url,
+                    entry.date.map(|date| date.timestamp()),
+                    entry.size.as_u64() as i64,
+                    entry.seeders as i64,
+                    entry.leechers as i64,
+                    entry.magnet,
+                    entry.origin,
+                    entry.deferred.as_ref().map(|item| item.indexer.as_str()),
+                    entry.deferred.as_ref().map(|item| item.token.as_str()),
+                    now.timestamp(),
+                ])?;
+            }
+        }
+        tx.execute(
+            "DELETE FROM entries WHERE seen_at < ?1",
+            params![(now - self.retention).timestamp()],
+        )?;
+        tx.execute(
+            "DELETE FROM entries WHERE key IN (SELECT key FROM entries ORDER BY seen_at DESC LIMIT -1 OFFSET ?1)",
+            params![self.max_entries],
+        )?;
+        tx.commit()
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<IndexerEntry>, rusqlite::Error> {
+        let Some(query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let connection = self.connection.lock().expect("history lock poisoned");
+        let mut stmt = connection.prepare_cached(
+            r#"SELECT entries.name, entries.url, entries.date, entries.size, entries.seeders, entries.leechers, entries.magnet, entries.deferred_indexer, entries.deferred_token
+FROM entries_fts
+JOIN entries ON entries.rowid = entries_fts.rowid
+WHERE entries_fts MATCH ?1
+ORDER BY rank
+LIMIT ?2"#,
+        )?;
+        let rows = stmt.query_map(params![query, self.search_limit], |row| {
+            let deferred = match (row.get(7)?, row.get(8)?) {
+                (Some(indexer), Some(token)) => Some(DeferredLink { indexer, token }),
+                _ => None,
+            };
+            let name: String = row.get(0)?;
+            let magnet: String = row.get(6)?;
+            Ok(IndexerEntry {
+                release: release::parse(&name),
+                name,
+                url: row.get(1)?,
+                date: row
+                    .get::<_, Option<i64>>(2)?
+                    .and_then(|date| Utc.timestamp_opt(date, 0).single()),
+                size: ByteSize::b(row.get::<_, i64>(3)? as u64),
+                seeders: row.get::<_, i64>(4)? as usize,
+                leechers: row.get::<_, i64>(5)? as usize,
+                info_hash: info_hash(&magnet),
+                magnet,
+                origin: NAME,
+                uploader: None,
+                deferred,
+                duplicates: Vec::new(),
+                files: Vec::new(),
+                suspicious: Vec::new(),
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+/// Keeps track of every entry seen so that they can still be found once they
+/// disappeared from the indexers.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    inner: Option<Arc<Store>>,
+}
+
+impl History {
+    pub async fn record(&self, entries: &[IndexerEntry]) {
+        let Some(store) = self.inner.clone() else {
+            return;
+        };
+        if entries.is_empty() {
+            return;
+        }
+        let entries = entries.to_vec();
+        let result = tokio::task::spawn_blocking(move || store.record(&entries)).await;
+        match result {
+            Ok(Err(error)) => tracing::warn!("unable to record history: {error:?}"),
+            Err(error) => tracing::warn!("unable to record history: {error:?}"),
+            Ok(Ok(())) => {}
+        }
+    }
+
+    pub async fn search(&self, query: &str) -> Vec<IndexerEntry> {
+        let Some(store) = self.inner.clone() else {
+            return Vec::new();
+        };
+        let query = query.to_string();
+        match tokio::task::spawn_blocking(move || store.search(&query)).await {
+            Ok(Ok(found)) => found,
+            Ok(Err(error)) => {
+                tracing::warn!("unable to search history: {error:?}");
+                Vec::new()
+            }
+            Err(error) => {
+                tracing::warn!("unable to search history: {error:?}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Appends the entries from the history that are not already in the live results.
+    pub async fn complete(&self, query: &str, entries: &mut Vec<IndexerEntry>) {
+        let found = self.search(query).await;
+        if found.is_empty() {
+            return;
+        }
+        let known = entries.iter().map(entry_key).collect::<HashSet<_>>();
+        let before = entries.len();
+        entries.extend(
+            found
+                .into_iter()
+                .filter(|entry| !known.contains(&entry_key(entry))),
+        );
+        tracing::debug!("added {} entries from history", entries.len() - before);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HistoryConfig, NAME};
+    use manteau_indexer_manager::testing::{entry, EntryBuilder};
+    use manteau_indexer_prelude::bytesize::ByteSize;
+
+    fn history(max_entries: u32) -> super::History {
+        HistoryConfig {
+            path: ":memory:".into(),
+            retention_days: 30,
+            max_entries,
+            search_limit: 100,
+        }
+        .build()
+        .unwrap()
+    }
+
+    fn hashed(name: &str, hash: &str) -> EntryBuilder {
+        entry(name)
+            .size(ByteSize::mb(120))
+            .magnet(format!("magnet:?xt=urn:btih:{hash}&dn={name}"))
+    }
+
+    #[tokio::test]
+    async fn should_search_recorded_entries() {
+        let history = history(100);
+        history
+            .record(&[
+                hashed("How.I.Met.Your.Mother.S01E01", &"A".repeat(40))
+                    .seeders(10)
+                    .build(),
+                hashed("How.I.Met.Your.Mother.S01E02", &"B".repeat(40))
+                    .seeders(10)
+                    .build(),
+                hashed("Dark.S01E01", &"C".repeat(40)).seeders(10).build(),
+            ])
+            .await;
+
+        let found = history.search("how i met your mother").await;
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|item| item.origin == NAME));
+
+        let found = history.search("dark").await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "Dark.S01E01");
+    }
+
+    #[tokio::test]
+    async fn should_deduplicate_by_info_hash() {
+        let history = history(100);
+        history
+            .record(&[
+                hashed("Dark.S01E01", "abcdef0123456789abcdef0123456789abcdef01")
+                    .seeders(10)
+                    .build(),
+            ])
+            .await;
+        history
+            .record(&[
+                hashed("Dark.S01E01", "ABCDEF0123456789ABCDEF0123456789ABCDEF01")
+                    .seeders(42)
+                    .build(),
+            ])
+            .await;
+
+        let found = history.search("dark").await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].seeders, 42);
+    }
+
+    #[tokio::test]
+    async fn should_respect_max_entries() {
+        let history = history(2);
+        history
+            .record(&[
+                hashed("Dark.S01E01", &"A".repeat(40)).seeders(10).build(),
+                hashed("Dark.S01E02", &"B".repeat(40)).seeders(10).build(),
+                hashed("Dark.S01E03", &"C".repeat(40)).seeders(10).build(),
+            ])
+            .await;
+
+        assert_eq!(history.search("dark").await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_complete_live_results() {
+        let history = history(100);
+        history
+            .record(&[
+                hashed("Dark.S01E01", &"A".repeat(40)).seeders(10).build(),
+                hashed("Dark.S01E02", &"B".repeat(40)).seeders(10).build(),
+            ])
+            .await;
+
+        let mut entries = vec![hashed("Dark.S01E01", &"A".repeat(40)).seeders(12).build()];
+        history.complete("dark", &mut entries).await;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].origin, "example");
+        assert_eq!(entries[1].origin, NAME);
+        assert_eq!(entries[1].name, "Dark.S01E02");
+    }
+
+    #[tokio::test]
+    async fn should_do_nothing_when_disabled() {
+        let history = super::History::default();
+        history
+            .record(&[hashed("Dark.S01E01", &"A".repeat(40)).seeders(10).build()])
+            .await;
+        assert!(history.search("dark").await.is_empty());
+    }
+}
@@ -1,2 +1,5 @@
+pub mod access;
 pub mod cache;
+pub mod history;
+pub mod scheduler;
 pub mod torznab;
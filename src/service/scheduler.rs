@@ -0,0 +1,178 @@
+use crate::service::history::History;
+use manteau_indexer_manager::IndexerManager;
+use manteau_indexer_prelude::Category;
+use rand::Rng;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct SchedulerConfig {
+    #[serde(default)]
+    pub jobs: Vec<JobConfig>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct JobConfig {
+    /// Names of the indexers to refresh, all of them when empty.
+    #[serde(default)]
+    pub indexers: Vec<String>,
+    pub categories: Vec<Category>,
+    /// Delay between two refreshes, in seconds.
+    pub interval: u64,
+    /// Random delay added to the interval, in seconds.
+    #[serde(default = "JobConfig::default_jitter")]
+    pub jitter: u64,
+}
+
+impl JobConfig {
+    fn default_jitter() -> u64 {
+        30
+    }
+}
+
+impl SchedulerConfig {
+    pub fn build(self) -> Scheduler {
+        Scheduler { jobs: self.jobs }
+    }
+}
+
+#[derive(Debug)]
+struct Task {
+    indexer: String,
+    categories: Vec<Category>,
+    interval: Duration,
+    jitter: Duration,
+}
+
+impl Task {
+    fn random_jitter(&self) -> Duration {
+        if self.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            rand::thread_rng().gen_range(Duration::ZERO..self.jitter)
+        }
+    }
+
+    async fn run(self, history: History, manager: Arc<IndexerManager>) {
+        // a missed refresh should not make the feed cold
+        let valid_for = (self.interval + self.jitter) * 2;
+        tokio::time::sleep(self.random_jitter()).await;
+        loop {
+            for category in self.categories.iter() {
+                tracing::debug!("prefetching {category:?} feed of {:?}", self.indexer);
+                if let Some(result) = manager.prefetch(&self.indexer, *category, valid_for).await {
+                    if !result.errors.is_empty() {
+                        tracing::debug!("had the following errors: {:?}", result.errors);
+                    }
+                    history.record(&result.entries).await;
+                }
+            }
+            tokio::time::sleep(self.interval + self.random_jitter()).await;
+        }
+    }
+}
+
+/// Keeps the feeds of the indexers warm by refreshing them periodically.
+#[derive(Debug)]
+pub struct Scheduler {
+    jobs: Vec<JobConfig>,
+}
+
+impl Scheduler {
+    fn tasks(&self, manager: &IndexerManager) -> Vec<Task> {
+        let mut tasks = Vec::new();
+        for job in self.jobs.iter() {
+            let names: Vec<String> = if job.indexers.is_empty() {
+                manager.indexer_names().map(String::from).collect()
+            } else {
+                job.indexers.clone()
+            };
+            for name in names {
+                let Some(min_interval) = manager.feed_interval(&name) else {
+                    tracing::warn!("unable to schedule unknown indexer {name:?}");
+                    continue;
+                };
+                let interval = Duration::from_secs(job.interval);
+                if interval < min_interval {
+                    tracing::warn!(
+                        "interval for {name:?} raised to {}s to respect the site",
+                        min_interval.as_secs()
+                    );
+                }
+                tasks.push(Task {
+                    indexer: name,
+                    categories: job.categories.clone(),
+                    interval: interval.max(min_interval),
+                    jitter: Duration::from_secs(job.jitter),
+                });
+            }
+        }
+        tasks
+    }
+
+    pub fn start(self, history: History, manager: Arc<IndexerManager>) -> Vec<JoinHandle<()>> {
+        self.tasks(&manager)
+            .into_iter()
+            .map(|task| {
+                tracing::info!(
+                    "scheduling {:?} every {}s",
+                    task.indexer,
+                    task.interval.as_secs()
+                );
+                tokio::spawn(task.run(history.clone(), manager.clone()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(all(
+        feature = "indexer-1337x",
+        feature = "indexer-bitsearch",
+        feature = "indexer-thepiratebay"
+    ))]
+    fn should_respect_feed_interval() {
+        use super::SchedulerConfig;
+        use manteau_indexer_manager::IndexerManager;
+        use std::time::Duration;
+
+        let config: SchedulerConfig = toml::from_str(
+            r#"
+[[jobs]]
+categories = [2000, "5000"]
+interval = 10
+"#,
+        )
+        .unwrap();
+        let manager = IndexerManager::default();
+        let tasks = config.build().tasks(&manager);
+        assert_eq!(tasks.len(), 3);
+        let i1337x = tasks.iter().find(|task| task.indexer == "1337x").unwrap();
+        assert_eq!(i1337x.interval, Duration::from_secs(900));
+        assert_eq!(i1337x.categories.len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "indexer-bitsearch")]
+    fn should_skip_unknown_indexers() {
+        use super::SchedulerConfig;
+        use manteau_indexer_manager::IndexerManager;
+        use std::time::Duration;
+
+        let config: SchedulerConfig = toml::from_str(
+            r#"
+[[jobs]]
+indexers = ["bitsearch", "unknown"]
+categories = [2000]
+interval = 600
+"#,
+        )
+        .unwrap();
+        let tasks = config.build().tasks(&IndexerManager::default());
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].interval, Duration::from_secs(600));
+    }
+}
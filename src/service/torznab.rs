@@ -1,4 +1,5 @@
-use manteau_indexer_prelude::{Category, IndexerEntry};
+use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode};
+use manteau_indexer_prelude::{Category, IndexerEntry, DEFAULT_LIMIT};
 use quick_xml::events::BytesText;
 use quick_xml::writer::Writer;
 use quick_xml::Result;
@@ -69,21 +70,21 @@ impl Default for TorznabBuilder {
 
 // Capabilities
 impl TorznabBuilder {
-    pub fn capabilities(&self) -> String {
+    pub fn capabilities(&self, caps: &Capabilities) -> String {
         let mut writer = Writer::new(Vec::new());
-        self.write_caps(&mut writer)
+        self.write_caps(&mut writer, caps)
             .expect("build capabilities xml");
         let inner = writer.into_inner();
         let result = String::from_utf8_lossy(&inner);
         format!("{}{result}", DOM)
     }
 
-    fn write_caps(&self, writer: &mut Writer<Vec<u8>>) -> Result<()> {
+    fn write_caps(&self, writer: &mut Writer<Vec<u8>>, caps: &Capabilities) -> Result<()> {
         writer.create_element("caps").write_inner_content(|w| {
             self.write_server(w)?;
-            self.write_limits(w)?;
-            self.write_searching(w)?;
-            self.write_categories(w)?;
+            self.write_limits(w, caps)?;
+            self.write_searching(w, caps)?;
+            self.write_categories(w, caps)?;
             Ok(())
         })?;
         Ok(())
@@ -96,70 +97,82 @@ impl TorznabBuilder {
         Ok(())
     }
 
-    fn write_limits(&self, writer: &mut Writer<Vec<u8>>) -> Result<()> {
+    fn write_limits(&self, writer: &mut Writer<Vec<u8>>, caps: &Capabilities) -> Result<()> {
+        let limit = DEFAULT_LIMIT.to_string();
         writer
             .create_element("limits")
-            .with_attribute(("default", "100"))
-            .with_attribute(("max", "100"))
+            .with_attribute(("default", limit.as_str()))
+            .with_attribute(("max", limit.as_str()))
+            .write_empty()?;
+        // without it, the next pages only come from what the first ones had in excess
+        writer
+            .create_element("paging")
+            .with_attribute(("available", if caps.paging { "yes" } else { "no" }))
             .write_empty()?;
         Ok(())
     }
 
-    fn write_searching(&self, writer: &mut Writer<Vec<u8>>) -> Result<()> {
+    fn write_searching(&self, writer: &mut Writer<Vec<u8>>, caps: &Capabilities) -> Result<()> {
         writer
             .create_element("searching")
             .write_inner_content(|w| {
-                w.create_element("search")
-                    .with_attribute(("available", "yes"))
-                    .with_attribute(("supportedParams", "q"))
-                    .write_empty()?;
-                w.create_element("tv-search")
-                    .with_attribute(("available", "yes"))
-                    .with_attribute(("supportedParams", "q,season,ep"))
-                    .write_empty()?;
-                w.create_element("movie-search")
-                    .with_attribute(("available", "yes"))
-                    .with_attribute(("supportedParams", "q"))
-                    .write_empty()?;
-                w.create_element("music-search")
-                    .with_attribute(("available", "yes"))
-                    .with_attribute(("supportedParams", "q"))
-                    .write_empty()?;
-                w.create_element("book-search")
-                    .with_attribute(("available", "yes"))
-                    .with_attribute(("supportedParams", "q"))
-                    .write_empty()?;
+                for mode in SearchMode::ALL {
+                    let params = caps
+                        .searching
+                        .get(&mode)
+                        .map(|params| {
+                            params
+                                .iter()
+                                .map(|param| param.name())
+                                .collect::<Vec<_>>()
+                                .join(",")
+                        })
+                        .unwrap_or_default();
+                    let available = if caps.searching.contains_key(&mode) {
+                        "yes"
+                    } else {
+                        "no"
+                    };
+                    w.create_element(mode.name())
+                        .with_attribute(("available", available))
+                        .with_attribute(("supportedParams", params.as_str()))
+                        .write_empty()?;
+                }
                 Ok(())
             })?;
         Ok(())
     }
 
-    fn write_categories(&self, writer: &mut Writer<Vec<u8>>) -> Result<()> {
+    fn write_categories(&self, writer: &mut Writer<Vec<u8>>, caps: &Capabilities) -> Result<()> {
         writer
             .create_element("categories")
             .write_inner_content(|w| {
-                w.create_element("category")
-                    .with_attribute(("id", "2000"))
-                    .with_attribute(("name", "Movies"))
-                    .write_empty()?;
-                w.create_element("category")
-                    .with_attribute(("id", "3000"))
-                    .with_attribute(("name", "Audio"))
-                    .write_empty()?;
-                w.create_element("category")
-                    .with_attribute(("id", "5000"))
-                    .with_attribute(("name", "TV"))
-                    .write_empty()?;
-                w.create_element("category")
-                    .with_attribute(("id", "7000"))
-                    .with_attribute(("name", "Books"))
-                    .write_empty()?;
+                for category in caps.newznab_categories() {
+                    w.create_element("category")
+                        .with_attribute(("id", category.kind_str()))
+                        .with_attribute(("name", category.name()))
+                        .write_empty()?;
+                }
                 Ok(())
             })?;
         Ok(())
     }
 }
 
+/// Error as described by the newznab specification.
+pub fn error(code: u16, description: &str) -> String {
+    let mut writer = Writer::new(Vec::new());
+    writer
+        .create_element("error")
+        .with_attribute(("code", code.to_string().as_str()))
+        .with_attribute(("description", description))
+        .write_empty()
+        .expect("build error xml");
+    let inner = writer.into_inner();
+    let result = String::from_utf8_lossy(&inner);
+    format!("{}{result}", DOM)
+}
+
 // Feed
 impl TorznabBuilder {
     pub fn feed(&self, category: Category, entries: &[IndexerEntry]) -> String {
@@ -216,6 +229,50 @@ impl TorznabBuilder {
         Ok(())
     }
 
+    /// Link to download the entry, going through manteau when the magnet is deferred.
+    pub fn link<'a>(&self, item: &'a IndexerEntry) -> Cow<'a, str> {
+        match item.deferred {
+            Some(ref deferred) => Cow::Owned(format!(
+                "{}/dl/{}/{}",
+                self.base_url,
+                urlencoding::encode(&deferred.indexer),
+                urlencoding::encode(&deferred.token)
+            )),
+            None => Cow::Borrowed(item.magnet.as_str()),
+        }
+    }
+
+    /// Attributes known from the release name.
+    fn release_attributes(item: &IndexerEntry) -> Vec<(&'static str, String)> {
+        let release = &item.release;
+        let episodes = release.episodes.as_ref();
+        [
+            ("season", episodes.map(|found| found.season.to_string())),
+            (
+                "episode",
+                episodes
+                    .and_then(|found| found.episodes)
+                    .map(|(first, _)| first.to_string()),
+            ),
+            ("year", release.year.map(|year| year.to_string())),
+            ("resolution", release.resolution.map(String::from)),
+            ("video", release.codec.map(String::from)),
+            ("source", release.source.map(String::from)),
+            ("hdr", release.hdr.map(String::from)),
+            ("audio", release.audio.map(String::from)),
+            ("language", release.language.map(String::from)),
+            ("team", release.group.clone()),
+            ("poster", item.uploader.clone()),
+            (
+                "tag",
+                (!item.suspicious.is_empty()).then(|| String::from("suspicious")),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+
     fn write_item(
         &self,
         writer: &mut Writer<Vec<u8>>,
@@ -223,6 +280,7 @@ impl TorznabBuilder {
         item: &IndexerEntry,
     ) -> quick_xml::Result<()> {
         tracing::trace!("writing item {:?}", item.name);
+        let link = self.link(item);
         writer.create_element("item").write_inner_content(|w| {
             w.create_element("title")
                 .write_text_content(BytesText::new(&item.name))?;
@@ -237,9 +295,9 @@ impl TorznabBuilder {
             w.create_element("size")
                 .write_text_content(BytesText::new(&item.size_str()))?;
             w.create_element("link")
-                .write_text_content(BytesText::new(&item.magnet))?;
+                .write_text_content(BytesText::new(&link))?;
             w.create_element("enclosure")
-                .with_attribute(("url", item.magnet.as_str()))
+                .with_attribute(("url", link.as_ref()))
                 .with_attribute(("length", item.size_str().as_str()))
                 .with_attribute(("type", "application/x-bittorrent"))
                 .write_empty()?;
@@ -258,10 +316,13 @@ impl TorznabBuilder {
                 .with_attribute(("name", "uploadvolumefactor"))
                 .with_attribute(("value", "1"))
                 .write_empty()?;
-            w.create_element("torznab:attr")
-                .with_attribute(("name", "magneturl"))
-                .with_attribute(("value", item.magnet.as_str()))
-                .write_empty()?;
+            // the feeds only giving the link to a .torrent or nzb file
+            if item.magnet.starts_with("magnet:") {
+                w.create_element("torznab:attr")
+                    .with_attribute(("name", "magneturl"))
+                    .with_attribute(("value", item.magnet.as_str()))
+                    .write_empty()?;
+            }
             w.create_element("torznab:attr")
                 .with_attribute(("name", "category"))
                 .with_attribute(("value", category.kind_str()))
@@ -274,8 +335,131 @@ impl TorznabBuilder {
                 .with_attribute(("name", "peers"))
                 .with_attribute(("value", (item.leechers + item.seeders).to_string().as_str()))
                 .write_empty()?;
+            for (name, value) in Self::release_attributes(item) {
+                w.create_element("torznab:attr")
+                    .with_attribute(("name", name))
+                    .with_attribute(("value", value.as_str()))
+                    .write_empty()?;
+            }
             Ok(())
         })?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TorznabConfig;
+    use manteau_indexer_helper::release;
+    use manteau_indexer_prelude::bytesize::ByteSize;
+    use manteau_indexer_prelude::capabilities::{Capabilities, SearchMode, SearchParam};
+    use manteau_indexer_prelude::{Category, DeferredLink, IndexerEntry};
+
+    #[test]
+    fn should_link_deferred_entries_to_manteau() {
+        let torznab = TorznabConfig {
+            base_url: "http://manteau:3000".into(),
+            ..Default::default()
+        }
+        .build();
+        let entry = IndexerEntry {
+            size: ByteSize::mb(120),
+            seeders: 10,
+            leechers: 20,
+            deferred: Some(DeferredLink {
+                indexer: "1337x".into(),
+                token: "/torrent/42/foo/".into(),
+            }),
+            ..IndexerEntry::new("fake", "foo", "https://example.com/foo")
+        };
+        let body = torznab.feed(Category::Movie, &[entry]);
+        assert!(body.contains("<link>http://manteau:3000/dl/1337x/%2Ftorrent%2F42%2Ffoo%2F</link>"));
+        assert!(!body.contains("magneturl"));
+    }
+
+    #[test]
+    fn should_only_write_magnet_links_as_magneturl() {
+        let torznab = TorznabConfig::default().build();
+        let entry = IndexerEntry {
+            size: ByteSize::mb(120),
+            magnet: "https://example.com/foo.torrent".into(),
+            ..IndexerEntry::new("fake", "foo", "https://example.com/foo")
+        };
+        let body = torznab.feed(Category::Movie, &[entry]);
+        assert!(body.contains("<link>https://example.com/foo.torrent</link>"));
+        assert!(!body.contains("magneturl"));
+
+        let entry = IndexerEntry {
+            size: ByteSize::mb(120),
+            magnet: "magnet:?xt=urn:btih:foo".into(),
+            ..IndexerEntry::new("fake", "foo", "https://example.com/foo")
+        };
+        let body = torznab.feed(Category::Movie, &[entry]);
+        assert!(
+            body.contains(r#"<torznab:attr name="magneturl" value="magnet:?xt=urn:btih:foo"/>"#)
+        );
+    }
+
+    #[test]
+    fn should_write_release_attributes() {
+        let torznab = TorznabConfig::default().build();
+        let name = "Dark.S01E02.1080p.WEB.HDR.x264-GROUP";
+        let entry = IndexerEntry {
+            size: ByteSize::mb(120),
+            seeders: 10,
+            leechers: 20,
+            magnet: "magnet:?xt=urn:btih:foo".into(),
+            release: release::parse(name),
+            ..IndexerEntry::new("fake", name, "https://example.com/foo")
+        };
+        let body = torznab.feed(Category::Tv, &[entry]);
+        assert!(body.contains(r#"<torznab:attr name="season" value="1"/>"#));
+        assert!(body.contains(r#"<torznab:attr name="episode" value="2"/>"#));
+        assert!(body.contains(r#"<torznab:attr name="resolution" value="1080p"/>"#));
+        assert!(body.contains(r#"<torznab:attr name="video" value="x264"/>"#));
+        assert!(body.contains(r#"<torznab:attr name="source" value="WEB"/>"#));
+        assert!(body.contains(r#"<torznab:attr name="hdr" value="HDR"/>"#));
+        assert!(body.contains(r#"<torznab:attr name="team" value="GROUP"/>"#));
+        assert!(!body.contains(r#"name="year""#));
+        assert!(!body.contains(r#"name="tag""#));
+    }
+
+    #[test]
+    fn should_tag_suspicious_entries() {
+        let torznab = TorznabConfig::default().build();
+        let entry = IndexerEntry {
+            size: ByteSize::mb(120),
+            seeders: 10,
+            leechers: 20,
+            magnet: "magnet:?xt=urn:btih:foo".into(),
+            uploader: Some("someone".into()),
+            suspicious: vec!["executable"],
+            ..IndexerEntry::new("fake", "Dune.2021.2160p.exe", "https://example.com/foo")
+        };
+        let body = torznab.feed(Category::Movie, &[entry]);
+        assert!(body.contains(r#"<torznab:attr name="poster" value="someone"/>"#));
+        assert!(body.contains(r#"<torznab:attr name="tag" value="suspicious"/>"#));
+    }
+
+    #[test]
+    fn should_write_declared_capabilities() {
+        let torznab = TorznabConfig::default().build();
+        let caps = Capabilities::default()
+            .with_search(
+                SearchMode::Search,
+                &[SearchParam::Query, SearchParam::ImdbId],
+            )
+            .with_category("HD Movies", Category::Movie)
+            .with_category("Movies", Category::Movie)
+            .with_category("TV", Category::Tv);
+        let body = torznab.capabilities(&caps);
+        assert!(body.contains(r#"<search available="yes" supportedParams="q,imdbid"/>"#));
+        assert!(body.contains(r#"<book-search available="no" supportedParams=""/>"#));
+        assert!(body.contains(r#"<paging available="no"/>"#));
+        let body = torznab.capabilities(&caps.with_paging(true));
+        assert!(body.contains(r#"<limits default="100" max="100"/><paging available="yes"/>"#));
+        assert!(body.contains(
+            r#"<categories><category id="2000" name="Movies"/><category id="5000" name="TV"/></categories>"#
+        ));
+    }
+}